@@ -0,0 +1,107 @@
+use crate::ceos::buffer::buffer::Buffer;
+use crate::ceos::command;
+use crate::ceos::progress_manager::{ProgressManager, loading_id, new_loading, operation_label};
+use crate::ceos::tools::cancellation::CancellationToken;
+use crate::cli::Arguments;
+use crate::event::Event;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Write};
+use std::sync::mpsc::{Receiver, channel};
+use std::thread;
+
+/// The progress is reported on stderr every PROGRESS_STEP percents
+const PROGRESS_STEP: usize = 10;
+
+/// Apply the commands to the input file and write the result without opening a window
+pub(crate) fn run(arguments: &Arguments) -> Result<(), String> {
+    let [path] = arguments.files.as_slice() else {
+        return Err("The batch mode needs exactly one input file".to_string());
+    };
+    let commands = arguments
+        .commands
+        .iter()
+        .map(|text| command::parse_command(text).ok_or_else(|| format!("Unknown command '{text}'")))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let (sender, receiver) = channel();
+    let progress = thread::spawn(move || report_progress(receiver));
+    // nothing cancels the batch mode, it is stopped with the process
+    let cancellation = CancellationToken::default();
+    let loading = new_loading();
+    let mut buffer =
+        Buffer::new_from_file(path.clone(), loading, arguments.encoding, sender, &cancellation)
+            .map_err(|e| format!("Unable to read {path:?}: {e}"))?;
+    eprintln!("Loaded {} lines from {path:?}", buffer.line_count());
+    for command in commands {
+        command.execute(&mut buffer, &cancellation);
+        eprintln!("{command}: {} lines", buffer.line_count());
+    }
+
+    let result = match &arguments.output {
+        Some(output) => File::create(output)
+            .and_then(|file| write(&buffer, file))
+            .map_err(|e| format!("Unable to write {output:?}: {e}")),
+        None => write(&buffer, io::stdout().lock())
+            .map_err(|e| format!("Unable to write the result: {e}")),
+    };
+    // dropping the buffer closes the event channel
+    drop(buffer);
+    let _ = progress.join();
+    result
+}
+
+fn write(buffer: &Buffer, writer: impl Write) -> io::Result<()> {
+    let mut writer = BufWriter::new(writer);
+    buffer.write_to(&mut writer)?;
+    writer.flush()
+}
+
+/// Print the loading and operations progress on stderr until the channel is closed
+fn report_progress(receiver: Receiver<Event>) {
+    let mut progress_manager = ProgressManager::default();
+    // the last step reported for each operation
+    let mut reported: HashMap<String, usize> = HashMap::new();
+    for event in receiver {
+        let id = match event {
+            Event::BufferLoadingStarted(loading, path, size, cancellation) => {
+                let id = loading_id(loading);
+                let label = format!("Loading {path:?}");
+                progress_manager.add(id.clone(), label, size, Some(cancellation));
+                id
+            }
+            Event::BufferLoading(loading, current, _) => {
+                let id = loading_id(loading);
+                progress_manager.update(&id, current);
+                id
+            }
+            Event::OperationStarted(id, length, cancellation) => {
+                let label = operation_label(&id).to_owned();
+                progress_manager.add(id.clone(), label, length, cancellation);
+                id
+            }
+            Event::OperationProgress(id, value) => {
+                progress_manager.update(&id, value);
+                id
+            }
+            Event::OperationIncrement(id, amount) => {
+                progress_manager.increment(&id, amount);
+                id
+            }
+            Event::OperationFinished(id) => {
+                progress_manager.remove(&id);
+                reported.remove(&id);
+                continue;
+            }
+            _ => continue,
+        };
+        if let Some(progress) = progress_manager.progress.get(&id) {
+            let percent = (progress.percent() * 100.0) as usize;
+            let step = percent / PROGRESS_STEP;
+            if reported.insert(id, step) != Some(step) {
+                eprintln!("{} {percent}%", progress.label);
+            }
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use crate::ceos::tools::compression::Compression;
+use log::info;
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+/// The magic of an empty zip archive
+const EMPTY_ZIP_MAGIC: &[u8] = b"PK\x05\x06";
+/// The tar header is 512 bytes long with the ustar magic at offset 257
+const TAR_HEADER_LENGTH: usize = 512;
+const TAR_MAGIC_OFFSET: usize = 257;
+const TAR_MAGIC: &[u8] = b"ustar";
+
+/// The archives whose entries can be opened, a tar archive can be compressed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Archive {
+    Zip,
+    Tar(Compression),
+}
+
+/// A file stored in an archive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ArchiveEntry {
+    /// The path of the file relative to the archive
+    pub(crate) name: String,
+    /// The uncompressed size
+    pub(crate) size: usize,
+}
+
+impl Archive {
+    /// Returns the kind of archive of the file, None if it is not an archive
+    pub(crate) fn detect(path: &Path) -> io::Result<Option<Self>> {
+        let mut file = File::open(path)?;
+        let mut magic = Vec::with_capacity(ZIP_MAGIC.len());
+        (&mut file)
+            .take(ZIP_MAGIC.len() as u64)
+            .read_to_end(&mut magic)?;
+        if magic == ZIP_MAGIC || magic == EMPTY_ZIP_MAGIC {
+            return Ok(Some(Archive::Zip));
+        }
+
+        let mut file = BufReader::new(File::open(path)?);
+        let compression = Compression::detect(file.get_mut());
+        let mut header = Vec::with_capacity(TAR_HEADER_LENGTH);
+        // a compressed file that is not valid is not an archive, the loading will report it
+        let _ = compression
+            .decoder(file)?
+            .take(TAR_HEADER_LENGTH as u64)
+            .read_to_end(&mut header);
+        let is_tar = header
+            .get(TAR_MAGIC_OFFSET..TAR_MAGIC_OFFSET + TAR_MAGIC.len())
+            .is_some_and(|magic| magic == TAR_MAGIC);
+        Ok(is_tar.then_some(Archive::Tar(compression)))
+    }
+
+    /// List the files of the archive, the directories are skipped
+    pub(crate) fn entries(self, path: &Path) -> io::Result<Vec<ArchiveEntry>> {
+        let mut entries = Vec::new();
+        match self {
+            Archive::Zip => {
+                let mut zip = zip::ZipArchive::new(File::open(path)?)?;
+                for i in 0..zip.len() {
+                    let file = zip.by_index_raw(i)?;
+                    if file.is_file() {
+                        entries.push(ArchiveEntry {
+                            name: file.name().to_owned(),
+                            size: file.size() as usize,
+                        });
+                    }
+                }
+            }
+            Archive::Tar(compression) => {
+                let mut tar = Self::open_tar(path, compression)?;
+                for entry in tar.entries()? {
+                    let entry = entry?;
+                    if entry.header().entry_type().is_file() {
+                        entries.push(ArchiveEntry {
+                            name: entry.path()?.to_string_lossy().into_owned(),
+                            size: entry.size() as usize,
+                        });
+                    }
+                }
+            }
+        }
+        info!("{path:?} contains {} files", entries.len());
+        Ok(entries)
+    }
+
+    /// Read the given entries one after the other in a single pass over the archive,
+    /// the archive is not read further than the last of them
+    pub(crate) fn read_entries(
+        self,
+        path: &Path,
+        names: &[String],
+        mut read: impl FnMut(&str, &mut dyn BufRead) -> io::Result<()>,
+    ) -> io::Result<()> {
+        match self {
+            Archive::Zip => {
+                // the central directory gives the position of each entry
+                let mut zip = zip::ZipArchive::new(File::open(path)?)?;
+                for name in names {
+                    read(name, &mut BufReader::new(zip.by_name(name)?))?;
+                }
+            }
+            Archive::Tar(compression) => {
+                let mut remaining: Vec<&String> = names.iter().collect();
+                let mut tar = Self::open_tar(path, compression)?;
+                for entry in tar.entries()? {
+                    if remaining.is_empty() {
+                        break;
+                    }
+                    let entry = entry?;
+                    let name = entry.path()?.to_string_lossy().into_owned();
+                    if let Some(index) = remaining.iter().position(|wanted| **wanted == name) {
+                        remaining.swap_remove(index);
+                        read(&name, &mut BufReader::new(entry))?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn open_tar(
+        path: &Path,
+        compression: Compression,
+    ) -> io::Result<tar::Archive<Box<dyn Read + Send>>> {
+        let reader = compression.decoder(BufReader::new(File::open(path)?))?;
+        Ok(tar::Archive::new(reader))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    const ENTRIES: [(&str, &str); 2] = [("logs/a.log", "a1\na2\n"), ("logs/b.log", "b1\n")];
+
+    fn write_zip(path: &Path) -> io::Result<()> {
+        let mut zip = zip::ZipWriter::new(File::create(path)?);
+        zip.add_directory("logs/", zip::write::SimpleFileOptions::default())?;
+        for (name, content) in ENTRIES {
+            zip.start_file(name, zip::write::SimpleFileOptions::default())?;
+            zip.write_all(content.as_bytes())?;
+        }
+        zip.finish()?;
+        Ok(())
+    }
+
+    fn write_tar_gz(path: &Path) -> io::Result<()> {
+        let encoder =
+            flate2::write::GzEncoder::new(File::create(path)?, flate2::Compression::default());
+        let mut tar = tar::Builder::new(encoder);
+        for (name, content) in ENTRIES {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            tar.append_data(&mut header, name, content.as_bytes())?;
+        }
+        tar.into_inner()?.finish()?;
+        Ok(())
+    }
+
+    fn check_archive(path: &Path, expected: Archive) -> io::Result<()> {
+        let archive = Archive::detect(path)?;
+        assert_eq!(Some(expected), archive);
+        let entries = expected.entries(path)?;
+        assert_eq!(
+            vec![
+                ArchiveEntry {
+                    name: "logs/a.log".to_string(),
+                    size: 6
+                },
+                ArchiveEntry {
+                    name: "logs/b.log".to_string(),
+                    size: 3
+                },
+            ],
+            entries
+        );
+        let mut read = Vec::new();
+        expected.read_entries(path, &["logs/b.log".to_string()], |name, reader| {
+            let mut content = String::new();
+            reader.read_to_string(&mut content)?;
+            read.push((name.to_owned(), content));
+            Ok(())
+        })?;
+        assert_eq!(vec![("logs/b.log".to_string(), "b1\n".to_string())], read);
+        let mut read = Vec::new();
+        let names = ENTRIES.map(|(name, _)| name.to_string());
+        expected.read_entries(path, &names, |name, reader| {
+            let mut content = String::new();
+            reader.read_to_string(&mut content)?;
+            read.push((name.to_owned(), content));
+            Ok(())
+        })?;
+        assert_eq!(
+            ENTRIES
+                .map(|(name, content)| (name.to_string(), content.to_string()))
+                .to_vec(),
+            read
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_zip() -> io::Result<()> {
+        let path = std::env::temp_dir().join("ceos_archive_test.zip");
+        write_zip(&path)?;
+        let result = check_archive(&path, Archive::Zip);
+        std::fs::remove_file(&path)?;
+        result
+    }
+
+    #[test]
+    fn test_tar_gz() -> io::Result<()> {
+        let path = std::env::temp_dir().join("ceos_archive_test.tar.gz");
+        write_tar_gz(&path)?;
+        let result = check_archive(&path, Archive::Tar(Compression::Gzip));
+        std::fs::remove_file(&path)?;
+        result
+    }
+
+    #[test]
+    fn test_not_an_archive() -> io::Result<()> {
+        assert_eq!(None, Archive::detect(&PathBuf::from("Cargo.toml"))?);
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+use crate::ceos::buffer::buffer::Buffer;
+
+/// A decompressed group that may be evicted
+struct Candidate {
+    buffer: usize,
+    group: usize,
+    last_use: u64,
+    mem: usize,
+}
+
+/// Free the least recently used decompressed groups of the buffers until their lines fit in the
+/// budget. The groups used since `protected_from` are kept so the displayed lines are not
+/// decompressed again at each frame, as well as the last group of each buffer that receives the
+/// appended lines.
+/// Returns the memory used by the decompressed lines afterwards.
+pub(crate) fn enforce(buffers: &mut [&mut Buffer], budget: usize, protected_from: u64) -> usize {
+    let mut used: usize = buffers.iter().map(|buffer| buffer.decompressed_mem()).sum();
+    if used <= budget {
+        return used;
+    }
+    let mut candidates: Vec<Candidate> = buffers
+        .iter()
+        .enumerate()
+        .flat_map(|(buffer, b)| {
+            let groups = b.line_groups();
+            groups[..groups.len().saturating_sub(1)]
+                .iter()
+                .enumerate()
+                .filter(|(_, g)| g.is_decompressed() && g.last_use() < protected_from)
+                .map(move |(group, g)| Candidate {
+                    buffer,
+                    group,
+                    last_use: g.last_use(),
+                    mem: g.decompressed_mem(),
+                })
+        })
+        .collect();
+    candidates.sort_unstable_by_key(|candidate| candidate.last_use);
+    for candidate in candidates {
+        if used <= budget {
+            break;
+        }
+        buffers[candidate.buffer].evict_group(candidate.group);
+        used = used.saturating_sub(candidate.mem);
+    }
+    used
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ceos::buffer::line_group::LineGroup;
+
+    fn buffer(prefix: &str) -> Buffer {
+        let (sender, _) = std::sync::mpsc::channel();
+        let text: Vec<String> = (0..6).map(|i| format!("{prefix}{i}")).collect();
+        let mut buffer = Buffer::new_from_string(sender, &text.join("\n"), 2);
+        buffer.compress_all_groups();
+        buffer
+    }
+
+    #[test]
+    fn least_recently_used_groups_are_evicted() {
+        let mut first = buffer("a");
+        let mut second = buffer("b");
+        first.prepare_range_for_read(0..2);
+        second.prepare_range_for_read(0..2);
+        first.prepare_range_for_read(2..4);
+        let group_mem = first.line_groups()[0].decompressed_mem();
+        let protected_from = LineGroup::use_clock() + 1;
+
+        let used = enforce(
+            &mut [&mut first, &mut second],
+            2 * group_mem,
+            protected_from,
+        );
+
+        assert!(used <= 2 * group_mem);
+        assert!(!first.line_groups()[0].is_decompressed());
+        assert!(second.line_groups()[0].is_decompressed());
+        assert!(first.line_groups()[1].is_decompressed());
+        first.prepare_range_for_read(0..2);
+        assert_eq!(first.line_text(1), "a1");
+    }
+
+    #[test]
+    fn recently_used_groups_are_kept() {
+        let mut first = buffer("a");
+        let protected_from = LineGroup::use_clock();
+        first.prepare_range_for_read(0..4);
+
+        enforce(&mut [&mut first], 0, protected_from);
+
+        assert!(first.line_groups()[0].is_decompressed());
+        assert!(first.line_groups()[1].is_decompressed());
+    }
+}
@@ -1,25 +1,49 @@
+use crate::ceos::buffer::archive::{Archive, ArchiveEntry};
 use crate::ceos::buffer::line::Line;
 use crate::ceos::buffer::line_group::LineGroup;
+use crate::ceos::buffer::line_view::LineView;
+use crate::ceos::buffer::mapped;
+use crate::ceos::buffer::mapped::MappedFile;
+use crate::ceos::buffer::spill;
+use crate::ceos::buffer::spill::SpillFile;
 use crate::ceos::buffer::text_range::TextRange;
-use crate::ceos::tools::misc_tool::{gzip_uncompressed_size_fast, is_gzip};
+use crate::ceos::buffer::undo::BufferSnapshot;
+use crate::ceos::progress_manager::{loading_id, new_loading, operation_id};
+use crate::ceos::tools::cancellation::CancellationToken;
+use crate::ceos::tools::encoding::{Encoding, LineReader};
+use crate::ceos::tools::compression::Compression;
+use crate::ceos::tools::timestamp::{TimeBound, TimestampFormat};
 use crate::event::Event;
-use crate::event::Event::{BufferLoading, BufferLoadingStarted};
-use flate2::bufread::GzDecoder;
+use crate::event::Event::{
+    BufferGroupsLoaded, BufferLoading, BufferLoadingStarted, OperationFinished,
+};
 use rayon::prelude::*;
+use std::collections::HashSet;
 use std::fs::File;
 use std::io;
-use std::io::BufRead;
+use std::io::{BufRead, Read, Write};
 use std::ops::{Bound, Index, RangeBounds};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::sync::mpsc::Sender;
 use std::time::{Duration, Instant};
 use log::{info, warn};
 
 const DEFAULT_GROUP_SIZE: usize = 1000;
+/// The size of the blocks of a plain file read at once, the next block is read while the
+/// lines of the previous one are parsed
+const BATCH_SIZE: usize = 32 * 1024 * 1024;
+/// The size of the chunks of a block parsed and compressed by each thread
+const CHUNK_SIZE: usize = 1024 * 1024;
+/// The number of groups looked at for a timestamp before giving up, so the groups of a text
+/// without timestamps are not all decompressed
+const TIMESTAMP_LOOKUP: usize = 16;
 
 #[derive(Debug)]
 pub(crate) struct Buffer {
     pub(crate) path: Option<PathBuf>,
+    /// The name of the buffer if it is not the file name, like the path of an archive entry
+    pub(crate) name: Option<String>,
     /// the linegroups, the last one is never full. Eventually it is empty
     content: Vec<LineGroup>,
     length: usize,
@@ -27,6 +51,17 @@ pub(crate) struct Buffer {
     pub(crate) sender: Sender<Event>,
     /// The size of the groups used for line compression.
     group_size: usize,
+    /// The encoding of the file, used to save it
+    pub(crate) encoding: Encoding,
+    /// The number of complete groups already sent to the ui while the file is loaded
+    published_groups: usize,
+    /// The number of bytes of the file read by the loading, the followed lines are read after
+    /// them. None if the file was not loaded as plain text or was saved since.
+    pub(crate) loaded_size: Option<u64>,
+    /// The scratch file of the compressed lines moved out of memory, created when needed
+    spill_file: Option<Arc<SpillFile>>,
+    /// The id of the loading that read the buffer, carried by its events, 0 if it was not loaded
+    pub(crate) loading: usize,
 }
 
 const FILTERING: &str = "Filtering...";
@@ -38,6 +73,11 @@ impl Buffer {
         buffer
     }
 
+    /// Create an empty buffer without path, its lines are appended while they are read
+    pub(crate) fn new_stream_buffer(sender: Sender<Event>) -> Self {
+        Self::new_with_group_size(sender, DEFAULT_GROUP_SIZE)
+    }
+
     pub(crate) fn new_from_string(sender: Sender<Event>, text: &str, group_size: usize) -> Self {
         let mut buffer = Self::new_with_group_size(sender, group_size);
 
@@ -52,79 +92,339 @@ impl Buffer {
     fn new_with_group_size(sender: Sender<Event>, group_size: usize) -> Self {
         Self {
             path: None,
+            name: None,
             content: vec![LineGroup::new(0, group_size)],
             length: 0,
             dirty: false,
             sender,
             group_size,
+            encoding: Encoding::default(),
+            published_groups: 0,
+            spill_file: None,
+            loaded_size: None,
+            loading: 0,
         }
     }
 
+    /// Load a file, if no encoding is given it is detected from the beginning of the file.
+    /// The events of the loading carry the given loading id.
+    /// The loading fails with an `Interrupted` error if it is cancelled.
     pub(crate) fn new_from_file(
         path: PathBuf,
+        loading: usize,
+        encoding: Option<Encoding>,
         sender: Sender<Event>,
+        cancellation: &CancellationToken,
     ) -> Result<Self, std::io::Error> {
         let mut buffer = Self {
             path: Some(path),
+            loading,
             ..Self::new_with_group_size(sender, DEFAULT_GROUP_SIZE)
         };
 
-        buffer.load_buffer()?;
+        let loaded = buffer.load_buffer(encoding, cancellation);
+        // the progress is removed even if the loading failed or was cancelled
+        let _ = buffer.sender.send(OperationFinished(loading_id(loading)));
+        loaded?;
 
         Ok(buffer)
     }
 
+    /// Load entries of an archive in a single pass, each one in its own buffer given to `loaded`.
+    /// The buffers have no path so they are not saved into the archive.
+    pub(crate) fn load_archive_entries(
+        path: PathBuf,
+        archive: Archive,
+        entries: &[ArchiveEntry],
+        encoding: Option<Encoding>,
+        sender: Sender<Event>,
+        cancellation: &CancellationToken,
+        mut loaded: impl FnMut(Self),
+    ) -> Result<(), std::io::Error> {
+        let names: Vec<String> = entries.iter().map(|entry| entry.name.clone()).collect();
+        archive.read_entries(&path, &names, |name, reader| {
+            info!("Load {name} from {path:?}");
+            let size = entries
+                .iter()
+                .find(|entry| entry.name == name)
+                .map_or(0, |entry| entry.size);
+            let loading = new_loading();
+            let mut buffer = Self {
+                name: Some(name.to_owned()),
+                loading,
+                ..Self::new_with_group_size(sender.clone(), DEFAULT_GROUP_SIZE)
+            };
+            let _ = sender.send(BufferLoadingStarted(
+                loading,
+                path.clone(),
+                size,
+                cancellation.clone(),
+            ));
+            let read = Self::detect_encoding(reader, encoding).and_then(|encoding| {
+                buffer.encoding = encoding;
+                buffer.load_reader(&path, 0, size, reader, cancellation)
+            });
+            let _ = sender.send(OperationFinished(loading_id(loading)));
+            read?;
+            loaded(buffer);
+            Ok(())
+        })
+    }
+
+    /// The name of the buffer, the file name by default
+    pub(crate) fn name(&self) -> String {
+        match (&self.name, &self.path) {
+            (Some(name), _) => name.clone(),
+            (None, Some(path)) => path
+                .file_name()
+                .unwrap_or(path.as_os_str())
+                .to_string_lossy()
+                .into_owned(),
+            (None, None) => "untitled".to_string(),
+        }
+    }
+
     pub(crate) fn set_path(&mut self, path: PathBuf) {
         info!("set path to {path:?}");
         self.path = Some(path);
+        self.name = None;
         self.dirty = true;
+        self.loaded_size = None;
     }
 
-    fn load_buffer(&mut self) -> Result<(), io::Error> {
-        let path = self.path.as_ref().expect("buffer has no path");
-        let file = File::open(path)?;
+    fn load_buffer(
+        &mut self,
+        encoding: Option<Encoding>,
+        cancellation: &CancellationToken,
+    ) -> Result<(), io::Error> {
+        let path = self.path.clone().expect("buffer has no path");
+        let file = File::open(&path)?;
 
         let mut buffer_reader = io::BufReader::new(file);
 
-        let file_size = std::fs::metadata(path)?.len() as usize;
-        if is_gzip(&mut buffer_reader) {
-            let file_size = match gzip_uncompressed_size_fast(path) {
-                Ok(size) => size as usize,
-                Err(_) => file_size,
+        let metadata = std::fs::metadata(&path)?;
+        let file_size = metadata.len() as usize;
+        let compression = Compression::detect(&mut buffer_reader);
+        if compression == Compression::None {
+            let _ = self.sender.send(BufferLoadingStarted(
+                self.loading,
+                path.clone(),
+                file_size,
+                cancellation.clone(),
+            ));
+            let bom_length =
+                Encoding::from_bom(buffer_reader.fill_buf()?).map_or(0, |(_, length)| length);
+            self.encoding = Self::detect_encoding(&mut buffer_reader, encoding)?;
+            let read = if !self.encoding.has_byte_newline() {
+                self.load_reader(&path, 0, file_size, &mut buffer_reader, cancellation)?
+            } else if MappedFile::is_being_written(&metadata) {
+                info!("{path:?} is being written, it is loaded in memory");
+                self.load_parallel(&path, file_size, buffer_reader, cancellation)?
+            } else {
+                match MappedFile::open(buffer_reader.get_ref(), self.encoding) {
+                    Ok(file) => self.load_mapped(&path, Arc::new(file), cancellation)?,
+                    Err(e) => {
+                        warn!("Unable to map {path:?}, it is loaded in memory: {e}");
+                        self.load_parallel(&path, file_size, buffer_reader, cancellation)?
+                    }
+                }
             };
-            let _ = self
-                .sender
-                .send(BufferLoadingStarted(path.clone(), file_size));
-            let decoder = GzDecoder::new(buffer_reader);
-            let mut buffer_reader = io::BufReader::new(decoder);
-            self.load_reader(file_size, &mut buffer_reader)?;
+            self.loaded_size = Some((bom_length + read) as u64);
         } else {
-            let _ = self
-                .sender
-                .send(BufferLoadingStarted(path.clone(), file_size));
-            self.load_reader(file_size, &mut buffer_reader)?;
+            info!("{path:?} is compressed with {compression:?}");
+            let file_size = compression.uncompressed_size(&path, file_size);
+            let _ = self.sender.send(BufferLoadingStarted(
+                self.loading,
+                path.clone(),
+                file_size,
+                cancellation.clone(),
+            ));
+            let decoder = compression.decoder(buffer_reader)?;
+            let mut buffer_reader = io::BufReader::new(decoder);
+            self.encoding = Self::detect_encoding(&mut buffer_reader, encoding)?;
+            self.load_reader(&path, 0, file_size, &mut buffer_reader, cancellation)?;
         }
 
         Ok(())
     }
 
+    /// Skip the byte order mark and returns the encoding of the text.
+    /// The given encoding is used if any, otherwise it is detected.
+    pub(super) fn detect_encoding(
+        buffer_reader: &mut (impl BufRead + ?Sized),
+        encoding: Option<Encoding>,
+    ) -> Result<Encoding, io::Error> {
+        let bytes = buffer_reader.fill_buf()?;
+        let detected = match Encoding::from_bom(bytes) {
+            Some((detected, bom_length)) => {
+                buffer_reader.consume(bom_length);
+                detected
+            }
+            None => Encoding::guess(bytes),
+        };
+        info!("Detected encoding {detected}, requested {encoding:?}");
+        Ok(encoding.unwrap_or(detected))
+    }
+
+    /// Append the lines of the reader, the progress is reported as `loaded` bytes plus the bytes
+    /// read out of `total_size`.
+    /// Returns the number of bytes read, or an `Interrupted` error if the loading is cancelled.
     fn load_reader(
         &mut self,
-        file_size: usize,
+        source: &Path,
+        loaded: usize,
+        total_size: usize,
         buffer_reader: impl BufRead,
-    ) -> Result<(), io::Error> {
+        cancellation: &CancellationToken,
+    ) -> Result<usize, io::Error> {
         let mut start = Instant::now();
-        for line_text in buffer_reader.lines() {
+        let mut line_reader = LineReader::new(buffer_reader, self.encoding);
+        while let Some(line_text) = line_reader.next() {
             self.push_line(line_text?);
             if start.elapsed() > Duration::from_millis(50) {
-                let path = self.path.clone().expect("buffer has no path");
-                let _ = self
-                    .sender
-                    .send(BufferLoading(path, self.length, file_size));
+                if cancellation.is_cancelled() {
+                    info!("Loading of {source:?} cancelled");
+                    return Err(io::Error::new(io::ErrorKind::Interrupted, "loading cancelled"));
+                }
+                let _ = self.sender.send(BufferLoading(
+                    self.loading,
+                    loaded + line_reader.read(),
+                    total_size,
+                ));
+                self.spill_unpublished_groups(spill::threshold());
+                self.publish_groups(source);
                 start = Instant::now();
             }
         }
-        Ok(())
+        Ok(line_reader.read())
+    }
+
+    /// Load a plain file by blocks, each block is split in chunks ending on a newline that are
+    /// parsed and compressed in parallel, then their groups are appended in order.
+    /// The newline of the encoding must be a single byte.
+    /// Returns the number of bytes read, or an `Interrupted` error if the loading is cancelled.
+    fn load_parallel(
+        &mut self,
+        source: &Path,
+        total_size: usize,
+        mut reader: impl Read + Send,
+        cancellation: &CancellationToken,
+    ) -> Result<usize, io::Error> {
+        let encoding = self.encoding;
+        let group_size = self.group_size;
+        let mut loaded = 0;
+        let mut batch = Vec::with_capacity(BATCH_SIZE);
+        let mut end_of_file = read_batch(&mut reader, &mut batch)?;
+        loop {
+            let end = if end_of_file {
+                batch.len()
+            } else if let Some(newline) = batch.iter().rposition(|b| *b == b'\n') {
+                newline + 1
+            } else {
+                // a line longer than a block
+                end_of_file = read_batch(&mut reader, &mut batch)?;
+                continue;
+            };
+            let mut next = Vec::with_capacity(BATCH_SIZE);
+            next.extend_from_slice(&batch[end..]);
+            batch.truncate(end);
+            let (groups, read) = rayon::join(
+                || parse_chunks(&batch, encoding, group_size, CHUNK_SIZE),
+                || {
+                    if end_of_file {
+                        Ok(true)
+                    } else {
+                        read_batch(&mut reader, &mut next)
+                    }
+                },
+            );
+            loaded += batch.len();
+            self.append_groups(groups);
+            if cancellation.is_cancelled() {
+                info!("Loading of {source:?} cancelled");
+                return Err(io::Error::new(io::ErrorKind::Interrupted, "loading cancelled"));
+            }
+            let _ = self
+                .sender
+                .send(BufferLoading(self.loading, loaded, total_size));
+            self.spill_unpublished_groups(spill::threshold());
+            self.publish_groups(source);
+            if end_of_file {
+                return Ok(loaded);
+            }
+            end_of_file = read?;
+            batch = next;
+        }
+    }
+
+    /// Index the lines of a mapped file by blocks, the groups keep the byte range of their lines
+    /// that are read on demand. Returns the number of bytes indexed, or an `Interrupted` error
+    /// if the loading is cancelled.
+    fn load_mapped(
+        &mut self,
+        source: &Path,
+        file: Arc<MappedFile>,
+        cancellation: &CancellationToken,
+    ) -> Result<usize, io::Error> {
+        let bytes = file.bytes();
+        let total_size = bytes.len();
+        let bom_length = Encoding::from_bom(bytes).map_or(0, |(_, bom_length)| bom_length);
+        let mut start = bom_length;
+        while start < total_size {
+            let end = (start + BATCH_SIZE).min(total_size);
+            let end = bytes[end..]
+                .iter()
+                .position(|b| *b == b'\n')
+                .map_or(total_size, |newline| end + newline + 1);
+            let groups = mapped::scan(&file, start..end, self.group_size, CHUNK_SIZE);
+            self.append_groups(groups);
+            if cancellation.is_cancelled() {
+                info!("Loading of {source:?} cancelled");
+                return Err(io::Error::new(io::ErrorKind::Interrupted, "loading cancelled"));
+            }
+            let _ = self
+                .sender
+                .send(BufferLoading(self.loading, end, total_size));
+            self.spill_unpublished_groups(spill::threshold());
+            self.publish_groups(source);
+            start = end;
+        }
+        Ok(total_size - bom_length)
+    }
+
+    /// Send the groups completed since the last call so the beginning of a file can be browsed
+    /// while the rest is loaded. The complete groups are compressed, they are shared with the ui.
+    /// Only the buffers of a file are published, they are matched with their tab by loading id.
+    fn publish_groups(&mut self, source: &Path) {
+        if self.path.is_none() {
+            return;
+        }
+        let complete = self.content.len() - 1;
+        if complete > self.published_groups {
+            let groups = self.content[self.published_groups..complete]
+                .iter()
+                .map(LineGroup::snapshot)
+                .collect();
+            let _ = self
+                .sender
+                .send(BufferGroupsLoaded(self.loading, source.to_path_buf(), groups));
+            self.published_groups = complete;
+        }
+    }
+
+    /// Append the groups published while a file is loaded, they are renumbered after the
+    /// current lines. It doesn't make the buffer dirty as the lines come from the file.
+    pub(crate) fn append_groups(&mut self, groups: Vec<LineGroup>) {
+        let mut last = self.content.pop().expect("buffer is empty");
+        let mut first_line = self.line_count();
+        for mut group in groups {
+            group.set_first_line(first_line);
+            first_line += group.line_count();
+            self.length += group.len();
+            self.content.push(group);
+        }
+        last.set_first_line(first_line);
+        self.content.push(last);
     }
 
     /// Compress all line groups and free their in-memory lines to reclaim memory.
@@ -144,6 +444,78 @@ impl Buffer {
         }
     }
 
+    /// Move the compressed lines of the least recently used groups to the scratch file until the
+    /// compressed lines kept in memory fit in the threshold. The groups of the snapshots kept to
+    /// undo the commands are counted and spilled with the ones of the buffer.
+    pub(crate) fn spill_groups(&mut self, threshold: usize, snapshots: &[&LineGroup]) {
+        self.spill_groups_from(0, threshold, snapshots);
+    }
+
+    /// Spill the groups loaded since the last publication while the compressed lines kept in
+    /// memory exceed the threshold. The published groups share the scratch file with the ui, it
+    /// doesn't keep their compressed lines in memory nor write them again.
+    fn spill_unpublished_groups(&mut self, threshold: usize) {
+        self.spill_groups_from(self.published_groups, threshold, &[]);
+    }
+
+    fn spill_groups_from(
+        &mut self,
+        first_group: usize,
+        threshold: usize,
+        snapshots: &[&LineGroup],
+    ) {
+        // the compressed lines shared by a group and its snapshots are counted once
+        let mut counted = HashSet::new();
+        let mut in_memory: usize = self
+            .content
+            .iter()
+            .chain(snapshots.iter().copied())
+            .filter(|g| g.compressed_id().is_some_and(|id| counted.insert(id)))
+            .map(LineGroup::compressed_size)
+            .sum();
+        if in_memory <= threshold {
+            return;
+        }
+        let spill_file = match &self.spill_file {
+            Some(spill_file) => Arc::clone(spill_file),
+            None => match SpillFile::create() {
+                Ok(spill_file) => Arc::clone(self.spill_file.insert(Arc::new(spill_file))),
+                Err(e) => {
+                    warn!("Unable to create a scratch file: {e}");
+                    return;
+                }
+            },
+        };
+        let mut spilled = HashSet::new();
+        let mut groups: Vec<&LineGroup> = self.content[first_group..]
+            .iter()
+            .chain(snapshots.iter().copied())
+            .filter(|g| g.compressed_id().is_some_and(|id| spilled.insert(id)))
+            .collect();
+        groups.sort_by_key(|g| g.last_use());
+        for group in groups {
+            if in_memory <= threshold {
+                break;
+            }
+            let size = group.compressed_size();
+            if let Err(e) = group.spill(&spill_file) {
+                warn!("Unable to spill the compressed lines: {e}");
+                return;
+            }
+            in_memory -= size;
+        }
+    }
+
+    /// Compress a group and free its lines, they are decompressed again when they are read
+    pub(crate) fn evict_group(&mut self, index: usize) {
+        let line_group = &mut self.content[index];
+        if line_group.is_empty() || !line_group.is_decompressed() {
+            return;
+        }
+        line_group.eventually_compress();
+        line_group.free();
+    }
+
     fn push_line(&mut self, line: impl Into<Line>) {
         let last_group = self.content.last_mut().expect("buffer is empty");
         let line = line.into();
@@ -158,6 +530,12 @@ impl Buffer {
         }
     }
 
+    /// Append lines at the end of the buffer, it doesn't make it dirty as the lines come from
+    /// the file itself.
+    pub(crate) fn append_lines(&mut self, lines: Vec<String>) {
+        lines.into_iter().for_each(|line| self.push_line(line));
+    }
+
     /// Delete a range of text from the buffer.
     pub(crate) fn delete_range(&mut self, text_range: TextRange) {
         let line_count = self.line_count();
@@ -245,6 +623,18 @@ impl Buffer {
         }
     }
 
+    /// Write the lines of the buffer in its encoding, each line ending with '\n'
+    pub(crate) fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        let eol = self.encoding.encode("\n");
+        for group in &self.content {
+            for line in group.lines().iter() {
+                writer.write_all(&self.encoding.encode(line.content()))?;
+                writer.write_all(&eol)?;
+            }
+        }
+        Ok(())
+    }
+
     pub(crate) fn line_groups(&self) -> &[LineGroup] {
         &self.content
     }
@@ -304,40 +694,62 @@ impl Buffer {
         new_length
     }
 
-    pub(crate) fn filter_line_mut<F>(&mut self, filter: F) -> usize
+    /// Apply the filter to all the lines.
+    /// If it is cancelled, some groups are left unchanged and the buffer must be restored.
+    pub(crate) fn filter_line_mut<F>(
+        &mut self,
+        filter: F,
+        cancellation: &CancellationToken,
+    ) -> usize
     where
         F: FnMut(&mut Line) + Clone + Sync,
     {
+        let progress_id = operation_id(FILTERING);
         let _ = self.sender.send(Event::OperationStarted(
-            FILTERING.to_owned(),
+            progress_id.clone(),
             self.content.len(),
+            Some(cancellation.clone()),
         ));
         self.content.par_iter_mut().for_each(|line_group| {
             let _ = self
                 .sender
-                .send(Event::OperationIncrement(FILTERING.to_owned(), 1));
+                .send(Event::OperationIncrement(progress_id.clone(), 1));
+            if cancellation.is_cancelled() {
+                return;
+            }
             line_group.filter_lines_mut(filter.clone());
         });
         let new_length = self.compute_length();
         self.dirty = true;
         let _ = self
             .sender
-            .send(Event::OperationFinished(FILTERING.to_owned()));
+            .send(Event::OperationFinished(progress_id));
         new_length
     }
 
-    pub(crate) fn retain_line_mut<F>(&mut self, filter: F) -> usize
+    /// Keep only the lines accepted by the filter.
+    /// If it is cancelled, some groups are left unchanged and the buffer must be restored.
+    pub(crate) fn retain_line_mut<F>(
+        &mut self,
+        filter: F,
+        cancellation: &CancellationToken,
+    ) -> usize
     where
         F: Fn(&Line) -> bool + Sync + Send + Clone,
     {
+        let progress_id = operation_id(FILTERING);
         let _ = self.sender.send(Event::OperationStarted(
-            FILTERING.to_owned(),
+            progress_id.clone(),
             self.content.len(),
+            Some(cancellation.clone()),
         ));
         self.content.par_iter_mut().for_each(|line_group| {
             let _ = self
                 .sender
-                .send(Event::OperationIncrement(FILTERING.to_owned(), 1));
+                .send(Event::OperationIncrement(progress_id.clone(), 1));
+            if cancellation.is_cancelled() {
+                return;
+            }
             line_group.retain(filter.clone());
         });
         // remove empty groups
@@ -347,10 +759,190 @@ impl Buffer {
         self.dirty = true;
         let _ = self
             .sender
-            .send(Event::OperationFinished(FILTERING.to_owned()));
+            .send(Event::OperationFinished(progress_id));
         new_length
     }
 
+    /// Keep only the lines of the view, the lines it hides are removed from the buffer
+    pub(crate) fn retain_view(&mut self, view: &LineView) -> usize {
+        self.content.par_iter_mut().for_each(|line_group| {
+            let mut line = line_group.first_line();
+            line_group.retain(|_| {
+                let visible = view.contains(line);
+                line += 1;
+                visible
+            });
+        });
+        // remove empty groups
+        self.content.retain(|g| !g.is_empty());
+        let new_length = self.compute_length();
+        self.recompute_first_lines();
+        self.dirty = true;
+        new_length
+    }
+
+    /// Builds a view of the lines accepted by the filter, without modifying the buffer.
+    /// If a view is given, only the lines it contains are considered.
+    /// If it is cancelled, the view is incomplete and must be dropped.
+    pub(crate) fn filter_view<F>(
+        &self,
+        view: Option<&LineView>,
+        filter: F,
+        cancellation: &CancellationToken,
+    ) -> LineView
+    where
+        F: Fn(&Line) -> bool + Sync + Send,
+    {
+        let progress_id = operation_id(FILTERING);
+        let _ = self.sender.send(Event::OperationStarted(
+            progress_id.clone(),
+            self.content.len(),
+            Some(cancellation.clone()),
+        ));
+        let lines = self
+            .content
+            .par_iter()
+            .map(|line_group| {
+                let _ = self
+                    .sender
+                    .send(Event::OperationIncrement(progress_id.clone(), 1));
+                if cancellation.is_cancelled() {
+                    return Vec::new();
+                }
+                let first_line = line_group.first_line();
+                match view {
+                    Some(view) => {
+                        let end_line = first_line + line_group.line_count();
+                        let start = view.view_line(first_line);
+                        let end = view.view_line(end_line);
+                        if start == end {
+                            // no visible line in this group, no need to decompress it
+                            return Vec::new();
+                        }
+                        let group_lines = line_group.lines();
+                        view.lines()[start..end]
+                            .iter()
+                            .copied()
+                            .filter(|line| filter(&group_lines[line - first_line]))
+                            .collect::<Vec<_>>()
+                    }
+                    None => line_group
+                        .lines()
+                        .iter()
+                        .enumerate()
+                        .filter_map(|(i, line)| filter(line).then_some(first_line + i))
+                        .collect::<Vec<_>>(),
+                }
+            })
+            .collect::<Vec<_>>()
+            .concat();
+        let _ = self
+            .sender
+            .send(Event::OperationFinished(progress_id));
+        LineView::new(lines)
+    }
+
+    /// Returns the first line of the groups logged at or after the given time.
+    /// The groups are binary searched by their first timestamp, so only the groups looked at
+    /// are decompressed, and only while they are read. It is meant to run on a snapshot in a
+    /// background thread.
+    pub(crate) fn find_time(
+        groups: &[LineGroup],
+        time: TimeBound,
+        format: &TimestampFormat,
+    ) -> Option<usize> {
+        let first = groups
+            .iter()
+            .take(TIMESTAMP_LOOKUP)
+            .find_map(|group| Self::first_timestamp(group, format))?;
+        let timestamp = time.resolve(first);
+        // the first group whose timestamp is at or after the time
+        let (mut after, mut end) = (0, groups.len());
+        while after < end {
+            let middle = after + (end - after) / 2;
+            if Self::group_timestamp(groups, middle, format)
+                .is_none_or(|(_, first)| first < timestamp)
+            {
+                after = middle + 1;
+            } else {
+                end = middle;
+            }
+        }
+        // the line may be logged in the group giving the timestamp of the previous one
+        let before = after
+            .checked_sub(1)
+            .and_then(|previous| Self::group_timestamp(groups, previous, format))
+            .map(|(index, _)| index);
+        before
+            .into_iter()
+            .chain((after < groups.len()).then_some(after))
+            .find_map(|index| {
+                let group = &groups[index];
+                group
+                    .lines()
+                    .iter()
+                    .position(|line| {
+                        format
+                            .parse(line.content())
+                            .is_some_and(|line_timestamp| line_timestamp >= timestamp)
+                    })
+                    .map(|line| group.first_line() + line)
+            })
+    }
+
+    /// Returns the first timestamp of the group and its index. A group without timestamp, like
+    /// one holding a stack trace, has the one of the previous groups so they stay ordered.
+    /// Only the last [`TIMESTAMP_LOOKUP`] groups are looked at.
+    fn group_timestamp(
+        groups: &[LineGroup],
+        index: usize,
+        format: &TimestampFormat,
+    ) -> Option<(usize, i64)> {
+        groups[..=index]
+            .iter()
+            .enumerate()
+            .rev()
+            .take(TIMESTAMP_LOOKUP)
+            .find_map(|(index, group)| Self::first_timestamp(group, format).map(|t| (index, t)))
+    }
+
+    fn first_timestamp(group: &LineGroup, format: &TimestampFormat) -> Option<i64> {
+        group
+            .lines()
+            .iter()
+            .find_map(|line| format.parse(line.content()))
+    }
+
+    /// Decompress only the line groups containing the given lines (sorted buffer line indexes).
+    /// This is the equivalent of `prepare_range_for_read` for filtered views where the visible
+    /// lines might be spread across the whole buffer.
+    ///
+    /// The groups that are not containing or neighbouring those lines are recompressed.
+    pub(crate) fn prepare_lines_for_read(&mut self, lines: &[usize]) {
+        let mut needed = vec![false; self.content.len()];
+        for line in lines {
+            let group_index = self
+                .content
+                .partition_point(|g| g.first_line() + g.line_count() <= *line);
+            if let Some(needed) = needed.get_mut(group_index) {
+                *needed = true;
+            }
+        }
+
+        for (group_index, g) in self.content.iter_mut().enumerate() {
+            if needed[group_index] {
+                g.eventually_decompress();
+                continue;
+            }
+            let previous_needed = group_index > 0 && needed[group_index - 1];
+            let next_needed = needed.get(group_index + 1).copied().unwrap_or(false);
+            if !previous_needed && !next_needed && !g.is_empty() {
+                g.eventually_compress();
+                g.free();
+            }
+        }
+    }
+
     /// Decompress only the line groups that intersect with the provided line range.
     /// This is a preparatory pass to ensure subsequent read operations on that
     /// span won't trigger on-demand decompression.
@@ -432,42 +1024,93 @@ impl Buffer {
         self.content.iter().map(|g| g.line_count()).sum()
     }
 
-    pub(crate) fn insert_char(&mut self, line: usize, col: usize, ch: char) {
-        if ch == '\n' {
-            self.insert_newline(line, col);
-            return;
-        }
-        if let Some((gi, _)) = self.find_group_index(line) {
-            let relative_line = line - self.content[gi].first_line();
-            self.content[gi].filter_line_mut(relative_line, |l| {
-                l.insert(col, ch);
+    /// Insert a text, that may contain several lines, at the given position.
+    /// Returns the position (line, column) of the end of the inserted text.
+    pub(crate) fn insert_text(&mut self, line: usize, column: usize, text: &str) -> (usize, usize) {
+        let Some((gi, li)) = self.find_group_index(line) else {
+            warn!("insert_text: line {line} out of bounds");
+            return (line, column);
+        };
+        let mut parts = text.split('\n');
+        let first_part = parts.next().unwrap_or_default();
+        let mut new_lines: Vec<Line> = parts.map(Line::from).collect();
+        let line_group = &mut self.content[gi];
+        let end = if new_lines.is_empty() {
+            line_group.filter_line_mut(li, |l| l.insert_str(column, first_part));
+            (line, column + first_part.len())
+        } else {
+            let mut suffix = String::new();
+            line_group.filter_line_mut(li, |l| {
+                suffix = l.content()[column..].to_owned();
+                l.drain(column..);
+                l.push_str(first_part);
             });
-            self.compute_length();
-            self.dirty = true;
-        }
+            let new_line_count = new_lines.len();
+            let last_line = &mut new_lines[new_line_count - 1];
+            let end = (line + new_line_count, last_line.len());
+            last_line.push_str(&suffix);
+            line_group.insert_lines(li + 1, new_lines);
+            end
+        };
+
+        self.compute_length();
+        self.recompute_first_lines();
+        self.dirty = true;
+        end
     }
 
-    pub(crate) fn insert_newline(&mut self, line: usize, col: usize) {
-        if let Some((gi, li)) = self.find_group_index(line) {
-            let suffix = {
-                let line_group = &mut self.content[gi];
-                let mut suffix = String::new();
-                line_group.filter_line_mut(li, |l| {
-                    suffix = l.content()[col..].to_owned();
-                    l.drain(col..);
-                });
-                suffix
+    /// Returns the text contained in the range, the lines are separated by '\n'
+    pub(crate) fn text(&mut self, text_range: &TextRange) -> String {
+        let line_count = self.line_count();
+        if line_count == 0 || text_range.start_line >= line_count {
+            return String::new();
+        }
+        let end_line = text_range.end_line.min(line_count - 1);
+        self.prepare_range_for_read(text_range.start_line..=end_line);
+        let mut text = String::new();
+        for line in text_range.start_line..=end_line {
+            let line_text = self.line_text(line);
+            let end_column = if line == text_range.end_line {
+                text_range.end_column.min(line_text.len())
+            } else {
+                line_text.len()
             };
+            let start_column = if line == text_range.start_line {
+                text_range.start_column.min(end_column)
+            } else {
+                0
+            };
+            text.push_str(&line_text[start_column..end_column]);
+            if line < end_line {
+                text.push('\n');
+            }
+        }
+        text
+    }
 
-            let line_group = &mut self.content[gi];
-            line_group.insert_line(li + 1, Line::from(suffix));
-
-            self.compute_length();
-            self.recompute_first_lines();
-            self.dirty = true;
+    /// Returns a compressed copy of the buffer content, used to undo the commands
+    /// modifying the whole buffer.
+    pub(crate) fn snapshot(&self) -> BufferSnapshot {
+        BufferSnapshot {
+            content: self
+                .content
+                .par_iter()
+                .map(|line_group| line_group.snapshot())
+                .collect(),
+            length: self.length,
         }
     }
 
+    /// Restore the content of a snapshot.
+    /// Returns the snapshot of the replaced content, so the restore can be reverted.
+    pub(crate) fn restore(&mut self, snapshot: BufferSnapshot) -> BufferSnapshot {
+        let current = self.snapshot();
+        self.content = snapshot.content;
+        self.length = snapshot.length;
+        self.dirty = true;
+        current
+    }
+
     /// Returns the buffer length.
     /// It is the number of chars + end of lines
     #[inline]
@@ -491,6 +1134,19 @@ impl Buffer {
         self.content.iter().filter(|g| g.is_compressed()).count()
     }
 
+    /// Returns true if some lines are read from a mapped file
+    pub(crate) fn is_mapped(&self) -> bool {
+        self.content.iter().any(LineGroup::is_mapped)
+    }
+
+    /// Read in memory the lines of a mapped file, like before following the file as it changes
+    pub(crate) fn unmap(&mut self) {
+        if self.is_mapped() {
+            info!("Read the mapped lines of {:?} in memory", self.path);
+            self.content.par_iter_mut().for_each(LineGroup::unmap);
+        }
+    }
+
     pub(crate) fn decompressed_group_count(&self) -> usize {
         self.content.iter().filter(|g| g.is_decompressed()).count()
     }
@@ -518,6 +1174,11 @@ impl Buffer {
         vec_overhead + array_mem + groups_mem
     }
 
+    /// The memory used by the decompressed lines of the groups
+    pub(crate) fn decompressed_mem(&self) -> usize {
+        self.content.iter().map(LineGroup::decompressed_mem).sum()
+    }
+
     pub(crate) fn compressed_size(&self) -> usize {
         self.content.iter().map(|data| data.compressed_size()).sum()
     }
@@ -581,6 +1242,50 @@ impl Buffer {
     }
 }
 
+/// Append the next block of a file to the bytes, returns true if the end of the file is reached
+fn read_batch(reader: &mut impl Read, bytes: &mut Vec<u8>) -> io::Result<bool> {
+    let read = reader.take(BATCH_SIZE as u64).read_to_end(bytes)?;
+    Ok(read < BATCH_SIZE)
+}
+
+/// Split the lines in chunks ending on a newline, the chunks are parsed and compressed in
+/// parallel. Returns the groups of the chunks in order, they are numbered from 0.
+fn parse_chunks(
+    bytes: &[u8],
+    encoding: Encoding,
+    group_size: usize,
+    chunk_size: usize,
+) -> Vec<LineGroup> {
+    mapped::split_chunks(bytes, chunk_size)
+        .into_par_iter()
+        .map(|chunk| parse_chunk(&bytes[chunk], encoding, group_size))
+        .collect::<Vec<_>>()
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+/// Parse the lines of a chunk into compressed groups, the last one may not be full
+fn parse_chunk(chunk: &[u8], encoding: Encoding, group_size: usize) -> Vec<LineGroup> {
+    let mut groups = Vec::new();
+    let mut group = LineGroup::new(0, group_size);
+    // reading from memory cannot fail
+    for line in LineReader::new(chunk, encoding).map_while(Result::ok) {
+        group.push(Line::from(line));
+        if group.is_full() {
+            group.eventually_compress();
+            group.free();
+            groups.push(std::mem::replace(&mut group, LineGroup::new(0, group_size)));
+        }
+    }
+    if !group.is_empty() {
+        group.eventually_compress();
+        group.free();
+        groups.push(group);
+    }
+    groups
+}
+
 impl Index<usize> for Buffer {
     type Output = Line;
 
@@ -654,11 +1359,14 @@ mod tests {
     fn filter_line_mut_updates_all_lines() {
         let (sender, _) = std::sync::mpsc::channel();
         let mut b = Buffer::new_from_string(sender, "a\nbb", 2);
-        let new_len = b.filter_line_mut(|line| {
-            let mut s = line.to_string();
-            s.push('x');
-            *line = Line::from(s);
-        });
+        let new_len = b.filter_line_mut(
+            |line| {
+                let mut s = line.to_string();
+                s.push('x');
+                *line = Line::from(s);
+            },
+            &CancellationToken::default(),
+        );
         assert!(new_len >= b.len());
         b.prepare_range_for_read(..);
         assert!(b.line_text(0).ends_with('x'));
@@ -670,13 +1378,42 @@ mod tests {
     fn retain_line_mut_keeps_predicate_matches() {
         let (sender, _) = std::sync::mpsc::channel();
         let mut b = Buffer::new_from_string(sender, "a\nbb\nccc\ndddd", 2);
-        let _ = b.retain_line_mut(|l| l.len() % 2 == 0); // keep even lengths: 2 and 4
+        // keep even lengths: 2 and 4
+        let _ = b.retain_line_mut(|l| l.len() % 2 == 0, &CancellationToken::default());
         assert_eq!(b.line_count(), 2);
         assert_eq!(b.line_text(0), "bb");
         assert_eq!(b.line_text(1), "dddd");
         assert!(b.dirty);
     }
 
+    #[test]
+    fn append_groups_renumbers_lines() {
+        let (sender, _) = std::sync::mpsc::channel();
+        let loaded = Buffer::new_from_string(sender.clone(), "a\nbb\nccc\ndddd\ne", 2);
+        let mut b = Buffer::new_stream_buffer(sender);
+        let groups = loaded.line_groups();
+        b.append_groups(vec![groups[0].snapshot()]);
+        b.append_groups(vec![groups[1].snapshot()]);
+        assert_eq!(b.line_count(), 4);
+        assert_eq!(b.len(), 14);
+        b.prepare_range_for_read(..);
+        assert_eq!(b.line_text(2), "ccc");
+        assert_eq!(b.line_groups()[1].first_line(), 2);
+        assert!(!b.dirty);
+    }
+
+    #[test]
+    fn cancelled_retain_line_mut_keeps_lines() {
+        let (sender, _) = std::sync::mpsc::channel();
+        let mut b = Buffer::new_from_string(sender, "a\nbb\nccc\ndddd", 2);
+        let cancellation = CancellationToken::default();
+        cancellation.cancel();
+        let _ = b.retain_line_mut(|l| l.len() % 2 == 0, &cancellation);
+        assert_eq!(b.line_count(), 4);
+        b.prepare_range_for_read(..);
+        assert_eq!(b.line_text(2), "ccc");
+    }
+
     #[test]
     fn drain_line_mut_various_ranges() {
         let (sender, _) = std::sync::mpsc::channel();
@@ -713,14 +1450,95 @@ mod tests {
     fn new_from_file_loads_cargo_toml() {
         let (sender, _) = std::sync::mpsc::channel();
         let path = PathBuf::from("Cargo.toml");
-        let mut buffer = Buffer::new_from_file(path, sender).expect("Failed to load Cargo.toml");
+        let mut buffer = Buffer::new_from_file(path, 1, None, sender, &CancellationToken::default())
+            .expect("Failed to load Cargo.toml");
 
         assert!(buffer.line_count() > 0);
+        assert_eq!(buffer.loaded_size, Some(std::fs::metadata("Cargo.toml").unwrap().len()));
+        // the groups of a loaded file are read on demand
+        buffer.prepare_range_for_read(..);
         let first_line = buffer.line_text(0);
         assert!(first_line.contains("[package]"));
         buffer.compress_all_groups();
     }
 
+    /// Write a file that is not written anymore, as it was modified a while ago
+    fn write_old_file(name: &str, text: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("ceos_{name}_{}", std::process::id()));
+        std::fs::write(&path, text).unwrap();
+        let file = File::options().write(true).open(&path).unwrap();
+        let modified = std::time::SystemTime::now() - Duration::from_secs(3_600);
+        file.set_modified(modified).unwrap();
+        path
+    }
+
+    #[test]
+    fn new_from_file_maps_plain_files() {
+        let (sender, _) = std::sync::mpsc::channel();
+        let text = std::fs::read_to_string("Cargo.toml").unwrap();
+        let path = write_old_file("mapped", &text);
+        let mut buffer =
+            Buffer::new_from_file(path.clone(), 1, None, sender, &CancellationToken::default())
+                .expect("Failed to load the file");
+        std::fs::remove_file(path).unwrap();
+
+        // the lines stay in the file until they are read
+        assert!(buffer.line_groups().iter().all(|g| g.compressed_size() == 0));
+        assert_eq!(buffer.decompressed_group_count(), 1);
+        assert_eq!(buffer.line_count(), text.lines().count());
+        assert_eq!(buffer.len(), text.lines().map(|l| l.len() + 1).sum::<usize>());
+        buffer.prepare_range_for_read(..);
+        for (i, line) in text.lines().enumerate() {
+            assert_eq!(buffer.line_text(i), line);
+        }
+    }
+
+    #[test]
+    fn new_from_file_loads_written_files_in_memory() {
+        let (sender, _) = std::sync::mpsc::channel();
+        let path = std::env::temp_dir().join(format!("ceos_written_{}", std::process::id()));
+        std::fs::write(&path, "first\nsecond\n").unwrap();
+        let buffer =
+            Buffer::new_from_file(path.clone(), 1, None, sender, &CancellationToken::default())
+                .expect("Failed to load the file");
+        std::fs::remove_file(path).unwrap();
+        assert!(!buffer.is_mapped());
+        assert_eq!(2, buffer.line_count());
+        assert_eq!(Some(13), buffer.loaded_size);
+    }
+
+    #[test]
+    fn unmap_reads_the_lines_in_memory() {
+        let (sender, _) = std::sync::mpsc::channel();
+        let path = write_old_file("unmap", "first\nsecond\nthird\n");
+        let mut buffer =
+            Buffer::new_from_file(path.clone(), 1, None, sender, &CancellationToken::default())
+                .expect("Failed to load the file");
+        assert!(buffer.is_mapped());
+        buffer.unmap();
+        // the file can change, the lines are not read from it anymore
+        std::fs::remove_file(path).unwrap();
+        assert!(!buffer.is_mapped());
+        buffer.prepare_range_for_read(..);
+        assert_eq!("third", buffer.line_text(2));
+    }
+
+    #[test]
+    fn parse_chunks_keeps_lines_in_order() {
+        let (sender, _) = std::sync::mpsc::channel();
+        let text: String = (0..50).map(|i| format!("line {i}\r\n")).collect();
+        let groups = parse_chunks(text.as_bytes(), Encoding::Utf8, 4, 30);
+        assert!(groups.iter().all(LineGroup::is_compressed));
+        let mut b = Buffer::new_stream_buffer(sender);
+        b.append_groups(groups);
+        assert_eq!(b.line_count(), 50);
+        assert_eq!(b.len(), text.len() - 50);
+        b.prepare_range_for_read(..);
+        for i in 0..50 {
+            assert_eq!(b.line_text(i), format!("line {i}"));
+        }
+    }
+
     #[test]
     fn drain_line_mut_bug_reproduction() {
         let (sender, _) = std::sync::mpsc::channel();
@@ -806,6 +1624,115 @@ mod tests {
         assert!(buffer.dirty);
     }
 
+    #[test]
+    fn filter_view_keeps_buffer_intact() {
+        let (sender, _) = std::sync::mpsc::channel();
+        let mut b = Buffer::new_from_string(sender, "a1\nb2\na3\nb4\na5", 2);
+        let cancellation = CancellationToken::default();
+        let view = b.filter_view(None, |l| l.content().contains("a"), &cancellation);
+        assert_eq!(view.lines(), &[0, 2, 4]);
+        let view = b.filter_view(Some(&view), |l| !l.content().contains("3"), &cancellation);
+        assert_eq!(view.lines(), &[0, 4]);
+        assert_eq!(b.line_count(), 5);
+        assert!(!b.dirty);
+        b.prepare_lines_for_read(view.lines());
+        assert_eq!(b.line_text(4), "a5");
+    }
+
+    #[test]
+    fn insert_text_and_text_roundtrip() {
+        let (sender, _) = std::sync::mpsc::channel();
+        let mut b = Buffer::new_from_string(sender, "hello\nworld\n!!!", 2);
+        let end = b.insert_text(1, 2, "AA\nBB\nCC");
+        assert_eq!((3, 2), end);
+        assert_eq!(b.line_count(), 5);
+        let range = TextRange::new(1, 2, end.0, end.1);
+        assert_eq!("AA\nBB\nCC", b.text(&range));
+        assert_eq!(b.line_text(1), "woAA");
+        assert_eq!(b.line_text(3), "CCrld");
+        b.delete_range(range);
+        assert_eq!(b.line_count(), 3);
+        assert_eq!(b.line_text(1), "world");
+        assert_eq!(b.len(), "hello\nworld\n!!!\n".len());
+    }
+
+    #[test]
+    fn snapshot_restore() {
+        let (sender, _) = std::sync::mpsc::channel();
+        let mut b = Buffer::new_from_string(sender, "a1\nb2\na3", 2);
+        let snapshot = b.snapshot();
+        b.filter_line_mut(|line| line.drain(..1), &CancellationToken::default());
+        b.prepare_range_for_read(..);
+        assert_eq!(b.line_text(0), "1");
+        let snapshot = b.restore(snapshot);
+        b.prepare_range_for_read(..);
+        assert_eq!(b.line_text(0), "a1");
+        assert_eq!(b.line_text(2), "a3");
+        assert_eq!(b.len(), 9);
+        b.restore(snapshot);
+        b.prepare_range_for_read(..);
+        assert_eq!(b.line_text(2), "3");
+    }
+
+    #[test]
+    fn spilled_groups_are_read_back() {
+        let (sender, _) = std::sync::mpsc::channel();
+        let mut b = Buffer::new_from_string(sender, "a1\nb2\na3\nb4\na5", 2);
+        b.compress_all_groups();
+        b.spill_groups(0, &[]);
+        assert_eq!(b.compressed_size(), 0);
+        let mut out = Vec::new();
+        b.write_to(&mut out).unwrap();
+        assert_eq!(b"a1\nb2\na3\nb4\na5\n", out.as_slice());
+    }
+
+    #[test]
+    fn snapshots_share_the_spilled_lines() {
+        let (sender, _) = std::sync::mpsc::channel();
+        let mut b = Buffer::new_from_string(sender, "a1\nb2\na3\nb4\na5", 2);
+        b.compress_all_groups();
+        let snapshot = b.snapshot();
+        b.spill_groups(0, &[]);
+        assert!(snapshot.line_groups().iter().all(|g| g.compressed_size() == 0));
+        b.filter_line_mut(|line| line.drain(..1), &CancellationToken::default());
+        b.restore(snapshot);
+        let mut out = Vec::new();
+        b.write_to(&mut out).unwrap();
+        assert_eq!(b"a1\nb2\na3\nb4\na5\n", out.as_slice());
+    }
+
+    #[test]
+    fn published_groups_share_the_spilled_lines() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let mut b = Buffer::new_from_string(sender, "a1\nb2\na3\nb4\na5", 2);
+        let path = PathBuf::from("spilled.log");
+        b.path = Some(path.clone());
+        b.compress_all_groups();
+        b.published_groups = 1;
+        b.spill_unpublished_groups(0);
+        // the groups already published are left to the ui
+        assert!(b.line_groups()[0].compressed_size() > 0);
+        b.publish_groups(&path);
+        match receiver.try_recv() {
+            Ok(BufferGroupsLoaded(_, _, groups)) => {
+                assert!(!groups.is_empty());
+                assert!(groups.iter().all(|g| g.compressed_size() == 0));
+            }
+            _ => panic!("the groups are not published"),
+        }
+    }
+
+    #[test]
+    fn write_to_writes_all_lines() -> Result<(), io::Error> {
+        let (sender, _) = std::sync::mpsc::channel();
+        let mut b = Buffer::new_from_string(sender, "a1\nb2\na3", 2);
+        b.compress_all_groups();
+        let mut out = Vec::new();
+        b.write_to(&mut out)?;
+        assert_eq!(b"a1\nb2\na3\n", out.as_slice());
+        Ok(())
+    }
+
     #[test]
     fn mem_non_decreasing_after_growth() {
         let (sender, _) = std::sync::mpsc::channel();
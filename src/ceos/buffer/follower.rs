@@ -0,0 +1,250 @@
+use crate::ceos::tools::compression::Compression;
+use crate::ceos::tools::encoding::{Encoding, LineReader};
+use crate::event::Event;
+use crate::event::Event::BufferAppended;
+use log::{info, warn};
+use std::fs::{File, Metadata};
+use std::io;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::time::Duration;
+
+/// The delay between two checks of the followed file
+pub(crate) const FOLLOW_INTERVAL: Duration = Duration::from_millis(250);
+/// The maximum number of bytes read at each check, so a huge append doesn't freeze the ui
+const MAX_READ: u64 = 16 * 1024 * 1024;
+
+/// Follow a growing file like `tail -F`.
+///
+/// A thread watches the file and sends the complete lines appended to it with
+/// [`BufferAppended`] events. It is stopped when the follower is dropped.
+#[derive(Debug)]
+pub(crate) struct Follower {
+    path: PathBuf,
+    stop: Arc<AtomicBool>,
+}
+
+impl Follower {
+    /// Start following the file after the `offset` bytes already loaded, or from its current
+    /// end if the loaded size is unknown. The lines are decoded with the encoding of the buffer.
+    pub(crate) fn start(
+        path: PathBuf,
+        offset: Option<u64>,
+        encoding: Encoding,
+        sender: Sender<Event>,
+    ) -> io::Result<Self> {
+        let mut tail = Tail::open(&path, encoding)?;
+        if tail.is_compressed()? {
+            return Err(io::Error::other("cannot follow a compressed file"));
+        }
+        tail.offset = match offset {
+            Some(offset) => offset,
+            None => tail.file.metadata()?.len(),
+        };
+        info!("Follow {path:?} from {}", tail.offset);
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+        let thread_path = path.clone();
+        thread::spawn(move || {
+            while !thread_stop.load(Ordering::Relaxed) {
+                match tail.poll(&thread_path) {
+                    Ok(lines) if lines.is_empty() => {}
+                    Ok(lines) => {
+                        if sender
+                            .send(BufferAppended(thread_path.clone(), lines))
+                            .is_err()
+                        {
+                            break;
+                        }
+                    }
+                    // the file may be missing for a short time while it is rotated
+                    Err(e) => warn!("Unable to follow {thread_path:?}: {e}"),
+                }
+                thread::sleep(FOLLOW_INTERVAL);
+            }
+            info!("Stop following {thread_path:?}");
+        });
+        Ok(Self { path, stop })
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for Follower {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// The reading state of a followed file
+struct Tail {
+    file: File,
+    file_id: Option<u64>,
+    encoding: Encoding,
+    /// The number of bytes already read
+    offset: u64,
+    /// The beginning of an incomplete line
+    pending: Vec<u8>,
+}
+
+impl Tail {
+    fn open(path: &Path, encoding: Encoding) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let file_id = file_id(&file.metadata()?);
+        Ok(Self {
+            file,
+            file_id,
+            encoding,
+            offset: 0,
+            pending: Vec::new(),
+        })
+    }
+
+    fn is_compressed(&mut self) -> io::Result<bool> {
+        let is_compressed = Compression::detect(&mut self.file) != Compression::None;
+        self.file.seek(SeekFrom::Start(0))?;
+        Ok(is_compressed)
+    }
+
+    /// Read the lines appended since the last poll.
+    /// If the file was rotated, the end of the old file is read before switching to the new one,
+    /// if it was truncated it is read again from the start.
+    fn poll(&mut self, path: &Path) -> io::Result<Vec<String>> {
+        let metadata = std::fs::metadata(path)?;
+        let mut lines = Vec::new();
+        if file_id(&metadata) != self.file_id {
+            info!("{path:?} was rotated");
+            self.read_lines(&mut lines)?;
+            self.flush_pending(&mut lines);
+            *self = Self::open(path, self.encoding)?;
+        } else if metadata.len() < self.offset {
+            info!("{path:?} was truncated");
+            self.offset = 0;
+            self.pending.clear();
+        }
+        self.read_lines(&mut lines)?;
+        Ok(lines)
+    }
+
+    fn read_lines(&mut self, lines: &mut Vec<String>) -> io::Result<()> {
+        self.file.seek(SeekFrom::Start(self.offset))?;
+        let from_start = self.offset == 0;
+        let read = (&mut self.file)
+            .take(MAX_READ)
+            .read_to_end(&mut self.pending)?;
+        self.offset += read as u64;
+        if from_start && let Some((_, bom_length)) = Encoding::from_bom(&self.pending) {
+            self.pending.drain(..bom_length);
+        }
+        let Some(end) = self.encoding.complete_lines_len(&self.pending) else {
+            return Ok(());
+        };
+        let remaining = self.pending.split_off(end);
+        let complete = std::mem::replace(&mut self.pending, remaining);
+        for line in LineReader::new(&complete[..], self.encoding) {
+            lines.push(line?);
+        }
+        Ok(())
+    }
+
+    /// The last line of a rotated file will not be completed
+    fn flush_pending(&mut self, lines: &mut Vec<String>) {
+        if !self.pending.is_empty() {
+            lines.push(self.encoding.decode(&self.pending).into_owned());
+            self.pending.clear();
+        }
+    }
+}
+
+#[cfg(unix)]
+fn file_id(metadata: &Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.ino())
+}
+
+#[cfg(not(unix))]
+fn file_id(_metadata: &Metadata) -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::OpenOptions;
+    use std::io::Write;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("ceos_follow_{}_{name}", std::process::id()))
+    }
+
+    fn append(path: &Path, text: &str) {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap();
+        file.write_all(text.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn test_poll_appended_lines() -> io::Result<()> {
+        let path = temp_path("append");
+        std::fs::write(&path, "old\n")?;
+        let mut tail = Tail::open(&path, Encoding::Utf8)?;
+        tail.offset = 4;
+        assert!(tail.poll(&path)?.is_empty());
+        append(&path, "a\r\nb\nincomplete");
+        assert_eq!(vec!["a", "b"], tail.poll(&path)?);
+        append(&path, " line\n");
+        assert_eq!(vec!["incomplete line"], tail.poll(&path)?);
+        std::fs::remove_file(&path)
+    }
+
+    #[test]
+    fn test_poll_truncated() -> io::Result<()> {
+        let path = temp_path("truncate");
+        std::fs::write(&path, "a long first line\n")?;
+        let mut tail = Tail::open(&path, Encoding::Utf8)?;
+        assert_eq!(vec!["a long first line"], tail.poll(&path)?);
+        std::fs::write(&path, "new\n")?;
+        assert_eq!(vec!["new"], tail.poll(&path)?);
+        std::fs::remove_file(&path)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_poll_rotated() -> io::Result<()> {
+        let path = temp_path("rotate");
+        let rotated = temp_path("rotate.1");
+        std::fs::write(&path, "a\n")?;
+        let mut tail = Tail::open(&path, Encoding::Utf8)?;
+        assert_eq!(vec!["a"], tail.poll(&path)?);
+        append(&path, "b\nc");
+        std::fs::rename(&path, &rotated)?;
+        std::fs::write(&path, "d\n")?;
+        assert_eq!(vec!["b", "c", "d"], tail.poll(&path)?);
+        std::fs::remove_file(&rotated)?;
+        std::fs::remove_file(&path)
+    }
+
+    #[test]
+    fn test_poll_utf16() -> io::Result<()> {
+        let path = temp_path("utf16");
+        let encoding = Encoding::Utf16Le;
+        std::fs::write(&path, encoding.encode("\u{FEFF}a\u{10A}\r\nb"))?;
+        let mut tail = Tail::open(&path, encoding)?;
+        assert_eq!(vec!["a\u{10A}"], tail.poll(&path)?);
+        OpenOptions::new()
+            .append(true)
+            .open(&path)?
+            .write_all(&encoding.encode("c\n"))?;
+        assert_eq!(vec!["bc"], tail.poll(&path)?);
+        std::fs::remove_file(&path)
+    }
+}
@@ -27,10 +27,6 @@ impl Line {
         &self.content
     }
 
-    pub(crate) fn contains(&self, needle: &str) -> bool {
-        self.content.contains(needle)
-    }
-
     pub(crate) const fn mem(&self) -> usize {
         self.content.capacity()
     }
@@ -47,8 +43,8 @@ impl Line {
         self.content.push_str(str);
     }
 
-    pub(crate) fn insert(&mut self, idx: usize, ch: char) {
-        self.content.insert(idx, ch);
+    pub(crate) fn insert_str(&mut self, idx: usize, str: &str) {
+        self.content.insert_str(idx, str);
     }
 }
 
@@ -1,16 +1,94 @@
 use crate::ceos::buffer::line::Line;
+use crate::ceos::buffer::mapped::MappedFile;
+use crate::ceos::buffer::spill::SpillFile;
 use log::{debug, error, warn};
 use std::borrow::Cow;
+use std::io;
 use std::io::{Read, Write};
 use std::ops::Index;
-use std::ops::RangeBounds;
+use std::ops::{Range, RangeBounds};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard};
+
+/// Counts the uses of the groups of all the buffers, orders them from the least recently used
+static USE_CLOCK: AtomicU64 = AtomicU64::new(0);
+
+/// Where the lines of a group are read from when they are not in memory
+#[derive(Debug, Clone)]
+enum Backing {
+    /// The lines compressed with LZ4 and separated by '\n'
+    Lz4(Arc<Compressed>),
+    /// The byte range of the lines in a plain file, kept until the group is modified
+    Mapped(Arc<MappedFile>, Range<usize>),
+}
+
+/// The compressed lines of a group, shared with its snapshots. Spilling them moves them to the
+/// scratch file for all the groups sharing them, so their memory is freed.
+#[derive(Debug)]
+struct Compressed(RwLock<Stored>);
+
+#[derive(Debug)]
+enum Stored {
+    Memory(Box<[u8]>),
+    /// The compressed lines moved to a scratch file to save memory
+    Spilled {
+        file: Arc<SpillFile>,
+        offset: u64,
+        len: usize,
+    },
+}
+
+impl Compressed {
+    fn new(data: Vec<u8>) -> Arc<Self> {
+        Arc::new(Self(RwLock::new(Stored::Memory(data.into()))))
+    }
+
+    fn stored(&self) -> RwLockReadGuard<'_, Stored> {
+        self.0.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The size of the compressed lines kept in memory
+    fn size(&self) -> usize {
+        match &*self.stored() {
+            Stored::Memory(data) => data.len(),
+            Stored::Spilled { .. } => 0,
+        }
+    }
+
+    fn is_spilled(&self) -> bool {
+        matches!(*self.stored(), Stored::Spilled { .. })
+    }
+
+    /// Give the compressed lines, read from the scratch file if they were spilled
+    fn with_bytes<R>(&self, f: impl FnOnce(&[u8]) -> R) -> io::Result<R> {
+        match &*self.stored() {
+            Stored::Memory(data) => Ok(f(data)),
+            Stored::Spilled { file, offset, len } => file.read(*offset, *len).map(|data| f(&data)),
+        }
+    }
+
+    fn spill(&self, file: &Arc<SpillFile>) -> io::Result<()> {
+        let mut stored = self.0.write().unwrap_or_else(PoisonError::into_inner);
+        if let Stored::Memory(data) = &*stored {
+            let len = data.len();
+            let offset = file.write(data)?;
+            *stored = Stored::Spilled {
+                file: Arc::clone(file),
+                offset,
+                len,
+            };
+        }
+        Ok(())
+    }
+}
 
 #[derive(Debug)]
 pub(crate) struct LineGroup {
     /// Contains the uncompressed data. Might be there even if the compressed data is present.
     lines: Option<Vec<Line>>,
-    /// Contains the compressed data if the group is compressed, None otherwise.
-    compressed: Option<Vec<u8>>,
+    /// Contains the compressed data or the mapped range if the group is compressed, None
+    /// otherwise. It is shared with the snapshots of the group.
+    compressed: Option<Backing>,
     // number of lines stored in this group (stable even when compressed)
     line_count: usize,
     // total UTF-8 text length of the group with one '\n' separator between lines
@@ -19,6 +97,10 @@ pub(crate) struct LineGroup {
     /// Global index (0-based) of the first line contained in this group
     first_line: usize,
     group_size: usize,
+    /// The value of the use clock when the lines were last decompressed or read
+    last_use: u64,
+    /// The memory used by the text of the decompressed lines, updated when they change
+    text_mem: usize,
 }
 
 impl LineGroup {
@@ -31,6 +113,30 @@ impl LineGroup {
             max_line_length: 0,
             first_line,
             group_size,
+            last_use: 0,
+            text_mem: 0,
+        }
+    }
+
+    /// Create a group of lines read on demand from a range of a mapped file
+    pub(crate) const fn mapped(
+        file: Arc<MappedFile>,
+        range: Range<usize>,
+        line_count: usize,
+        length: usize,
+        max_line_length: usize,
+        group_size: usize,
+    ) -> Self {
+        Self {
+            lines: None,
+            compressed: Some(Backing::Mapped(file, range)),
+            line_count,
+            length,
+            max_line_length,
+            first_line: 0,
+            group_size,
+            last_use: 0,
+            text_mem: 0,
         }
     }
 
@@ -40,6 +146,7 @@ impl LineGroup {
             error!("free called on a decompressed group");
         }
         self.lines = None;
+        self.text_mem = 0;
         // It's valid to free an empty group (no compressed data expected),
         // but for non-empty groups we expect compressed data to be present.
         debug_assert!(
@@ -65,6 +172,13 @@ impl LineGroup {
         let Some(lines) = &self.lines else {
             panic!("compress called on empty group");
         };
+        if let Some(data) = Self::compress_lines(lines) {
+            self.compressed = Some(Backing::Lz4(Compressed::new(data)));
+        }
+    }
+
+    /// Compress the lines, returns None if the compression failed
+    fn compress_lines(lines: &[Line]) -> Option<Vec<u8>> {
         // Stream (frame) compression to avoid building a large intermediate buffer
         let out = Vec::new();
         match lz4::EncoderBuilder::new().build(out) {
@@ -72,29 +186,63 @@ impl LineGroup {
                 for (i, line) in lines.iter().enumerate() {
                     if let Err(e) = encoder.write_all(line.content().as_bytes()) {
                         warn!("Failed to write to LZ4 encoder: {e}");
-                        return;
+                        return None;
                     }
-                    if i != self.line_count - 1
+                    if i != lines.len() - 1
                         && let Err(e) = encoder.write_all(b"\n")
                     {
                         warn!("Failed to write newline to LZ4 encoder: {e}");
-                        return;
+                        return None;
                     }
                 }
 
                 let (data, res) = encoder.finish();
                 match res {
-                    Ok(()) => self.compressed = Some(data),
-                    Err(e) => warn!("Failed to finalize LZ4 encoder: {e}"),
+                    Ok(()) => Some(data),
+                    Err(e) => {
+                        warn!("Failed to finalize LZ4 encoder: {e}");
+                        None
+                    }
                 }
             }
-            Err(e) => warn!("Failed to build LZ4 encoder: {e}"),
+            Err(e) => {
+                warn!("Failed to build LZ4 encoder: {e}");
+                None
+            }
+        }
+    }
+
+    /// Returns a compressed copy of the group, the compressed data is shared with this group
+    /// if it is already compressed.
+    pub(crate) fn snapshot(&self) -> LineGroup {
+        let compressed = match (&self.compressed, &self.lines) {
+            (Some(backing), _) => Some(backing.clone()),
+            (None, Some(lines)) if !lines.is_empty() => {
+                Self::compress_lines(lines).map(|data| Backing::Lz4(Compressed::new(data)))
+            }
+            _ => None,
+        };
+        Self {
+            lines: if compressed.is_none() {
+                Some(Vec::with_capacity(self.group_size))
+            } else {
+                None
+            },
+            compressed,
+            line_count: self.line_count,
+            length: self.length,
+            max_line_length: self.max_line_length,
+            first_line: self.first_line,
+            group_size: self.group_size,
+            last_use: 0,
+            text_mem: 0,
         }
     }
 
     pub(crate) fn eventually_decompress(&mut self) {
         if self.lines.is_some() {
             debug!("eventually_decompress called on a decompressed group");
+            self.touch();
             return;
         }
         if self.compressed.is_none() {
@@ -111,9 +259,16 @@ impl LineGroup {
         let lines = self.decompress_lines();
         debug_assert!(!lines.is_empty());
         let decompressed_line_count = lines.len();
+        self.text_mem = Self::text_mem(&lines);
         self.lines = Some(lines);
-        // successful decompression; drop compressed data
-        self.compressed = None;
+        self.touch();
+        // successful decompression; drop compressed data, a mapped or spilled range costs
+        // nothing and allows freeing the lines without compressing them
+        if let Some(Backing::Lz4(compressed)) = &self.compressed
+            && !compressed.is_spilled()
+        {
+            self.compressed = None;
+        }
         #[cfg(debug_assertions)]
         if self.line_count != decompressed_line_count {
             warn!(
@@ -126,28 +281,45 @@ impl LineGroup {
     /// Decompresses the group's compressed data and returns the resulting Vec<Line>.
     /// On failure, returns an empty Vec.
     pub(crate) fn decompress_lines(&self) -> Vec<Line> {
-        let Some(data) = self.compressed.as_deref() else {
-            return Vec::new();
-        };
+        match &self.compressed {
+            Some(Backing::Lz4(compressed)) => {
+                match compressed.with_bytes(|data| self.decode(data)) {
+                    Ok(lines) => lines,
+                    Err(e) => {
+                        // keep the line count consistent, the lines are lost
+                        warn!("Failed to read the spilled lines: {e}");
+                        vec![Line::default(); self.line_count]
+                    }
+                }
+            }
+            Some(Backing::Mapped(file, range)) => {
+                // keep the line count consistent if the file is no longer readable, or if the
+                // range holds other lines since the file was rewritten
+                let mut lines = file.lines(range).unwrap_or_default();
+                lines.resize_with(self.line_count, Line::default);
+                lines
+            }
+            None => Vec::new(),
+        }
+    }
 
+    /// Decode the lines compressed with LZ4
+    fn decode(&self, data: &[u8]) -> Vec<Line> {
         let cursor = std::io::Cursor::new(data);
         match lz4::Decoder::new(cursor) {
             Ok(mut decoder) => {
                 let mut bytes = Vec::new();
                 match decoder.read_to_end(&mut bytes) {
-                    Ok(_) => match String::from_utf8(bytes) {
-                        Ok(text) => {
-                            if text.is_empty() {
-                                Vec::new()
-                            } else {
-                                text.split('\n').map(Line::from).collect()
-                            }
-                        }
-                        Err(e) => {
-                            warn!("Failed to decode UTF-8 after LZ4 stream decompress: {}", e);
+                    Ok(_) => {
+                        // the lines were valid strings when compressed, a lossy decoding keeps
+                        // the group readable even if the data was damaged
+                        let text = String::from_utf8_lossy(&bytes);
+                        if self.line_count == 0 {
                             Vec::new()
+                        } else {
+                            text.split('\n').map(Line::from).collect()
                         }
-                    },
+                    }
                     Err(e) => {
                         warn!("Failed to read from LZ4 decoder: {}", e);
                         Vec::new()
@@ -162,11 +334,16 @@ impl LineGroup {
     }
 
     pub(crate) fn push(&mut self, line: Line) {
+        if self.lines.is_none() {
+            // lines are appended to the last group after the loading when following a file
+            self.decompress();
+        }
         let line_length = line.len();
 
         self.length += line_length + 1;
         self.line_count += 1;
         self.max_line_length = line_length.max(self.max_line_length);
+        self.text_mem += line.mem();
         if let Some(lines) = &mut self.lines {
             lines.push(line);
         }
@@ -209,6 +386,63 @@ impl LineGroup {
         self.first_line = value;
     }
 
+    /// Move the compressed lines to the scratch file, the group and its snapshots keep their
+    /// position in it
+    pub(crate) fn spill(&self, file: &Arc<SpillFile>) -> io::Result<()> {
+        if let Some(Backing::Lz4(compressed)) = &self.compressed {
+            compressed.spill(file)?;
+        }
+        Ok(())
+    }
+
+    /// Identifies the compressed lines kept in memory, they are shared with the snapshots of
+    /// the group. None if they are not in memory.
+    pub(crate) fn compressed_id(&self) -> Option<usize> {
+        match &self.compressed {
+            Some(Backing::Lz4(compressed)) if !compressed.is_spilled() => {
+                Some(Arc::as_ptr(compressed).addr())
+            }
+            _ => None,
+        }
+    }
+
+    /// Mark the group as the most recently used one
+    fn touch(&mut self) {
+        self.last_use = USE_CLOCK.fetch_add(1, Ordering::Relaxed) + 1;
+    }
+
+    /// The current value of the use clock, the groups used after have a greater `last_use`
+    pub(crate) fn use_clock() -> u64 {
+        USE_CLOCK.load(Ordering::Relaxed)
+    }
+
+    pub(crate) const fn last_use(&self) -> u64 {
+        self.last_use
+    }
+
+    /// Returns true if the lines of this group are read from a mapped file
+    pub(crate) const fn is_mapped(&self) -> bool {
+        matches!(self.compressed, Some(Backing::Mapped(..)))
+    }
+
+    /// Compress the lines read from a mapped file, the group does not depend on the file anymore
+    pub(crate) fn unmap(&mut self) {
+        if !self.is_mapped() {
+            return;
+        }
+        if self.lines.is_some() {
+            self.compressed = None;
+            return;
+        }
+        let lines = self.decompress_lines();
+        self.compressed =
+            Self::compress_lines(&lines).map(|data| Backing::Lz4(Compressed::new(data)));
+        if self.compressed.is_none() {
+            self.text_mem = Self::text_mem(&lines);
+            self.lines = Some(lines);
+        }
+    }
+
     /// Returns true if this group currently holds compressed data
     pub(crate) const fn is_compressed(&self) -> bool {
         self.compressed.is_some()
@@ -248,9 +482,9 @@ impl LineGroup {
         let should_decompress = self.lines.is_none();
         if should_decompress {
             self.decompress();
-            // free compresed data as we will modify the line array
-            self.compressed = None;
         }
+        // free compresed data as we will modify the line array
+        self.compressed = None;
         debug_assert!(self.lines.is_some());
         if let Some(lines) = &mut self.lines {
             lines.iter_mut().for_each(filter);
@@ -273,9 +507,9 @@ impl LineGroup {
         let should_decompress = self.lines.is_none();
         if should_decompress {
             self.decompress();
-            // free compresed data as we will modify the line array
-            self.compressed = None;
         }
+        // free compresed data as we will modify the line array
+        self.compressed = None;
         debug_assert!(self.lines.is_some());
 
         if let Some(lines) = &mut self.lines {
@@ -302,11 +536,12 @@ impl LineGroup {
             self.line_count = lines.len();
             self.length = length;
             self.max_line_length = max_line_length;
+            self.text_mem = Self::text_mem(lines);
         }
     }
 
     pub(crate) fn retain<F: FnMut(&Line) -> bool>(&mut self, f: F) {
-        let compressed = self.is_compressed();
+        let compressed = self.lines.is_none();
         if compressed {
             self.decompress();
         }
@@ -319,7 +554,7 @@ impl LineGroup {
                 self.compute_metadata();
             }
         }
-        if compressed {
+        if compressed && !self.is_compressed() {
             self.compress();
         }
     }
@@ -328,7 +563,7 @@ impl LineGroup {
     where
         R: RangeBounds<usize>,
     {
-        let compressed = self.is_compressed();
+        let compressed = self.lines.is_none();
         if compressed {
             self.decompress();
         }
@@ -342,14 +577,14 @@ impl LineGroup {
         }
     }
 
-    pub(crate) fn insert_line(&mut self, line_number: usize, line: Line) {
-        let compressed = self.is_compressed();
+    pub(crate) fn insert_lines(&mut self, line_number: usize, new_lines: Vec<Line>) {
+        let compressed = self.lines.is_none();
         if compressed {
             self.decompress();
         }
         self.compressed = None;
         if let Some(lines) = &mut self.lines {
-            lines.insert(line_number, line);
+            lines.splice(line_number..line_number, new_lines);
         }
         self.compute_metadata();
         if compressed {
@@ -358,21 +593,25 @@ impl LineGroup {
     }
 
     pub(crate) fn mem(&self) -> usize {
-        let vec_overhead = std::mem::size_of::<Vec<Line>>();
-        if let Some(lines) = &self.lines {
-            let array_mem = lines.capacity() * std::mem::size_of::<Line>();
-            let strings_mem: usize = lines.iter().map(|line| line.mem()).sum();
-            vec_overhead + array_mem + strings_mem + self.compressed_size()
-        } else {
-            vec_overhead + self.compressed_size()
-        }
+        std::mem::size_of::<Vec<Line>>() + self.decompressed_mem() + self.compressed_size()
+    }
+
+    /// The memory used by the decompressed lines, freed when the group is evicted
+    pub(crate) fn decompressed_mem(&self) -> usize {
+        self.lines.as_ref().map_or(0, |lines| {
+            lines.capacity() * std::mem::size_of::<Line>() + self.text_mem
+        })
+    }
+
+    fn text_mem(lines: &[Line]) -> usize {
+        lines.iter().map(Line::mem).sum()
     }
 
     pub fn compressed_size(&self) -> usize {
-        self.compressed
-            .as_ref()
-            .map(|data| data.len())
-            .unwrap_or_default()
+        match &self.compressed {
+            Some(Backing::Lz4(compressed)) => compressed.size(),
+            Some(Backing::Mapped(..)) | None => 0,
+        }
     }
 
     #[cfg(debug_assertions)]
@@ -470,6 +709,25 @@ mod tests {
         assert!(g[1].content().ends_with('x'));
     }
 
+    #[test]
+    fn decompressed_mem_follows_the_lines() {
+        let walked = |g: &LineGroup| {
+            g.lines.as_ref().map_or(0, |lines| {
+                lines.capacity() * std::mem::size_of::<Line>() + LineGroup::text_mem(lines)
+            })
+        };
+        let mut g = lg_from_strs(&["one", "two"]);
+        assert_eq!(walked(&g), g.decompressed_mem());
+        g.filter_line_mut(0, |l| *l = Line::from("a longer line"));
+        g.insert_lines(1, vec![Line::from("inserted")]);
+        assert_eq!(walked(&g), g.decompressed_mem());
+        g.compress();
+        g.free();
+        assert_eq!(0, g.decompressed_mem());
+        g.decompress();
+        assert_eq!(walked(&g), g.decompressed_mem());
+    }
+
     #[test]
     fn retain_and_drain_update_metadata() {
         let mut g = lg_from_strs(&["one", "two", "three", "four"]);
@@ -516,6 +774,19 @@ mod tests {
         assert_eq!(g[2].content(), "ghi");
     }
 
+    #[test]
+    fn snapshot_keeps_content() {
+        let mut g = lg_from_strs(&["abc", "def"]);
+        let mut snapshot = g.snapshot();
+        assert!(snapshot.is_compressed());
+        assert!(!g.is_compressed());
+        g.filter_lines_mut(|l| l.push_str("x"));
+        snapshot.decompress();
+        assert_eq!(snapshot.line_count(), 2);
+        assert_eq!(snapshot[0].content(), "abc");
+        assert_eq!(snapshot[1].content(), "def");
+    }
+
     #[test]
     fn compress_free_preserves_metadata() {
         let mut g = lg_from_strs(&["test1", "test2"]);
@@ -0,0 +1,66 @@
+/// A filtered view of a buffer.
+///
+/// It doesn't own any text, it only maps the visible lines to the lines of the buffer,
+/// so dropping the view shows the whole buffer again.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct LineView {
+    /// The (0-based) buffer line indexes of the visible lines, sorted
+    lines: Vec<usize>,
+}
+
+impl LineView {
+    pub(crate) fn new(lines: Vec<usize>) -> Self {
+        debug_assert!(lines.is_sorted());
+        Self { lines }
+    }
+
+    pub(crate) const fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub(crate) fn lines(&self) -> &[usize] {
+        &self.lines
+    }
+
+    /// Returns the buffer line displayed at the given view line
+    pub(crate) fn buffer_line(&self, line: usize) -> usize {
+        self.lines[line]
+    }
+
+    /// Returns the view line displaying the given buffer line, or the next visible one if it is
+    /// hidden.
+    pub(crate) fn view_line(&self, buffer_line: usize) -> usize {
+        self.lines.partition_point(|line| *line < buffer_line)
+    }
+
+    pub(crate) fn contains(&self, buffer_line: usize) -> bool {
+        self.lines.binary_search(&buffer_line).is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(0, 0)]
+    #[case(1, 0)]
+    #[case(2, 1)]
+    #[case(5, 2)]
+    #[case(8, 3)]
+    #[case(9, 4)]
+    fn test_view_line(#[case] buffer_line: usize, #[case] expected: usize) {
+        let view = LineView::new(vec![1, 2, 5, 8]);
+        assert_eq!(expected, view.view_line(buffer_line));
+    }
+
+    #[test]
+    fn test_buffer_line_and_contains() {
+        let view = LineView::new(vec![1, 2, 5, 8]);
+        assert_eq!(4, view.len());
+        assert_eq!(5, view.buffer_line(2));
+        assert!(view.contains(8));
+        assert!(!view.contains(3));
+    }
+}
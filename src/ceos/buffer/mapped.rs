@@ -0,0 +1,226 @@
+use crate::ceos::buffer::line::Line;
+use crate::ceos::buffer::line_group::LineGroup;
+use crate::ceos::tools::encoding::{Encoding, LineReader};
+use log::warn;
+use memmap2::Mmap;
+use rayon::prelude::*;
+use std::fs::{File, Metadata};
+use std::io;
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+/// A file modified more recently is probably still written, it is loaded in memory as its
+/// mapping could not be read anymore once the file changes
+const WRITING_DELAY: Duration = Duration::from_secs(60);
+
+/// A plain file mapped in memory, the groups of its buffer keep the byte range of their lines
+/// and read them on demand, so only the lines being displayed or edited are in memory.
+#[derive(Debug)]
+pub(crate) struct MappedFile {
+    /// Kept to check the file did not change before reading the mapping
+    file: File,
+    mmap: Mmap,
+    encoding: Encoding,
+    /// The size and the modification time of the file when it was mapped
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+impl MappedFile {
+    pub(crate) fn open(file: &File, encoding: Encoding) -> io::Result<Self> {
+        let metadata = file.metadata()?;
+        // SAFETY: reading a mapping beyond the end of a file truncated by another process
+        // faults. The files still being written and the followed ones are loaded in memory,
+        // the editor saves a mapped file by replacing it, and the mapping is only read while
+        // the file keeps the size and the modification time it had when it was mapped. A file
+        // truncated between this check and the read still faults, it is the price of not
+        // copying a file that is not written anymore.
+        let mmap = unsafe { Mmap::map(file)? };
+        Ok(Self {
+            file: file.try_clone()?,
+            mmap,
+            encoding,
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+        })
+    }
+
+    /// Returns true if the file was modified too recently to be mapped
+    pub(crate) fn is_being_written(metadata: &Metadata) -> bool {
+        metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age < WRITING_DELAY)
+    }
+
+    pub(crate) fn bytes(&self) -> &[u8] {
+        &self.mmap
+    }
+
+    /// Decode the lines stored in the byte range.
+    /// Returns None if the file changed since it was mapped.
+    pub(crate) fn lines(&self, range: &Range<usize>) -> Option<Vec<Line>> {
+        match self.file.metadata() {
+            Ok(metadata)
+                if metadata.len() == self.len && metadata.modified().ok() == self.modified => {}
+            Ok(_) => {
+                warn!("The mapped file changed, its lines cannot be read anymore");
+                return None;
+            }
+            Err(e) => {
+                warn!("Unable to check the mapped file: {e}");
+                return None;
+            }
+        }
+        let bytes = self.mmap.get(range.clone())?;
+        Some(
+            LineReader::new(bytes, self.encoding)
+                .map_while(Result::ok)
+                .map(Line::from)
+                .collect(),
+        )
+    }
+}
+
+/// Split the bytes in chunks of about `chunk_size` bytes ending on a newline
+pub(crate) fn split_chunks(bytes: &[u8], chunk_size: usize) -> Vec<Range<usize>> {
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < bytes.len() {
+        let end = (start + chunk_size).min(bytes.len());
+        let end = bytes[end..]
+            .iter()
+            .position(|b| *b == b'\n')
+            .map_or(bytes.len(), |newline| end + newline + 1);
+        chunks.push(start..end);
+        start = end;
+    }
+    chunks
+}
+
+/// Build the groups of the lines stored in the range of the file without keeping their text.
+/// The chunks of the range are scanned in parallel, the groups are numbered from 0.
+pub(crate) fn scan(
+    file: &Arc<MappedFile>,
+    range: Range<usize>,
+    group_size: usize,
+    chunk_size: usize,
+) -> Vec<LineGroup> {
+    let bytes = &file.bytes()[range.clone()];
+    split_chunks(bytes, chunk_size)
+        .into_par_iter()
+        .map(|chunk| {
+            let chunk = range.start + chunk.start..range.start + chunk.end;
+            scan_chunk(file, chunk, group_size)
+        })
+        .collect::<Vec<_>>()
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+/// Build the groups of a chunk ending on a newline, the last one may not be full
+fn scan_chunk(file: &Arc<MappedFile>, chunk: Range<usize>, group_size: usize) -> Vec<LineGroup> {
+    let bytes = &file.bytes()[chunk.clone()];
+    let mut groups = Vec::new();
+    let mut group_start = chunk.start;
+    let mut line_count = 0;
+    let mut length = 0;
+    let mut max_line_length = 0;
+    let mut offset = chunk.start;
+    let text = bytes.strip_suffix(b"\n").unwrap_or(bytes);
+    for line in text.split(|b| *b == b'\n') {
+        offset += line.len() + 1;
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        // the length of the decoded line, as computed by the loaded groups
+        let line_length = file.encoding.decode(line).len();
+        line_count += 1;
+        length += line_length + 1;
+        max_line_length = max_line_length.max(line_length);
+        if line_count == group_size {
+            let end = offset.min(chunk.end);
+            groups.push(LineGroup::mapped(
+                Arc::clone(file),
+                group_start..end,
+                line_count,
+                length,
+                max_line_length,
+                group_size,
+            ));
+            group_start = end;
+            line_count = 0;
+            length = 0;
+            max_line_length = 0;
+        }
+    }
+    if line_count > 0 {
+        groups.push(LineGroup::mapped(
+            Arc::clone(file),
+            group_start..chunk.end,
+            line_count,
+            length,
+            max_line_length,
+            group_size,
+        ));
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scan_reads_the_same_lines_as_the_loader() {
+        let text = b"first\r\nsecond line\n\ncaf\xC3\xA9\nlast";
+        let path = std::env::temp_dir().join(format!("ceos_mapped_{}", std::process::id()));
+        std::fs::write(&path, text).unwrap();
+        let file = File::open(&path).unwrap();
+        let mapped = Arc::new(MappedFile::open(&file, Encoding::Utf8).unwrap());
+
+        let groups = scan(&mapped, 0..text.len(), 2, 8);
+
+        let lines: Vec<String> = groups
+            .iter()
+            .flat_map(|g| {
+                g.lines()
+                    .iter()
+                    .map(|l| l.content().to_owned())
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        let expected: Vec<String> = LineReader::new(&text[..], Encoding::Utf8)
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(expected, lines);
+        for group in &groups {
+            let lines = group.lines();
+            assert_eq!(lines.len(), group.line_count());
+            assert_eq!(
+                lines.iter().map(|l| l.len() + 1).sum::<usize>(),
+                group.len()
+            );
+            assert_eq!(0, group.compressed_size());
+        }
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn changed_file_keeps_the_line_count() {
+        let path = std::env::temp_dir().join(format!("ceos_mapped_changed_{}", std::process::id()));
+        std::fs::write(&path, "1\n2\n3\n4\n").unwrap();
+        let file = File::open(&path).unwrap();
+        let mapped = Arc::new(MappedFile::open(&file, Encoding::Utf8).unwrap());
+        let groups = scan(&mapped, 0..8, 2, 8);
+
+        // rewritten in place with other lines, like a log rotated by copying it
+        std::fs::write(&path, "1\n\n\n\n\n\n\n\n\n\n").unwrap();
+
+        for group in &groups {
+            assert_eq!(group.line_count(), group.lines().len());
+        }
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,225 @@
+use crate::ceos::buffer::buffer::Buffer;
+use crate::ceos::progress_manager::{loading_id, new_loading};
+use crate::ceos::tools::cancellation::CancellationToken;
+use crate::ceos::tools::compression::Compression;
+use crate::ceos::tools::encoding::LineReader;
+use crate::ceos::tools::timestamp::TimestampFormat;
+use crate::event::Event;
+use crate::event::Event::{BufferLoading, BufferLoadingStarted, OperationFinished};
+use log::info;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
+use std::time::{Duration, Instant};
+
+/// A line with a timestamp followed by the lines without timestamp, like a stack trace
+#[derive(Debug)]
+struct Entry {
+    timestamp: i64,
+    lines: Vec<String>,
+}
+
+/// The entries of a log file, read one after the other
+struct Source {
+    /// The name of the file put before each line
+    prefix: String,
+    lines: LineReader<Box<dyn BufRead + Send>>,
+    /// The first line of the next entry, already read while looking for the end of the current one
+    next_line: Option<(i64, String)>,
+}
+
+impl Source {
+    fn new(prefix: String, lines: LineReader<Box<dyn BufRead + Send>>) -> Self {
+        Self {
+            prefix,
+            lines,
+            next_line: None,
+        }
+    }
+
+    /// Returns the next entry of the file.
+    /// The lines before the first timestamp are kept at the beginning of the merged buffer.
+    fn next_entry(&mut self, format: &TimestampFormat) -> io::Result<Option<Entry>> {
+        let (timestamp, first_line) = match self.next_line.take() {
+            Some(next_line) => next_line,
+            None => match self.lines.next() {
+                Some(line) => {
+                    let line = line?;
+                    (format.parse(&line).unwrap_or(i64::MIN), line)
+                }
+                None => return Ok(None),
+            },
+        };
+        let prefix = &self.prefix;
+        let mut lines = vec![format!("{prefix} {first_line}")];
+        for line in self.lines.by_ref() {
+            let line = line?;
+            if let Some(timestamp) = format.parse(&line) {
+                self.next_line = Some((timestamp, line));
+                break;
+            }
+            lines.push(format!("{prefix} {line}"));
+        }
+        Ok(Some(Entry { timestamp, lines }))
+    }
+}
+
+/// Merge log files into a new buffer, the entries are sorted by timestamp.
+/// Each line starts with the name of its file and the lines without timestamp stay after
+/// the line they follow.
+/// The merge fails with an `Interrupted` error if it is cancelled.
+pub(crate) fn merge_files(
+    paths: &[PathBuf],
+    format: &TimestampFormat,
+    sender: Sender<Event>,
+    cancellation: &CancellationToken,
+) -> io::Result<Buffer> {
+    let names: Vec<String> = paths
+        .iter()
+        .map(|path| {
+            path.file_name()
+                .unwrap_or(path.as_os_str())
+                .to_string_lossy()
+                .into_owned()
+        })
+        .collect();
+    let name = format!("merge of {}", names.join(", "));
+    info!("{name} with the timestamp format {format}");
+    let width = names
+        .iter()
+        .map(|name| name.chars().count())
+        .max()
+        .unwrap_or(0)
+        + 2;
+    let mut sources = Vec::with_capacity(paths.len());
+    let mut total_size = 0;
+    for (path, name) in paths.iter().zip(&names) {
+        let (size, mut reader) = open_file(path)?;
+        let encoding = Buffer::detect_encoding(&mut reader, None)?;
+        total_size += size;
+        let prefix = format!("{:<width$}", format!("[{name}]"));
+        sources.push(Source::new(prefix, LineReader::new(reader, encoding)));
+    }
+
+    let loading = new_loading();
+    let _ = sender.send(BufferLoadingStarted(
+        loading,
+        PathBuf::from(&name),
+        total_size,
+        cancellation.clone(),
+    ));
+    let mut buffer = Buffer::new_stream_buffer(sender.clone());
+    buffer.name = Some(name);
+    buffer.loading = loading;
+    let merged = merge(sources, format, &mut buffer, |read| {
+        if cancellation.is_cancelled() {
+            return Err(io::Error::new(
+                io::ErrorKind::Interrupted,
+                "merge cancelled",
+            ));
+        }
+        let _ = sender.send(BufferLoading(loading, read, total_size));
+        Ok(())
+    });
+    let _ = sender.send(OperationFinished(loading_id(loading)));
+    merged?;
+    Ok(buffer)
+}
+
+/// Returns the uncompressed size of a file and a reader of its text
+fn open_file(path: &Path) -> io::Result<(usize, Box<dyn BufRead + Send>)> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let file_size = std::fs::metadata(path)?.len() as usize;
+    let compression = Compression::detect(&mut reader);
+    if compression == Compression::None {
+        Ok((file_size, Box::new(reader)))
+    } else {
+        let size = compression.uncompressed_size(path, file_size);
+        Ok((size, Box::new(BufReader::new(compression.decoder(reader)?))))
+    }
+}
+
+/// K-way merge of the entries of the sources, the entries with the same timestamp are taken
+/// in the order of the sources.
+/// The progress is called with the number of bytes read from time to time, the merge stops
+/// if it returns an error.
+fn merge(
+    mut sources: Vec<Source>,
+    format: &TimestampFormat,
+    buffer: &mut Buffer,
+    mut progress: impl FnMut(usize) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut heads = Vec::with_capacity(sources.len());
+    let mut heap = BinaryHeap::new();
+    for (index, source) in sources.iter_mut().enumerate() {
+        let entry = source.next_entry(format)?;
+        if let Some(entry) = &entry {
+            heap.push(Reverse((entry.timestamp, index)));
+        }
+        heads.push(entry);
+    }
+    let mut start = Instant::now();
+    while let Some(Reverse((_, index))) = heap.pop() {
+        let entry = heads[index]
+            .take()
+            .expect("the entry of the source is in the heap");
+        buffer.append_lines(entry.lines);
+        let next = sources[index].next_entry(format)?;
+        if let Some(next) = &next {
+            heap.push(Reverse((next.timestamp, index)));
+        }
+        heads[index] = next;
+        if start.elapsed() > Duration::from_millis(50) {
+            progress(sources.iter().map(|source| source.lines.read()).sum())?;
+            start = Instant::now();
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ceos::tools::encoding::Encoding;
+
+    fn source(prefix: &str, text: &'static str) -> Source {
+        let reader: Box<dyn BufRead + Send> = Box::new(text.as_bytes());
+        Source::new(prefix.to_string(), LineReader::new(reader, Encoding::Utf8))
+    }
+
+    #[test]
+    fn test_merge() {
+        let api = source(
+            "[api]",
+            "2024-01-31 12:00:01,000 request\n2024-01-31 12:00:03,000 error\n\tat Api.call\n",
+        );
+        let db = source(
+            "[db] ",
+            "started\n2024-01-31 12:00:02,000 query\n2024-01-31 12:00:03,000 slow\n",
+        );
+        let mut buffer = Buffer::new_stream_buffer(std::sync::mpsc::channel().0);
+        merge(vec![api, db], &TimestampFormat::Auto, &mut buffer, |_| {
+            Ok(())
+        })
+        .unwrap();
+        buffer.prepare_range_for_read(..);
+        let lines: Vec<&str> = (0..buffer.line_count())
+            .map(|line| buffer.line_text(line))
+            .collect();
+        assert_eq!(
+            vec![
+                "[db]  started",
+                "[api] 2024-01-31 12:00:01,000 request",
+                "[db]  2024-01-31 12:00:02,000 query",
+                "[api] 2024-01-31 12:00:03,000 error",
+                "[api] \tat Api.call",
+                "[db]  2024-01-31 12:00:03,000 slow",
+            ],
+            lines
+        );
+    }
+}
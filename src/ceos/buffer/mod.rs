@@ -1,4 +1,13 @@
+pub(crate) mod archive;
+pub(crate) mod budget;
 pub(crate) mod buffer;
+pub(crate) mod follower;
 pub(crate) mod line;
-pub(super) mod line_group;
+pub(crate) mod line_group;
+pub(crate) mod line_view;
+pub(crate) mod mapped;
+pub(crate) mod spill;
+pub(crate) mod merge;
+pub(crate) mod stream;
 pub(crate) mod text_range;
+pub(crate) mod undo;
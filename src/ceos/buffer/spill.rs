@@ -0,0 +1,201 @@
+use log::{info, warn};
+use std::fs::{File, TryLockError};
+use std::io;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+/// The size in bytes of the compressed lines a buffer keeps in memory, the least recently used
+/// ones are moved to its scratch file above it
+static THRESHOLD: AtomicUsize = AtomicUsize::new(usize::MAX);
+/// Numbers the scratch files of the process
+static NEXT_FILE: AtomicUsize = AtomicUsize::new(0);
+/// The lock file held while the process runs, the scratch files of the processes that don't
+/// hold their lock anymore are removed at startup
+static LOCK: OnceLock<Option<File>> = OnceLock::new();
+
+pub(crate) fn set_threshold(bytes: usize) {
+    THRESHOLD.store(bytes, Ordering::Relaxed);
+}
+
+pub(crate) fn threshold() -> usize {
+    THRESHOLD.load(Ordering::Relaxed)
+}
+
+/// The directory of the scratch files of all the processes
+fn scratch_dir() -> PathBuf {
+    std::env::temp_dir().join("ceos")
+}
+
+fn lock_path(dir: &Path, pid: u32) -> PathBuf {
+    dir.join(format!("ceos-{pid}.lock"))
+}
+
+/// Lock the lock file of the process, it is released when the process ends even if it crashes
+fn lock_process(dir: &Path) -> Option<File> {
+    let lock = File::create(lock_path(dir, std::process::id()))
+        .and_then(|file| file.try_lock().map(|()| file).map_err(io::Error::from));
+    match lock {
+        Ok(file) => Some(file),
+        Err(e) => {
+            warn!("Unable to lock the scratch directory {dir:?}: {e}");
+            None
+        }
+    }
+}
+
+/// Remove the scratch files left by the processes that ended without removing them
+pub(crate) fn clean_scratch_dir() {
+    remove_stale_files(&scratch_dir());
+}
+
+fn remove_stale_files(dir: &Path) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let Some(pid) = name
+            .strip_prefix("ceos-")
+            .and_then(|name| name.strip_suffix(".lock"))
+            .and_then(|pid| pid.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        if pid == std::process::id() {
+            continue;
+        }
+        let Ok(lock) = File::open(entry.path()) else {
+            continue;
+        };
+        match lock.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => continue,
+            Err(TryLockError::Error(e)) => {
+                warn!("Unable to check {:?}: {e}", entry.path());
+                continue;
+            }
+        }
+        info!("Remove the scratch files of the process {pid}");
+        let prefix = format!("ceos-{pid}-");
+        for spilled in std::fs::read_dir(dir).into_iter().flatten().flatten() {
+            if spilled.file_name().to_string_lossy().starts_with(&prefix) {
+                let _ = std::fs::remove_file(spilled.path());
+            }
+        }
+        drop(lock);
+        let _ = std::fs::remove_file(entry.path());
+    }
+}
+
+/// A scratch file holding the compressed lines moved out of memory, it is removed when the
+/// buffer and its snapshots are dropped
+#[derive(Debug)]
+pub(crate) struct SpillFile {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl SpillFile {
+    pub(crate) fn create() -> io::Result<Self> {
+        let dir = scratch_dir();
+        std::fs::create_dir_all(&dir)?;
+        LOCK.get_or_init(|| lock_process(&dir));
+        Self::create_in(&dir)
+    }
+
+    fn create_in(dir: &Path) -> io::Result<Self> {
+        let number = NEXT_FILE.fetch_add(1, Ordering::Relaxed);
+        let path = dir.join(format!("ceos-{}-{number}.spill", std::process::id()));
+        let file = File::options()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        info!("Spill compressed lines to {path:?}");
+        Ok(Self {
+            path,
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Append the bytes to the file, returns their offset
+    pub(crate) fn write(&self, bytes: &[u8]) -> io::Result<u64> {
+        let mut file = self
+            .file
+            .lock()
+            .map_err(|_| io::Error::other("poisoned lock"))?;
+        let offset = file.seek(SeekFrom::End(0))?;
+        file.write_all(bytes)?;
+        Ok(offset)
+    }
+
+    pub(crate) fn read(&self, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+        let mut file = self
+            .file
+            .lock()
+            .map_err(|_| io::Error::other("poisoned lock"))?;
+        file.seek(SeekFrom::Start(offset))?;
+        let mut bytes = vec![0; len];
+        file.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            warn!("Unable to remove {:?}: {e}", self.path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ceos_spill_{name}_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn spilled_bytes_are_read_back() -> io::Result<()> {
+        let dir = test_dir("read");
+        let file = SpillFile::create_in(&dir)?;
+        let first = file.write(b"first")?;
+        let second = file.write(b"second")?;
+        assert_eq!(b"second".to_vec(), file.read(second, 6)?);
+        assert_eq!(b"first".to_vec(), file.read(first, 5)?);
+        let path = file.path.clone();
+        drop(file);
+        assert!(!path.exists());
+        std::fs::remove_dir_all(dir)
+    }
+
+    #[test]
+    fn files_of_ended_processes_are_removed() -> io::Result<()> {
+        let dir = test_dir("stale");
+        // a process that ended without removing its files
+        let stale_pid = u32::MAX;
+        File::create(lock_path(&dir, stale_pid))?;
+        let stale = dir.join(format!("ceos-{stale_pid}-0.spill"));
+        File::create(&stale)?;
+        // a running process holding its lock
+        let running_pid = u32::MAX - 1;
+        let lock = File::create(lock_path(&dir, running_pid))?;
+        lock.try_lock().map_err(io::Error::from)?;
+        let running = dir.join(format!("ceos-{running_pid}-0.spill"));
+        File::create(&running)?;
+
+        remove_stale_files(&dir);
+
+        assert!(!stale.exists());
+        assert!(!lock_path(&dir, stale_pid).exists());
+        assert!(running.exists());
+        drop(lock);
+        std::fs::remove_dir_all(dir)
+    }
+}
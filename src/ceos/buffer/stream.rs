@@ -0,0 +1,107 @@
+use crate::ceos::tools::encoding::{Encoding, LineReader};
+use crate::event::Event;
+use crate::event::Event::{BufferStreamed, BufferStreaming};
+use log::{info, warn};
+use std::io;
+use std::io::{BufReader, Read};
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The maximum delay before the lines read are sent to the ui
+const SEND_INTERVAL: Duration = Duration::from_millis(100);
+/// The maximum number of lines sent in one event
+const MAX_LINES: usize = 100_000;
+const READ_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// Read a stream of unknown size like the standard input in a background thread.
+///
+/// The lines are sent in batches with [`BufferStreaming`] events so they can be displayed
+/// while the stream is still open, [`BufferStreamed`] is sent at the end.
+pub(crate) fn stream_lines(
+    reader: impl Read + Send + 'static,
+    encoding: Encoding,
+    sender: Sender<Event>,
+) {
+    thread::spawn(move || {
+        let result = read_lines(
+            LineReader::new(BufReader::with_capacity(READ_BUFFER_SIZE, reader), encoding),
+            |lines, read| sender.send(BufferStreaming(lines, read)).is_ok(),
+        );
+        match result {
+            Ok(read) => info!("End of stream after {read} bytes"),
+            Err(e) => warn!("Unable to read the stream: {e}"),
+        }
+        let _ = sender.send(BufferStreamed);
+    });
+}
+
+/// Read the lines and give them in batches with the number of bytes read so far.
+/// The reading stops when `send` returns false.
+/// Returns the number of bytes read.
+fn read_lines<R: Read>(
+    mut reader: LineReader<BufReader<R>>,
+    mut send: impl FnMut(Vec<String>, usize) -> bool,
+) -> io::Result<usize> {
+    let mut lines = Vec::new();
+    let mut start = Instant::now();
+    let mut first = true;
+    while let Some(line) = reader.next() {
+        let line = line?;
+        if std::mem::take(&mut first) {
+            // the stream may start with a byte order mark
+            lines.push(line.strip_prefix('\u{FEFF}').unwrap_or(&line).to_owned());
+        } else {
+            lines.push(line);
+        }
+        // the lines are sent before waiting for more data, or every SEND_INTERVAL
+        if (lines.len() >= MAX_LINES
+            || start.elapsed() > SEND_INTERVAL
+            || reader.get_ref().buffer().is_empty())
+            && !send(std::mem::take(&mut lines), reader.read())
+        {
+            return Ok(reader.read());
+        }
+        if lines.is_empty() {
+            start = Instant::now();
+        }
+    }
+    if !lines.is_empty() {
+        send(lines, reader.read());
+    }
+    Ok(reader.read())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_lines() -> io::Result<()> {
+        let mut received = Vec::new();
+        let mut last_read = 0;
+        let reader = LineReader::new(BufReader::new(&b"a\r\nb\n\xffc"[..]), Encoding::Utf8);
+        let read = read_lines(reader, |lines, read| {
+            received.extend(lines);
+            last_read = read;
+            true
+        })?;
+        assert_eq!(vec!["a", "b", "\u{fffd}c"], received);
+        assert_eq!(7, read);
+        assert_eq!(7, last_read);
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_utf16_lines() -> io::Result<()> {
+        let encoding = Encoding::Utf16Be;
+        let bytes = encoding.encode("\u{FEFF}a\u{10A}\nb");
+        let mut received = Vec::new();
+        read_lines(LineReader::new(BufReader::new(&bytes[..]), encoding), |lines, _| {
+            received.extend(lines);
+            true
+        })?;
+        assert_eq!(vec!["a\u{10A}", "b"], received);
+        Ok(())
+    }
+}
@@ -11,6 +11,11 @@ impl TextRange {
         Self { start_line, start_column, end_line, end_column }
     }
 
+    /// Returns the start position (line, column)
+    pub(crate) const fn start(&self) -> (usize, usize) {
+        (self.start_line, self.start_column)
+    }
+
     pub(crate) fn is_empty(&self) -> bool {
         self.start_line == self.end_line && self.start_column == self.end_column
     }
@@ -0,0 +1,267 @@
+use crate::ceos::buffer::line_group::LineGroup;
+use crate::ceos::buffer::line_view::LineView;
+use crate::ceos::buffer::text_range::TextRange;
+use std::collections::VecDeque;
+
+/// The maximum number of edits kept in the undo stack
+const MAX_UNDO: usize = 1000;
+/// The maximum number of commands kept in the undo stack, each one holds a compressed copy of
+/// the buffer
+const MAX_RESTORE: usize = 10;
+
+/// The compressed content of a buffer.
+#[derive(Debug)]
+pub(crate) struct BufferSnapshot {
+    pub(super) content: Vec<LineGroup>,
+    pub(super) length: usize,
+}
+
+impl BufferSnapshot {
+    pub(crate) fn line_groups(&self) -> &[LineGroup] {
+        &self.content
+    }
+
+    pub(crate) fn line_count(&self) -> usize {
+        self.content.iter().map(LineGroup::line_count).sum()
+    }
+}
+
+/// An operation that can be applied to revert a change.
+/// Applying an edit returns its own inverse that is pushed on the opposite stack.
+#[derive(Debug)]
+pub(crate) enum Edit {
+    /// Insert the text at the given position (line, column)
+    Insert {
+        line: usize,
+        column: usize,
+        text: String,
+    },
+    /// Delete the text at the given position (line, column)
+    Delete {
+        line: usize,
+        column: usize,
+        text: String,
+    },
+    /// Restore the buffer content before a command
+    Restore(BufferSnapshot),
+    /// Push back a filtered view
+    PushView(LineView),
+    /// Remove the last filtered view
+    PopView,
+}
+
+impl Edit {
+    /// Returns the range covered by the text of an insert or delete edit
+    pub(crate) fn text_range(line: usize, column: usize, text: &str) -> TextRange {
+        let (end_line, end_column) = Self::end_position(line, column, text);
+        TextRange::new(line, column, end_line, end_column)
+    }
+
+    fn end_position(line: usize, column: usize, text: &str) -> (usize, usize) {
+        match text.rsplit_once('\n') {
+            Some((before, last_line)) => (line + before.matches('\n').count() + 1, last_line.len()),
+            None => (line, column + text.len()),
+        }
+    }
+
+    /// Merge the given edit into this one if they were typed consecutively on the same line,
+    /// returns the edit back if it couldn't be merged.
+    fn merge(&mut self, edit: Edit) -> Option<Edit> {
+        match (self, edit) {
+            // inverse of consecutive insertions
+            (
+                Edit::Delete { line, column, text },
+                Edit::Delete {
+                    line: new_line,
+                    column: new_column,
+                    text: new_text,
+                },
+            ) if *line == new_line
+                && *column + text.len() == new_column
+                && !text.contains('\n')
+                && !new_text.contains('\n') =>
+            {
+                text.push_str(&new_text);
+                None
+            }
+            // inverse of consecutive backspaces
+            (
+                Edit::Insert { line, column, text },
+                Edit::Insert {
+                    line: new_line,
+                    column: new_column,
+                    text: new_text,
+                },
+            ) if *line == new_line
+                && new_column + new_text.len() == *column
+                && !text.contains('\n')
+                && !new_text.contains('\n') =>
+            {
+                text.insert_str(0, &new_text);
+                *column = new_column;
+                None
+            }
+            (_, edit) => Some(edit),
+        }
+    }
+}
+
+/// The undo and redo stacks.
+///
+/// The stacks contain the inverse of the operations done by the user.
+#[derive(Debug, Default)]
+pub(crate) struct UndoManager {
+    undo: VecDeque<Edit>,
+    redo: Vec<Edit>,
+}
+
+impl UndoManager {
+    /// Record the inverse of an edit done by the user. The redo stack is cleared.
+    pub(crate) fn record(&mut self, edit: Edit) {
+        self.redo.clear();
+        self.push_undo(edit, true);
+    }
+
+    /// Record the inverse of an edit that was just undone
+    pub(crate) fn push_redo(&mut self, edit: Edit) {
+        self.redo.push(edit);
+    }
+
+    /// Record the inverse of an edit that was just redone
+    pub(crate) fn push_undo_after_redo(&mut self, edit: Edit) {
+        self.push_undo(edit, false);
+    }
+
+    fn push_undo(&mut self, edit: Edit, merge: bool) {
+        let edit = match self.undo.back_mut() {
+            Some(last) if merge => last.merge(edit),
+            _ => Some(edit),
+        };
+        if let Some(edit) = edit {
+            self.undo.push_back(edit);
+            if self.undo.len() > MAX_UNDO {
+                self.undo.pop_front();
+            }
+            // the oldest edits are dropped up to the oldest command
+            while self.restore_count() > MAX_RESTORE {
+                while !matches!(self.undo.pop_front(), Some(Edit::Restore(_)) | None) {}
+            }
+        }
+    }
+
+    /// The groups of the buffer snapshots kept to undo or redo the commands
+    pub(crate) fn snapshot_groups(&self) -> impl Iterator<Item = &LineGroup> {
+        self.undo
+            .iter()
+            .chain(&self.redo)
+            .filter_map(|edit| match edit {
+                Edit::Restore(snapshot) => Some(snapshot.line_groups()),
+                _ => None,
+            })
+            .flatten()
+    }
+
+    fn restore_count(&self) -> usize {
+        self.undo
+            .iter()
+            .filter(|edit| matches!(edit, Edit::Restore(_)))
+            .count()
+    }
+
+    pub(crate) fn pop_undo(&mut self) -> Option<Edit> {
+        self.undo.pop_back()
+    }
+
+    pub(crate) fn pop_redo(&mut self) -> Option<Edit> {
+        self.redo.pop()
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn delete(line: usize, column: usize, text: &str) -> Edit {
+        Edit::Delete {
+            line,
+            column,
+            text: text.to_string(),
+        }
+    }
+
+    fn insert(line: usize, column: usize, text: &str) -> Edit {
+        Edit::Insert {
+            line,
+            column,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_text_range() {
+        let range = Edit::text_range(1, 2, "ab");
+        assert_eq!((1, 2, 1, 4), (range.start_line, range.start_column, range.end_line, range.end_column));
+        let range = Edit::text_range(1, 2, "ab\ncd\nefg");
+        assert_eq!((1, 2, 3, 3), (range.start_line, range.start_column, range.end_line, range.end_column));
+    }
+
+    #[test]
+    fn test_typing_is_merged() {
+        let mut manager = UndoManager::default();
+        manager.record(delete(0, 0, "a"));
+        manager.record(delete(0, 1, "b"));
+        manager.record(delete(0, 5, "c"));
+        assert!(matches!(manager.pop_undo(), Some(Edit::Delete { column: 5, .. })));
+        match manager.pop_undo() {
+            Some(Edit::Delete { column, text, .. }) => {
+                assert_eq!(0, column);
+                assert_eq!("ab", text);
+            }
+            edit => panic!("unexpected edit {edit:?}"),
+        }
+        assert!(manager.pop_undo().is_none());
+    }
+
+    #[test]
+    fn test_backspaces_are_merged() {
+        let mut manager = UndoManager::default();
+        manager.record(insert(0, 4, "d"));
+        manager.record(insert(0, 3, "c"));
+        match manager.pop_undo() {
+            Some(Edit::Insert { column, text, .. }) => {
+                assert_eq!(3, column);
+                assert_eq!("cd", text);
+            }
+            edit => panic!("unexpected edit {edit:?}"),
+        }
+    }
+
+    #[test]
+    fn test_record_clears_redo() {
+        let mut manager = UndoManager::default();
+        manager.record(Edit::PopView);
+        let edit = manager.pop_undo().unwrap();
+        manager.push_redo(edit);
+        manager.record(Edit::PopView);
+        assert!(manager.pop_redo().is_none());
+    }
+
+    #[test]
+    fn test_oldest_snapshots_dropped() {
+        let mut manager = UndoManager::default();
+        for _ in 0..MAX_RESTORE + 2 {
+            manager.record(Edit::PopView);
+            manager.record(Edit::Restore(BufferSnapshot {
+                content: Vec::new(),
+                length: 0,
+            }));
+        }
+        assert_eq!(MAX_RESTORE, manager.restore_count());
+        assert_eq!(2 * MAX_RESTORE, manager.undo.len());
+    }
+}
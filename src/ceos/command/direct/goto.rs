@@ -1,14 +1,22 @@
 use log::info;
+use crate::ceos::buffer::buffer::Buffer;
 use crate::ceos::gui::textpane::textareaproperties::TextAreaProperties;
+use crate::ceos::tools::timestamp::{TimeBound, TimestampFormat};
+use crate::event::Event::GotoLine;
+use std::thread;
 
 #[derive(Debug)]
 pub(crate) struct Goto {
     line: usize,
+    /// Move the caret at this column of the line instead of only scrolling to it
+    column: Option<usize>,
+    /// Go to the first line logged at or after this time instead of the line
+    time: Option<TimeBound>,
 }
 
 impl From<usize> for Goto {
     fn from(line: usize) -> Self {
-        Self { line }
+        Self::new(line)
     }
 }
 
@@ -19,7 +27,13 @@ impl TryFrom<&str> for Goto {
         if let Some(stripped) = command.strip_prefix(':')
             && let Ok(line) = stripped.parse::<usize>()
         {
-            return Ok(Goto { line });
+            return Ok(Goto::new(line));
+        }
+        if let Some(stripped) = command.strip_prefix('@') {
+            return Ok(Goto {
+                time: Some(TimeBound::parse(stripped, false)?),
+                ..Goto::new(0)
+            });
         }
         Err(())
     }
@@ -27,12 +41,51 @@ impl TryFrom<&str> for Goto {
 
 impl Goto {
     pub(crate) const fn new(line: usize) -> Self {
-        Self { line }
+        Self {
+            line,
+            column: None,
+            time: None,
+        }
+    }
+
+    /// Go to a column of a line, like a search result
+    pub(crate) const fn at(line: usize, column: usize) -> Self {
+        Self {
+            line,
+            column: Some(column),
+            time: None,
+        }
     }
 
+    /// Go to the line. The line logged at a time is searched in a background thread, the caret
+    /// is moved by the [`GotoLine`] event it sends.
     pub(crate) fn execute(&self, textarea: &mut TextAreaProperties) {
+        if let Some(time) = self.time {
+            let snapshot = textarea.buffer.snapshot();
+            let sender = textarea.buffer.sender.clone();
+            thread::spawn(move || {
+                match Buffer::find_time(snapshot.line_groups(), time, &TimestampFormat::Auto) {
+                    Some(line) => {
+                        info!("goto {time:?} found at line {line}");
+                        let _ = sender.send(GotoLine(Goto::at(line, 0)));
+                    }
+                    None => info!("no line logged at or after {time:?}"),
+                }
+            });
+            return;
+        }
+        if let Some(column) = self.column {
+            info!("goto {}:{column}", self.line);
+            textarea.restore_caret(self.line);
+            let caret_line = textarea.caret_position.line;
+            // the line may not be displayed yet, it is read to bound the column
+            textarea.prepare_range_for_read(caret_line..caret_line + 1);
+            textarea.caret_position.column = column.min(textarea.line_length(caret_line));
+            return;
+        }
         info!("goto {}", self.line);
-        textarea.set_first_line(self.line);
+        // the line is a buffer line, it has to be converted if the buffer is filtered
+        textarea.set_first_line(textarea.view_line(self.line));
     }
 }
 
@@ -41,6 +94,19 @@ mod tests {
     use rstest::rstest;
 
     use super::*;
+    use crate::event::Event;
+    use std::sync::mpsc::Receiver;
+
+    /// Execute a goto, waiting for the line logged at its time to be found
+    fn goto_time(textarea: &mut TextAreaProperties, receiver: &Receiver<Event>, command: &str) {
+        Goto::try_from(command).unwrap().execute(textarea);
+        while let Ok(event) = receiver.recv() {
+            if let GotoLine(goto) = event {
+                goto.execute(textarea);
+                return;
+            }
+        }
+    }
 
     #[rstest]
     #[case(3, ":3")]
@@ -50,6 +116,56 @@ mod tests {
         assert_eq!(expected, goto.line);
     }
 
+    #[rstest]
+    #[case("@14:03:12", TimeBound::TimeOfDay(50_592_000))]
+    #[case("@14:03", TimeBound::TimeOfDay(50_580_000))]
+    #[case("@2026-10-17T14:03", TimeBound::Timestamp(1_792_245_780_000))]
+    fn test_try_from_time(#[case] command: &str, #[case] expected: TimeBound) {
+        let goto = Goto::try_from(command).unwrap();
+        assert_eq!(Some(expected), goto.time);
+    }
+
+    #[test]
+    fn test_goto_time() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let mut textarea = TextAreaProperties::new(sender.clone());
+        let text: String = (0..50)
+            .map(|minute| format!("2024-01-31 14:{minute:02}:00,000 line {minute}\n\tat continuation\n"))
+            .collect();
+        textarea.set_buffer(Buffer::new_from_string(sender, &text, 4));
+        goto_time(&mut textarea, &receiver, "@14:20:30");
+        assert_eq!(42, textarea.caret_position.line);
+        goto_time(&mut textarea, &receiver, "@2024-01-31T14:03");
+        assert_eq!(6, textarea.caret_position.line);
+    }
+
+    #[test]
+    fn test_goto_time_before_continuation_groups() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let mut textarea = TextAreaProperties::new(sender.clone());
+        // the groups of continuation lines follow a group logged after the time
+        let text = "2024-01-31 14:00:00,000 first\n\
+            2024-01-31 14:01:00,000 second\n\
+            2024-01-31 14:05:00,000 failed\n\
+            \tat com.example.Main\n"
+            .to_owned()
+            + &"\tat com.example.Other\n".repeat(6);
+        textarea.set_buffer(Buffer::new_from_string(sender, &text, 2));
+        goto_time(&mut textarea, &receiver, "@14:02");
+        assert_eq!(2, textarea.caret_position.line);
+    }
+
+    #[test]
+    fn test_find_time_without_timestamp() {
+        let (sender, _) = std::sync::mpsc::channel();
+        let text = "no timestamp\n".repeat(100);
+        let buffer = Buffer::new_from_string(sender, &text, 2);
+        let time = TimeBound::parse("14:00", false).unwrap();
+        assert_eq!(None, Buffer::find_time(buffer.line_groups(), time, &TimestampFormat::Auto));
+        // only the first groups were decompressed to look for a timestamp
+        assert!(buffer.decompressed_group_count() <= 17);
+    }
+
     #[rstest]
     #[case("invalid")]
     #[case(":3inv")]
@@ -14,6 +14,7 @@ use crate::ceos::gui::textpane::textareaproperties::TextAreaProperties;
 use crate::ceos::gui::theme::Theme;
 use crate::ceos::gui::tools;
 use crate::ceos::tools::range::Range;
+use crate::ceos::tools::cancellation::CancellationToken;
 
 #[derive(Debug, PartialEq)]
 pub(crate) struct ColumnFilter {
@@ -63,9 +64,9 @@ impl Renderer for ColumnFilter {
 }
 
 impl Command for ColumnFilter {
-    fn execute(&self, buffer: &mut Buffer) {
+    fn execute(&self, buffer: &mut Buffer, cancellation: &CancellationToken) {
         let line_count = buffer.line_count();
-        let new_length = buffer.filter_line_mut(|line| self.apply_to_line(line));
+        let new_length = buffer.filter_line_mut(|line| self.apply_to_line(line), cancellation);
         debug!(
             "Applied filter removed {} lines, new length {}",
             line_count - buffer.line_count(),
@@ -159,7 +160,7 @@ mod tests {
         let mut buffer = Buffer::new_from_string(sender, content, 2);
         assert_eq!(content.len(), buffer.len());
         assert_eq!(5, buffer.line_count());
-        filter.execute(&mut buffer);
+        filter.execute(&mut buffer, &CancellationToken::default());
         assert_eq!(content.len() - 8, buffer.len());
         Ok(())
     }
@@ -0,0 +1,251 @@
+use std::fmt::Display;
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+/// A boolean expression used to filter lines.
+///
+/// The grammar is the following, from the lowest to the highest precedence
+/// ```text
+/// or      := and ('|' and)*
+/// and     := not ('&' not)*
+/// not     := '!' not | primary
+/// primary := '(' or ')' | '"' quoted text '"' | text
+/// ```
+/// A bare text ends at the next operator or parenthesis and is trimmed,
+/// a quoted text can contain any character, `\"` and `\\` being escaped.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Expression {
+    Contains(String),
+    Not(Box<Expression>),
+    And(Vec<Expression>),
+    Or(Vec<Expression>),
+}
+
+impl Expression {
+    pub(crate) fn matches(&self, text: &str) -> bool {
+        match self {
+            Expression::Contains(pattern) => text.contains(pattern.as_str()),
+            Expression::Not(expression) => !expression.matches(text),
+            Expression::And(expressions) => expressions.iter().all(|e| e.matches(text)),
+            Expression::Or(expressions) => expressions.iter().any(|e| e.matches(text)),
+        }
+    }
+}
+
+impl TryFrom<&str> for Expression {
+    type Error = ();
+
+    fn try_from(text: &str) -> Result<Self, Self::Error> {
+        let mut parser = Parser {
+            text,
+            chars: text.char_indices().peekable(),
+        };
+        let expression = parser.parse_or()?;
+        parser.skip_whitespaces();
+        if parser.chars.peek().is_some() {
+            // unbalanced closing parenthesis
+            return Err(());
+        }
+        Ok(expression)
+    }
+}
+
+impl Display for Expression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expression::Contains(pattern) => write!(f, "{pattern:?}"),
+            Expression::Not(expression) => write!(f, "!{expression}"),
+            Expression::And(expressions) => write_list(f, expressions, " & "),
+            Expression::Or(expressions) => write_list(f, expressions, " | "),
+        }
+    }
+}
+
+fn write_list(
+    f: &mut std::fmt::Formatter<'_>,
+    expressions: &[Expression],
+    separator: &str,
+) -> std::fmt::Result {
+    f.write_str("(")?;
+    for (i, expression) in expressions.iter().enumerate() {
+        if i != 0 {
+            f.write_str(separator)?;
+        }
+        write!(f, "{expression}")?;
+    }
+    f.write_str(")")
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl Parser<'_> {
+    fn parse_or(&mut self) -> Result<Expression, ()> {
+        let mut expressions = vec![self.parse_and()?];
+        while self.eat('|') {
+            expressions.push(self.parse_and()?);
+        }
+        Ok(Self::flatten(expressions, Expression::Or))
+    }
+
+    fn parse_and(&mut self) -> Result<Expression, ()> {
+        let mut expressions = vec![self.parse_not()?];
+        while self.eat('&') {
+            expressions.push(self.parse_not()?);
+        }
+        Ok(Self::flatten(expressions, Expression::And))
+    }
+
+    fn parse_not(&mut self) -> Result<Expression, ()> {
+        if self.eat('!') {
+            return Ok(Expression::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expression, ()> {
+        self.skip_whitespaces();
+        match self.chars.peek() {
+            Some((_, '(')) => {
+                self.chars.next();
+                let expression = self.parse_or()?;
+                if self.eat(')') {
+                    Ok(expression)
+                } else {
+                    Err(())
+                }
+            }
+            Some((_, '"')) => {
+                self.chars.next();
+                self.parse_quoted()
+            }
+            Some(_) => self.parse_bare(),
+            None => Err(()),
+        }
+    }
+
+    fn parse_quoted(&mut self) -> Result<Expression, ()> {
+        let mut pattern = String::new();
+        while let Some((_, c)) = self.chars.next() {
+            match c {
+                '"' => return Ok(Expression::Contains(pattern)),
+                '\\' => match self.chars.next() {
+                    Some((_, escaped)) => pattern.push(escaped),
+                    None => return Err(()),
+                },
+                _ => pattern.push(c),
+            }
+        }
+        // unterminated quote
+        Err(())
+    }
+
+    fn parse_bare(&mut self) -> Result<Expression, ()> {
+        let start = self.chars.peek().map(|(i, _)| *i).unwrap_or(self.text.len());
+        let mut end = self.text.len();
+        while let Some((i, c)) = self.chars.peek() {
+            if matches!(c, '&' | '|' | '(' | ')' | '"') {
+                end = *i;
+                break;
+            }
+            self.chars.next();
+        }
+        let pattern = self.text[start..end].trim();
+        if pattern.is_empty() {
+            return Err(());
+        }
+        Ok(Expression::Contains(pattern.to_string()))
+    }
+
+    /// Consume the expected char (ignoring the leading whitespaces) if it is the next one.
+    fn eat(&mut self, expected: char) -> bool {
+        self.skip_whitespaces();
+        if let Some((_, c)) = self.chars.peek()
+            && *c == expected
+        {
+            self.chars.next();
+            return true;
+        }
+        false
+    }
+
+    fn skip_whitespaces(&mut self) {
+        while self.chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+    }
+
+    fn flatten(
+        mut expressions: Vec<Expression>,
+        build: fn(Vec<Expression>) -> Expression,
+    ) -> Expression {
+        if expressions.len() == 1 {
+            expressions.remove(0)
+        } else {
+            build(expressions)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn contains(pattern: &str) -> Expression {
+        Expression::Contains(pattern.to_string())
+    }
+
+    #[test]
+    fn test_parse_precedence() {
+        let expression = Expression::try_from("a | b & !c").unwrap();
+        assert_eq!(
+            Expression::Or(vec![
+                contains("a"),
+                Expression::And(vec![contains("b"), Expression::Not(Box::new(contains("c")))])
+            ]),
+            expression
+        );
+    }
+
+    #[test]
+    fn test_parse_grouping_and_quotes() {
+        let expression = Expression::try_from(r#"(ERROR | FATAL) & "user \"42\"""#).unwrap();
+        assert_eq!(
+            Expression::And(vec![
+                Expression::Or(vec![contains("ERROR"), contains("FATAL")]),
+                contains("user \"42\"")
+            ]),
+            expression
+        );
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("a &")]
+    #[case("| a")]
+    #[case("(a | b")]
+    #[case("a | b)")]
+    #[case("\"a")]
+    #[case("!")]
+    #[case("a & () ")]
+    fn test_parse_invalid(#[case] text: &str) {
+        assert!(Expression::try_from(text).is_err());
+    }
+
+    #[rstest]
+    #[case("ERROR", "12:00 ERROR failed", true)]
+    #[case("user 42", "login user 42", true)]
+    #[case("!healthcheck", "GET /healthcheck", false)]
+    #[case("!healthcheck", "GET /login", true)]
+    #[case("(ERROR | FATAL) & !healthcheck", "FATAL boom", true)]
+    #[case("(ERROR | FATAL) & !healthcheck", "ERROR /healthcheck", false)]
+    #[case("(ERROR | FATAL) & !healthcheck", "INFO boom", false)]
+    #[case("!!a", "abc", true)]
+    #[case("!(a | b)", "cd", true)]
+    #[case("!(a | b)", "bd", false)]
+    fn test_matches(#[case] text: &str, #[case] line: &str, #[case] expected: bool) {
+        let expression = Expression::try_from(text).unwrap();
+        assert_eq!(expected, expression.matches(line));
+    }
+}
@@ -7,11 +7,13 @@ use egui::{StrokeKind, Ui};
 use log::info;
 
 use crate::ceos::buffer::buffer::Buffer;
+use crate::ceos::buffer::line_view::LineView;
 use crate::ceos::command::Command;
 use crate::ceos::gui::textpane::renderer::Renderer;
 use crate::ceos::gui::textpane::textareaproperties::TextAreaProperties;
 use crate::ceos::gui::theme::Theme;
 use crate::ceos::tools::range::Range;
+use crate::ceos::tools::cancellation::CancellationToken;
 
 /// LineDrop filter
 ///
@@ -60,7 +62,7 @@ impl Renderer for LineDrop {
 }
 
 impl Command for LineDrop {
-    fn execute(&self, buffer: &mut Buffer) {
+    fn execute(&self, buffer: &mut Buffer, _cancellation: &CancellationToken) {
         let line_count = buffer.line_count();
         let new_length = if let Some(end) = self.range.end {
             buffer.drain_line_mut(self.range.start..cmp::min(line_count, end))
@@ -74,6 +76,27 @@ impl Command for LineDrop {
             line_count - buffer.line_count()
         );
     }
+
+    fn filter_view(
+        &self,
+        buffer: &Buffer,
+        view: Option<&LineView>,
+        _cancellation: &CancellationToken,
+    ) -> Option<LineView> {
+        let line_count = view.map_or_else(|| buffer.line_count(), |view| view.len());
+        let end = self.range.end.map_or(line_count, |end| cmp::min(line_count, end));
+        let lines = (0..line_count)
+            .filter(|line| *line < self.range.start || *line >= end)
+            .map(|line| view.map_or(line, |view| view.buffer_line(line)))
+            .collect();
+        let new_view = LineView::new(lines);
+        info!(
+            "Dropped range '{:?}' from the view, {} lines remaining",
+            self.range,
+            new_view.len()
+        );
+        Some(new_view)
+    }
 }
 
 impl Display for LineDrop {
@@ -87,6 +110,7 @@ mod tests {
     use crate::ceos::buffer::buffer::Buffer;
     use crate::ceos::command::Command;
     use crate::ceos::command::filter::linedrop::LineDrop;
+    use crate::ceos::tools::cancellation::CancellationToken;
 
     const CONTENT: &str = "1 delete me\n\
         2 keep me\n\
@@ -101,7 +125,7 @@ mod tests {
         assert_eq!(CONTENT.len(), buffer.len());
         assert_eq!(5, buffer.line_count());
         let filter = LineDrop::try_from("l ..2")?;
-        filter.execute(&mut buffer);
+        filter.execute(&mut buffer, &CancellationToken::default());
         assert_eq!(3, buffer.line_count());
         buffer.prepare_range_for_read(..);
         assert_eq!("3 delete me", buffer.line_text(1));
@@ -116,7 +140,7 @@ mod tests {
         assert_eq!(CONTENT.len(), buffer.len());
         assert_eq!(5, buffer.line_count());
         let filter = LineDrop::try_from("l 3..")?;
-        filter.execute(&mut buffer);
+        filter.execute(&mut buffer, &CancellationToken::default());
         assert_eq!(3, buffer.line_count());
         buffer.prepare_range_for_read(..);
         assert_eq!("2 keep me", buffer.line_text(1));
@@ -124,6 +148,23 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_filter_view() -> Result<(), ()> {
+        let (sender, _) = std::sync::mpsc::channel();
+        let buffer = Buffer::new_from_string(sender, CONTENT, 2);
+        let filter = LineDrop::try_from("l 1..3")?;
+        let view = filter.filter_view(&buffer, None, &CancellationToken::default()).ok_or(())?;
+        assert_eq!(&[0, 3, 4], view.lines());
+        let filter = LineDrop::try_from("l ..1")?;
+        let view = filter
+            .filter_view(&buffer, Some(&view), &CancellationToken::default())
+            .ok_or(())?;
+        assert_eq!(&[3, 4], view.lines());
+        assert_eq!(5, buffer.line_count());
+        assert!(!buffer.dirty);
+        Ok(())
+    }
+
     #[test]
     fn test_filter_suffix() -> Result<(), ()> {
         let (sender, _) = std::sync::mpsc::channel();
@@ -131,7 +172,7 @@ mod tests {
         assert_eq!(CONTENT.len(), buffer.len());
         assert_eq!(5, buffer.line_count());
         let filter = LineDrop::try_from("l 2..4")?;
-        filter.execute(&mut buffer);
+        filter.execute(&mut buffer, &CancellationToken::default());
         assert_eq!(3, buffer.line_count());
         buffer.prepare_range_for_read(..);
         assert_eq!("2 keep me", buffer.line_text(1));
@@ -7,28 +7,25 @@ use std::time::Instant;
 
 use crate::ceos::buffer::buffer::Buffer;
 use crate::ceos::buffer::line::Line;
+use crate::ceos::buffer::line_view::LineView;
 use crate::ceos::command::Command;
+use crate::ceos::command::filter::expression::Expression;
 use crate::ceos::gui::textpane::renderer::Renderer;
 use crate::ceos::gui::textpane::textareaproperties::TextAreaProperties;
 use crate::ceos::gui::theme::Theme;
+use crate::ceos::tools::cancellation::CancellationToken;
 
+/// Line filter
+///
+/// It will keep only the lines matching a boolean expression, see [`Expression`]
 #[derive(Debug)]
 pub(crate) struct LineFilter {
-    filters: Vec<String>,
+    expression: Expression,
 }
 
 impl LineFilter {
     pub(crate) fn accept(&self, line: &Line) -> bool {
-        for filter in &self.filters {
-            if let Some(prefix) = filter.strip_prefix('!') {
-                if line.content().contains(prefix) && !line.content().contains(filter) {
-                    return false;
-                }
-            } else if !line.content().contains(filter) {
-                return false;
-            }
-        }
-        true
+        self.expression.matches(line.content())
     }
 }
 
@@ -38,14 +35,13 @@ impl TryFrom<&str> for LineFilter {
     fn try_from(command: &str) -> Result<Self, Self::Error> {
         const PREFIX: &str = "filter ";
 
-        command
+        let rest = command
             .strip_prefix(PREFIX)
             .filter(|rest| !rest.is_empty())
-            .map(|rest| {
-                let filters = rest.split('&').map(|tok| tok.to_string()).collect();
-                Self { filters }
-            })
-            .ok_or(())
+            .ok_or(())?;
+        Ok(Self {
+            expression: Expression::try_from(rest)?,
+        })
     }
 }
 
@@ -59,7 +55,7 @@ impl Renderer for LineFilter {
         drawing_pos: Pos2,
         _has_focus: bool,
     ) {
-        let line = &textarea_properties.buffer[line];
+        let line = textarea_properties.line(line);
         if !self.accept(line) {
             let bottom_right = Pos2::new(ui.max_rect().max.x, drawing_pos.y + textarea_properties.line_height);
             let line_rect = Rect::from_min_max(drawing_pos, bottom_right);
@@ -76,22 +72,39 @@ impl Renderer for LineFilter {
 }
 
 impl Command for LineFilter {
-    fn execute(&self, buffer: &mut Buffer) {
+    fn execute(&self, buffer: &mut Buffer, cancellation: &CancellationToken) {
         let start = Instant::now();
         let line_count = buffer.line_count();
-        let new_length = buffer.retain_line_mut(|line| self.accept(line));
+        let new_length = buffer.retain_line_mut(|line| self.accept(line), cancellation);
         info!(
-            "Applied filter '{:?}' removed {} lines, new length {new_length} in {}ms",
-            self.filters,
+            "Applied filter '{}' removed {} lines, new length {new_length} in {}ms",
+            self.expression,
             line_count - buffer.line_count(),
             start.elapsed().as_millis()
         );
     }
+
+    fn filter_view(
+        &self,
+        buffer: &Buffer,
+        view: Option<&LineView>,
+        cancellation: &CancellationToken,
+    ) -> Option<LineView> {
+        let start = Instant::now();
+        let new_view = buffer.filter_view(view, |line| self.accept(line), cancellation);
+        info!(
+            "Filtered view with '{}' keeps {} lines in {}ms",
+            self,
+            new_view.len(),
+            start.elapsed().as_millis()
+        );
+        Some(new_view)
+    }
 }
 
 impl Display for LineFilter {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Filter '{:?}'", self.filters)
+        write!(f, "Filter '{}'", self.expression)
     }
 }
 
@@ -110,9 +123,26 @@ mod tests {
         let mut buffer = Buffer::new_from_string(sender, CONTENT, 2);
         assert_eq!(CONTENT.len(), buffer.len());
         assert_eq!(4, buffer.line_count());
-        filter.execute(&mut buffer);
+        filter.execute(&mut buffer, &CancellationToken::default());
         assert!(buffer.dirty);
         assert_eq!(2, buffer.line_count());
         Ok(())
     }
+
+    #[test]
+    fn test_filter_expression() -> Result<(), ()> {
+        let filter = LineFilter::try_from("filter (ERROR | FATAL) & !healthcheck")?;
+        const CONTENT: &str = "1 ERROR failed\n\
+        2 INFO ok\n\
+        3 ERROR /healthcheck\n\
+        4 FATAL crashed\n";
+        let (sender, _) = std::sync::mpsc::channel();
+        let mut buffer = Buffer::new_from_string(sender, CONTENT, 2);
+        filter.execute(&mut buffer, &CancellationToken::default());
+        assert_eq!(2, buffer.line_count());
+        buffer.prepare_range_for_read(..);
+        assert_eq!("1 ERROR failed", buffer.line_text(0));
+        assert_eq!("4 FATAL crashed", buffer.line_text(1));
+        Ok(())
+    }
 }
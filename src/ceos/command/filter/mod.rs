@@ -1,3 +1,6 @@
 pub(crate) mod columnfilter;
+pub(crate) mod expression;
 pub(crate) mod linedrop;
 pub(crate) mod linefilter;
+pub(crate) mod regexfilter;
+pub(crate) mod timefilter;
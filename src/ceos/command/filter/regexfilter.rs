@@ -0,0 +1,141 @@
+use eframe::emath::{Pos2, Rect};
+use eframe::epaint::{Stroke, StrokeKind};
+use egui::Ui;
+use log::info;
+use regex::Regex;
+use std::fmt::Display;
+use std::time::Instant;
+
+use crate::ceos::buffer::buffer::Buffer;
+use crate::ceos::buffer::line::Line;
+use crate::ceos::buffer::line_view::LineView;
+use crate::ceos::command::Command;
+use crate::ceos::gui::textpane::renderer::Renderer;
+use crate::ceos::gui::textpane::textareaproperties::TextAreaProperties;
+use crate::ceos::gui::theme::Theme;
+use crate::ceos::tools::cancellation::CancellationToken;
+
+/// Regex filter
+///
+/// It will keep only the lines matching the regular expression
+#[derive(Debug)]
+pub(crate) struct RegexFilter {
+    regex: Regex,
+}
+
+impl RegexFilter {
+    pub(crate) fn accept(&self, line: &Line) -> bool {
+        self.regex.is_match(line.content())
+    }
+}
+
+impl TryFrom<&str> for RegexFilter {
+    type Error = ();
+
+    fn try_from(command: &str) -> Result<Self, Self::Error> {
+        const PREFIX: &str = "refilter ";
+
+        let pattern = command
+            .strip_prefix(PREFIX)
+            .filter(|rest| !rest.is_empty())
+            .ok_or(())?;
+        let regex = Regex::new(pattern).map_err(|_| ())?;
+        Ok(Self { regex })
+    }
+}
+
+impl Renderer for RegexFilter {
+    fn paint_line(
+        &self,
+        ui: &mut Ui,
+        theme: &Theme,
+        textarea_properties: &TextAreaProperties,
+        line: usize,
+        drawing_pos: Pos2,
+        _has_focus: bool,
+    ) {
+        let line = textarea_properties.line(line);
+        if !self.accept(line) {
+            let bottom_right = Pos2::new(ui.max_rect().max.x, drawing_pos.y + textarea_properties.line_height);
+            let line_rect = Rect::from_min_max(drawing_pos, bottom_right);
+            let painter = ui.painter();
+            painter.rect(
+                line_rect,
+                0.0,
+                theme.deleting,
+                Stroke::default(),
+                StrokeKind::Inside,
+            );
+        }
+    }
+}
+
+impl Command for RegexFilter {
+    fn execute(&self, buffer: &mut Buffer, cancellation: &CancellationToken) {
+        let start = Instant::now();
+        let line_count = buffer.line_count();
+        let new_length = buffer.retain_line_mut(|line| self.accept(line), cancellation);
+        info!(
+            "Applied regex filter '{}' removed {} lines, new length {new_length} in {}ms",
+            self.regex,
+            line_count - buffer.line_count(),
+            start.elapsed().as_millis()
+        );
+    }
+
+    fn filter_view(
+        &self,
+        buffer: &Buffer,
+        view: Option<&LineView>,
+        cancellation: &CancellationToken,
+    ) -> Option<LineView> {
+        let start = Instant::now();
+        let new_view = buffer.filter_view(view, |line| self.accept(line), cancellation);
+        info!(
+            "Filtered view with '{}' keeps {} lines in {}ms",
+            self,
+            new_view.len(),
+            start.elapsed().as_millis()
+        );
+        Some(new_view)
+    }
+}
+
+impl Display for RegexFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "RegexFilter '{}'", self.regex)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("refilter ")]
+    #[case("refilter (")]
+    #[case("filter abc")]
+    fn test_try_from_invalid(#[case] command: &str) {
+        assert!(RegexFilter::try_from(command).is_err());
+    }
+
+    #[test]
+    fn test_filter() -> Result<(), ()> {
+        let filter = RegexFilter::try_from(r"refilter status=5\d\d")?;
+        const CONTENT: &str = "1 status=500\n\
+        2 status=200\n\
+        3 status=503\n\
+        4 status=5xx\n";
+        let (sender, _) = std::sync::mpsc::channel();
+        let mut buffer = Buffer::new_from_string(sender, CONTENT, 2);
+        assert_eq!(4, buffer.line_count());
+        filter.execute(&mut buffer, &CancellationToken::default());
+        assert!(buffer.dirty);
+        assert_eq!(2, buffer.line_count());
+        buffer.prepare_range_for_read(..);
+        assert_eq!("1 status=500", buffer.line_text(0));
+        assert_eq!("3 status=503", buffer.line_text(1));
+        Ok(())
+    }
+}
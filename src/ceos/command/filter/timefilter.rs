@@ -0,0 +1,326 @@
+use eframe::emath::{Pos2, Rect};
+use eframe::epaint::{Stroke, StrokeKind};
+use egui::Ui;
+use log::info;
+use std::fmt::Display;
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, OnceLock};
+use std::time::Instant;
+
+use crate::ceos::buffer::buffer::Buffer;
+use crate::ceos::buffer::line_group::LineGroup;
+use crate::ceos::buffer::line_view::LineView;
+use crate::ceos::command::Command;
+use crate::ceos::gui::textpane::renderer::Renderer;
+use crate::ceos::gui::textpane::textareaproperties::TextAreaProperties;
+use crate::ceos::gui::theme::Theme;
+use crate::ceos::progress_manager::operation_id;
+use crate::ceos::tools::cancellation::CancellationToken;
+use crate::ceos::tools::timestamp::{TimeBound, TimestampFormat};
+use crate::event::Event;
+
+const SEPARATOR: &str = "..";
+const TIME_FILTERING: &str = "Filtering by time...";
+
+/// The lines kept in the buffer displayed while the filter is typed, and the number of lines
+/// of the buffer when they were computed
+type Preview = Arc<OnceLock<(usize, LineView)>>;
+
+/// Time range filter
+///
+/// It keeps the lines whose timestamp is within a time window, the lines without timestamp
+/// (like stack traces) are kept or dropped with the line before them
+#[derive(Debug)]
+pub(crate) struct TimeFilter {
+    text: String,
+    window: TimeWindow,
+    /// Computed once in the background the first time a line is painted
+    preview: OnceLock<Preview>,
+    /// Stops the computation of the preview when the filter is dropped
+    cancellation: CancellationToken,
+}
+
+/// The bounds of the kept timestamps, a window of times of day ending before its start wraps
+/// around midnight
+#[derive(Debug, Clone, Copy)]
+struct TimeWindow {
+    from: Option<TimeBound>,
+    to: Option<TimeBound>,
+}
+
+impl TimeWindow {
+    fn accept(&self, timestamp: i64) -> bool {
+        let after_from = self.from.is_none_or(|from| {
+            let (bound, timestamp) = from.compare(timestamp);
+            bound <= timestamp
+        });
+        let before_to = self.to.is_none_or(|to| {
+            let (bound, timestamp) = to.compare(timestamp);
+            timestamp <= bound
+        });
+        match (self.from, self.to) {
+            (Some(TimeBound::TimeOfDay(from)), Some(TimeBound::TimeOfDay(to))) if from > to => {
+                after_from || before_to
+            }
+            _ => after_from && before_to,
+        }
+    }
+
+    /// Returns true if a line is kept, a line without timestamp follows the previous one.
+    /// The lines before the first timestamp are kept only if the window has no start.
+    fn keeps(&self, line: &str, previous: Option<bool>) -> bool {
+        match TimestampFormat::Auto.parse(line) {
+            Some(timestamp) => self.accept(timestamp),
+            None => previous.unwrap_or(self.from.is_none()),
+        }
+    }
+
+    /// Returns the visible lines that are kept.
+    /// The lines are read in order as a line without timestamp depends on the ones before it.
+    /// If it is cancelled, the lines of the remaining groups are missing.
+    fn kept_lines(
+        &self,
+        groups: &[LineGroup],
+        sender: &Sender<Event>,
+        view: Option<&LineView>,
+        cancellation: &CancellationToken,
+    ) -> LineView {
+        let progress_id = operation_id(TIME_FILTERING);
+        let _ = sender.send(Event::OperationStarted(
+            progress_id.clone(),
+            groups.len(),
+            Some(cancellation.clone()),
+        ));
+        let mut previous = None;
+        let mut lines = Vec::new();
+        for group in groups {
+            if cancellation.is_cancelled() {
+                break;
+            }
+            let _ = sender.send(Event::OperationIncrement(progress_id.clone(), 1));
+            let first_line = group.first_line();
+            if let Some(view) = view
+                && view.view_line(first_line) == view.view_line(first_line + group.line_count())
+            {
+                // no visible line in this group, no need to decompress it
+                continue;
+            }
+            for (i, line) in group.lines().iter().enumerate() {
+                let buffer_line = first_line + i;
+                if view.is_some_and(|view| !view.contains(buffer_line)) {
+                    continue;
+                }
+                let kept = self.keeps(line.content(), previous);
+                previous = Some(kept);
+                if kept {
+                    lines.push(buffer_line);
+                }
+            }
+        }
+        let _ = sender.send(Event::OperationFinished(progress_id));
+        LineView::new(lines)
+    }
+}
+
+impl TimeFilter {
+    /// Returns the preview once it is computed, the computation is started on the first call
+    fn preview(&self, textarea_properties: &TextAreaProperties) -> Option<&(usize, LineView)> {
+        let preview = self.preview.get_or_init(|| {
+            let preview = Preview::default();
+            let buffer = &textarea_properties.buffer;
+            let line_count = buffer.line_count();
+            let snapshot = buffer.snapshot();
+            let sender = buffer.sender.clone();
+            let view = textarea_properties.view().cloned();
+            let window = self.window;
+            let cancellation = self.cancellation.clone();
+            let result = Arc::clone(&preview);
+            std::thread::spawn(move || {
+                let kept = window.kept_lines(
+                    snapshot.line_groups(),
+                    &sender,
+                    view.as_ref(),
+                    &cancellation,
+                );
+                if !cancellation.is_cancelled() {
+                    let _ = result.set((line_count, kept));
+                }
+            });
+            preview
+        });
+        preview.get()
+    }
+}
+
+impl Drop for TimeFilter {
+    fn drop(&mut self) {
+        self.cancellation.cancel();
+    }
+}
+
+impl TryFrom<&str> for TimeFilter {
+    type Error = ();
+
+    fn try_from(command: &str) -> Result<Self, Self::Error> {
+        let rest = command.strip_prefix("time ").ok_or(())?;
+        let (from, to) = rest.split_once(SEPARATOR).ok_or(())?;
+        let from = (!from.trim().is_empty())
+            .then(|| TimeBound::parse(from, false))
+            .transpose()?;
+        let to = (!to.trim().is_empty())
+            .then(|| TimeBound::parse(to, true))
+            .transpose()?;
+        if from.is_none() && to.is_none() {
+            return Err(());
+        }
+        Ok(Self {
+            text: rest.trim().to_string(),
+            window: TimeWindow { from, to },
+            preview: OnceLock::new(),
+            cancellation: CancellationToken::default(),
+        })
+    }
+}
+
+impl Renderer for TimeFilter {
+    fn paint_line(
+        &self,
+        ui: &mut Ui,
+        theme: &Theme,
+        textarea_properties: &TextAreaProperties,
+        line: usize,
+        drawing_pos: Pos2,
+        _has_focus: bool,
+    ) {
+        let Some((line_count, kept)) = self.preview(textarea_properties) else {
+            return;
+        };
+        let buffer_line = textarea_properties.buffer_line(line);
+        // the lines appended since the preview was computed are not painted
+        if buffer_line < *line_count && !kept.contains(buffer_line) {
+            let bottom_right = Pos2::new(
+                ui.max_rect().max.x,
+                drawing_pos.y + textarea_properties.line_height,
+            );
+            let line_rect = Rect::from_min_max(drawing_pos, bottom_right);
+            ui.painter().rect(
+                line_rect,
+                0.0,
+                theme.deleting,
+                Stroke::default(),
+                StrokeKind::Inside,
+            );
+        }
+    }
+}
+
+impl Command for TimeFilter {
+    fn execute(&self, buffer: &mut Buffer, cancellation: &CancellationToken) {
+        let line_count = buffer.line_count();
+        let view = self
+            .window
+            .kept_lines(buffer.line_groups(), &buffer.sender, None, cancellation);
+        if cancellation.is_cancelled() {
+            return;
+        }
+        let new_length = buffer.retain_view(&view);
+        info!(
+            "Applied time filter '{}' removed {} lines, new length {new_length}",
+            self.text,
+            line_count - buffer.line_count()
+        );
+    }
+
+    fn filter_view(
+        &self,
+        buffer: &Buffer,
+        view: Option<&LineView>,
+        cancellation: &CancellationToken,
+    ) -> Option<LineView> {
+        let start = Instant::now();
+        let new_view =
+            self.window
+                .kept_lines(buffer.line_groups(), &buffer.sender, view, cancellation);
+        info!(
+            "Filtered view with '{}' keeps {} lines in {}ms",
+            self,
+            new_view.len(),
+            start.elapsed().as_millis()
+        );
+        Some(new_view)
+    }
+}
+
+impl Display for TimeFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Time '{}'", self.text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    const CONTENT: &str = "starting\n\
+        2024-01-31 14:01:59,999 INFO before\n\
+        2024-01-31 14:02:00,000 ERROR failed\n\
+        \tat com.example.Main\n\
+        2024-01-31 14:05:30,500 INFO inside\n\
+        2024-01-31 14:05:31,000 INFO after\n\
+        \tat com.example.Other\n";
+
+    fn buffer() -> Buffer {
+        let (sender, _) = std::sync::mpsc::channel();
+        Buffer::new_from_string(sender, CONTENT, 2)
+    }
+
+    #[rstest]
+    #[case("time 14:02..14:05:30", &[2, 3, 4])]
+    #[case("time 14:02:00..14:05:30.000", &[2, 3])]
+    #[case("time ..14:02", &[0, 1, 2, 3])]
+    #[case("time 2024-01-31T14:05:30..", &[4, 5, 6])]
+    // crossing midnight
+    #[case("time 14:05:31..14:02", &[1, 2, 3, 5, 6])]
+    #[case("time 23:50..00:10", &[])]
+    fn test_filter_view(#[case] command: &str, #[case] expected: &[usize]) -> Result<(), ()> {
+        let filter = TimeFilter::try_from(command)?;
+        let view = filter
+            .filter_view(&buffer(), None, &CancellationToken::default())
+            .ok_or(())?;
+        assert_eq!(expected, view.lines());
+        Ok(())
+    }
+
+    #[test]
+    fn test_filter_view_on_view() -> Result<(), ()> {
+        let buffer = buffer();
+        let view = LineView::new(vec![1, 3, 5, 6]);
+        let filter = TimeFilter::try_from("time 14:00..14:03")?;
+        let view = filter
+            .filter_view(&buffer, Some(&view), &CancellationToken::default())
+            .ok_or(())?;
+        assert_eq!(&[1, 3], view.lines());
+        Ok(())
+    }
+
+    #[test]
+    fn test_execute() -> Result<(), ()> {
+        let mut buffer = buffer();
+        TimeFilter::try_from("time 14:02..14:05:00")?
+            .execute(&mut buffer, &CancellationToken::default());
+        assert_eq!(2, buffer.line_count());
+        buffer.prepare_range_for_read(..);
+        assert_eq!("\tat com.example.Main", buffer.line_text(1));
+        assert!(buffer.dirty);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case("time ..")]
+    #[case("time 14:02")]
+    #[case("time yesterday..today")]
+    fn test_invalid(#[case] command: &str) {
+        assert!(TimeFilter::try_from(command).is_err());
+    }
+}
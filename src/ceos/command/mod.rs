@@ -1,12 +1,53 @@
 use std::fmt::{Debug, Display};
 
 use crate::ceos::buffer::buffer::Buffer;
+use crate::ceos::buffer::line_view::LineView;
+use crate::ceos::command::filter::columnfilter::ColumnFilter;
+use crate::ceos::command::filter::linedrop::LineDrop;
+use crate::ceos::command::filter::linefilter::LineFilter;
+use crate::ceos::command::filter::regexfilter::RegexFilter;
+use crate::ceos::command::filter::timefilter::TimeFilter;
 use crate::ceos::gui::textpane::renderer::Renderer;
+use crate::ceos::tools::cancellation::CancellationToken;
 
 pub(crate) mod direct;
 pub(crate) mod filter;
 pub(crate) mod search;
 
 pub(crate) trait Command: Renderer + Display + Debug {
-    fn execute(&self, buffer: &mut Buffer);
+    /// Modify the buffer content.
+    /// If it is cancelled the buffer may be partially modified, the caller restores it.
+    fn execute(&self, buffer: &mut Buffer, cancellation: &CancellationToken);
+
+    /// Builds a filtered view of the buffer instead of modifying it.
+    /// If a view is given the new view is built on top of it.
+    /// If it is cancelled the returned view is incomplete, the caller drops it.
+    ///
+    /// Returns None if the command has to modify the buffer content, in that case
+    /// `execute` will be called.
+    fn filter_view(
+        &self,
+        _buffer: &Buffer,
+        _view: Option<&LineView>,
+        _cancellation: &CancellationToken,
+    ) -> Option<LineView> {
+        None
+    }
+}
+
+/// Parse a command modifying the buffer, returns None if the text is not a known command
+pub(crate) fn parse_command(command: &str) -> Option<Box<dyn Command + Send + Sync + 'static>> {
+    if let Ok(command) = LineFilter::try_from(command) {
+        Some(Box::new(command))
+    } else if let Ok(command) = RegexFilter::try_from(command) {
+        Some(Box::new(command))
+    } else if let Ok(command) = ColumnFilter::try_from(command) {
+        Some(Box::new(command))
+    } else if let Ok(command) = LineDrop::try_from(command) {
+        Some(Box::new(command))
+    } else if let Ok(command) = TimeFilter::try_from(command) {
+        Some(Box::new(command))
+    } else {
+        None
+    }
 }
@@ -1,39 +1,96 @@
-use std::time::Instant;
-use crate::ceos::buffer::buffer::Buffer;
+use crate::ceos::buffer::line_group::LineGroup;
+use crate::ceos::buffer::line_view::LineView;
+use crate::ceos::command::direct::goto::Goto;
+use crate::ceos::gui::overview;
 use crate::ceos::gui::textpane::renderer::Renderer;
 use crate::ceos::gui::textpane::textareaproperties::TextAreaProperties;
 use crate::ceos::gui::theme::Theme;
+use crate::ceos::progress_manager::operation_id;
+use crate::ceos::tools::cancellation::CancellationToken;
+use crate::event::Event;
 use eframe::emath::{Pos2, Rect};
 use eframe::epaint::{Stroke, StrokeKind};
 use egui::Ui;
 use log::info;
 use rayon::prelude::*;
-use crate::event::Event;
+use regex::Regex;
+use std::sync::mpsc::Sender;
+use std::time::Instant;
+
+/// The text searched, matched as is, ignoring the case or as a regular expression
+#[derive(Debug, Clone)]
+enum Pattern {
+    Text(String),
+    Regex(Regex),
+}
+
+impl Default for Pattern {
+    fn default() -> Self {
+        Pattern::Text(String::new())
+    }
+}
+
+impl Pattern {
+    /// Call the function with the start and end columns of each occurrence in the text
+    fn for_each_match(&self, text: &str, mut f: impl FnMut(usize, usize)) {
+        match self {
+            Pattern::Text(pattern) => text
+                .match_indices(pattern.as_str())
+                .for_each(|(start, found)| f(start, start + found.len())),
+            Pattern::Regex(regex) => regex
+                .find_iter(text)
+                .filter(|found| !found.is_empty())
+                .for_each(|found| f(found.start(), found.end())),
+        }
+    }
+}
+
+/// An occurrence of the searched text
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct SearchMatch {
+    /// The buffer line index
+    pub(crate) line: usize,
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+}
 
 /// Search filter
 #[derive(Default, Debug)]
 pub(crate) struct Search {
-    pattern: String,
-    // the line indexes containing the search value
-    lines: Vec<usize>,
+    pattern: Pattern,
+    // the occurrences of the search value, sorted by line and column
+    matches: Vec<SearchMatch>,
     index: usize,
+    /// Some of the displayed lines containing an occurrence, shown in the overview ruler
+    overview_lines: Vec<usize>,
 }
 
 impl TryFrom<&str> for Search {
     type Error = ();
 
+    /// `s text` searches the text, `si text` ignores the case and `s/regex/` searches a regular
+    /// expression
     fn try_from(command: &str) -> Result<Self, Self::Error> {
-        const PREFIX: &str = "s ";
-        if command.starts_with(PREFIX) && command.len() > 2 {
-            let pattern = command[2..].to_string();
-            Ok(Self {
-                pattern,
-                lines: Vec::new(),
-                index: 0,
-            })
+        let pattern = if let Some(regex) = command.strip_prefix("s/") {
+            let regex = regex.strip_suffix('/').unwrap_or(regex);
+            if regex.is_empty() {
+                return Err(());
+            }
+            Pattern::Regex(Regex::new(regex).map_err(|_| ())?)
+        } else if let Some(text) = command.strip_prefix("si ").filter(|text| !text.is_empty()) {
+            let regex = format!("(?i){}", regex::escape(text));
+            Pattern::Regex(Regex::new(&regex).map_err(|_| ())?)
+        } else if let Some(text) = command.strip_prefix("s ").filter(|text| !text.is_empty()) {
+            Pattern::Text(text.to_string())
         } else {
-            Err(())
-        }
+            return Err(());
+        };
+        Ok(Self {
+            pattern,
+            matches: Vec::new(),
+            index: 0,
+            overview_lines: Vec::new(),
+        })
     }
 }
 
@@ -47,47 +104,85 @@ impl Renderer for Search {
         drawing_pos: Pos2,
         _has_focus: bool,
     ) {
-        let line = &textarea.buffer[line];
-        if let Some(offset) = line.content().find(&self.pattern) {
-            let x1 = offset as f32 * textarea.char_width;
-            let x2 = (offset + self.pattern.len()) as f32 * textarea.char_width;
-            let top_left = Pos2::new(drawing_pos.x + x1, drawing_pos.y);
-            let bottom_right = Pos2::new(drawing_pos.x + x2, drawing_pos.y + textarea.line_height);
-            let line_rect = Rect::from_min_max(top_left, bottom_right);
-            let painter = ui.painter();
-            painter.rect(
-                line_rect,
-                0.0,
-                theme.deleting,
-                Stroke::default(),
-                StrokeKind::Inside,
-            );
-        }
+        let buffer_line = textarea.buffer_line(line);
+        let current = self.matches.get(self.index);
+        let painter = ui.painter();
+        self.pattern
+            .for_each_match(textarea.line(line).content(), |start, end| {
+                let x1 = start as f32 * textarea.char_width;
+                let x2 = end as f32 * textarea.char_width;
+                let top_left = Pos2::new(drawing_pos.x + x1, drawing_pos.y);
+                let bottom_right =
+                    Pos2::new(drawing_pos.x + x2, drawing_pos.y + textarea.line_height);
+                let line_rect = Rect::from_min_max(top_left, bottom_right);
+                // the current occurrence is outlined
+                let stroke = if current.is_some_and(|current| {
+                    current.line == buffer_line && current.start == start
+                }) {
+                    Stroke::new(2.0, theme.text)
+                } else {
+                    Stroke::default()
+                };
+                painter.rect(line_rect, 0.0, theme.deleting, stroke, StrokeKind::Inside);
+            });
     }
 }
 
 const SEARCHING_INDEX: &str = "Searching...";
 
 impl Search {
-    pub(crate) fn init(&mut self, buffer: &Buffer) {
+    /// Search the pattern in the line groups of a buffer, if a view is given only the visible
+    /// lines are searched.
+    /// Returns false if the search was cancelled, the matches are then incomplete.
+    pub(crate) fn init(
+        &mut self,
+        groups: &[LineGroup],
+        view: Option<&LineView>,
+        sender: &Sender<Event>,
+        cancellation: &CancellationToken,
+    ) -> bool {
         let start = Instant::now();
-        let _ = buffer.sender.send(Event::OperationStarted(SEARCHING_INDEX.to_owned(), buffer.line_groups().len()));
-        let lines: Vec<usize> = buffer
-            .line_groups()
+        let progress_id = operation_id(SEARCHING_INDEX);
+        let _ = sender.send(Event::OperationStarted(
+            progress_id.clone(),
+            groups.len(),
+            Some(cancellation.clone()),
+        ));
+        let matches: Vec<SearchMatch> = groups
             .par_iter()
+            .filter(|_| !cancellation.is_cancelled())
             .map(|line_group| (line_group.first_line(), line_group.lines()))
             .flat_map(|(first_line, lines)| {
-                let _ = buffer.sender.send(Event::OperationIncrement(SEARCHING_INDEX.to_owned(), 1));
+                let _ = sender.send(Event::OperationIncrement(progress_id.clone(), 1));
+                let mut matches = Vec::new();
                 lines
                     .iter()
                     .enumerate()
-                    .filter_map(|(i, line)| line.contains(&self.pattern).then_some(first_line + i))
-                    .collect::<Vec<_>>()
+                    .filter(|(i, _)| view.is_none_or(|view| view.contains(first_line + i)))
+                    .for_each(|(i, line)| {
+                        self.pattern.for_each_match(line.content(), |start, end| {
+                            matches.push(SearchMatch { line: first_line + i, start, end });
+                        });
+                    });
+                matches
             })
             .collect();
-        self.lines = lines;
-        let _ = buffer.sender.send(Event::OperationFinished(SEARCHING_INDEX.to_owned()));
+        let _ = sender.send(Event::OperationFinished(progress_id));
+        if cancellation.is_cancelled() {
+            info!("Search cancelled after {}ms", start.elapsed().as_millis());
+            return false;
+        }
+        let line_count = view.map_or_else(
+            || groups.iter().map(LineGroup::line_count).sum(),
+            LineView::len,
+        );
+        let lines = matches
+            .iter()
+            .map(|found| view.map_or(found.line, |view| view.view_line(found.line)));
+        self.overview_lines = overview::sample(lines, line_count);
+        self.matches = matches;
         info!("Search took {}ms", start.elapsed().as_millis());
+        true
     }
 
     pub(crate) fn reset(&mut self) {
@@ -95,53 +190,121 @@ impl Search {
     }
 
     pub(crate) const fn has_results(&self) -> bool {
-        !self.lines.is_empty()
+        !self.matches.is_empty()
     }
 
     pub(crate) const fn next(&mut self) {
-        self.index = (self.index + 1) % self.lines.len();
+        if self.matches.is_empty() {
+            return;
+        }
+        self.index = (self.index + 1) % self.matches.len();
     }
 
     pub(crate) const fn prev(&mut self) {
-        if self.lines.is_empty() {
+        if self.matches.is_empty() {
             return;
         }
         if self.index == 0 {
-            self.index = self.lines.len() - 1;
+            self.index = self.matches.len() - 1;
         } else {
             self.index -= 1;
         }
     }
 
-    pub(crate) fn line(&self) -> usize {
-        self.lines[self.index]
+    /// Returns the command moving the caret to the current occurrence
+    pub(crate) fn goto(&self) -> Option<Goto> {
+        self.matches
+            .get(self.index)
+            .map(|found| Goto::at(found.line, found.start))
     }
 
-    pub(crate) fn line_number(&self, index: usize) -> usize {
-        self.lines[index]
+    pub(crate) fn result(&self, index: usize) -> SearchMatch {
+        self.matches[index]
     }
 
+    /// The number of occurrences
     pub(crate) const fn result_count(&self) -> usize {
-        self.lines.len()
+        self.matches.len()
+    }
+
+    pub(crate) fn overview_lines(&self) -> &[usize] {
+        &self.overview_lines
+    }
+
+    /// The number of lines containing at least one occurrence
+    pub(crate) fn line_count(&self) -> usize {
+        self.matches
+            .chunk_by(|first, second| first.line == second.line)
+            .count()
     }
 }
 
-// #[cfg(test)]
-// mod tests {
-//     use super::*;
-//
-//     #[test]
-//     fn test_filter() -> Result<(), String> {
-//         let filter = LineFilter::try_from("filter delete")?;
-//         let content = "1 delete me\n\
-//         2 keep me\n\
-//         3 delete me\n\
-//         4 keep me\n";
-//         let mut buffer = Buffer::from(content);
-//         assert_eq!(content.len(), buffer.len());
-//         assert_eq!(4, buffer.line_count());
-//         filter.execute(&mut buffer);
-//         assert_eq!(2, buffer.line_count());
-//         Ok(())
-//     }
-// }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ceos::buffer::buffer::Buffer;
+    use rstest::rstest;
+
+    const CONTENT: &str = "Error: disk full, error 28\n\
+        info: ok\n\
+        ERROR: timeout 30\n";
+
+    fn search(command: &str) -> Search {
+        let (sender, _) = std::sync::mpsc::channel();
+        let buffer = Buffer::new_from_string(sender, CONTENT, 2);
+        let mut search = Search::try_from(command).unwrap();
+        let cancellation = CancellationToken::default();
+        assert!(search.init(buffer.line_groups(), None, &buffer.sender, &cancellation));
+        search
+    }
+
+    #[test]
+    fn test_cancelled() {
+        let (sender, _) = std::sync::mpsc::channel();
+        let buffer = Buffer::new_from_string(sender, CONTENT, 2);
+        let mut search = Search::try_from("s error").unwrap();
+        let cancellation = CancellationToken::default();
+        cancellation.cancel();
+        assert!(!search.init(buffer.line_groups(), None, &buffer.sender, &cancellation));
+        assert!(!search.has_results());
+    }
+
+    #[rstest]
+    #[case("s error", &[(0, 18, 23)])]
+    #[case("si error", &[(0, 0, 5), (0, 18, 23), (2, 0, 5)])]
+    #[case("s/[0-9]+/", &[(0, 24, 26), (2, 15, 17)])]
+    #[case("s/o*/", &[(0, 3, 4), (0, 21, 22), (1, 3, 4), (1, 6, 7), (2, 11, 12)])]
+    fn test_matches(#[case] command: &str, #[case] expected: &[(usize, usize, usize)]) {
+        let search = search(command);
+        let matches: Vec<(usize, usize, usize)> = (0..search.result_count())
+            .map(|index| search.result(index))
+            .map(|found| (found.line, found.start, found.end))
+            .collect();
+        assert_eq!(expected, matches);
+    }
+
+    #[test]
+    fn test_next_within_line() {
+        let mut search = search("si error");
+        assert_eq!(3, search.result_count());
+        assert_eq!(2, search.line_count());
+        assert_eq!(&[0, 2], search.overview_lines());
+        search.next();
+        assert_eq!(SearchMatch { line: 0, start: 18, end: 23 }, search.result(search.index));
+        search.next();
+        search.next();
+        assert_eq!(0, search.index);
+        search.prev();
+        assert_eq!(2, search.index);
+    }
+
+    #[rstest]
+    #[case("s ")]
+    #[case("si ")]
+    #[case("s//")]
+    #[case("s/(/")]
+    #[case("search")]
+    fn test_invalid(#[case] command: &str) {
+        assert!(Search::try_from(command).is_err());
+    }
+}
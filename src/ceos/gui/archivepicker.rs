@@ -0,0 +1,84 @@
+use crate::ceos::buffer::archive::{Archive, ArchiveEntry};
+use egui::{Align2, Context, Key, ScrollArea, TextEdit, Window};
+use humansize::{DECIMAL, format_size_i};
+use std::path::PathBuf;
+
+/// Lists the files of an archive so the user can choose the ones to open
+#[derive(Debug)]
+pub(crate) struct ArchivePicker {
+    pub(crate) path: PathBuf,
+    pub(crate) archive: Archive,
+    entries: Vec<ArchiveEntry>,
+    selected: Vec<bool>,
+    /// Only the entries containing this text are listed
+    filter: String,
+}
+
+impl ArchivePicker {
+    pub(crate) fn new(path: PathBuf, archive: Archive, entries: Vec<ArchiveEntry>) -> Self {
+        Self {
+            path,
+            archive,
+            selected: vec![false; entries.len()],
+            entries,
+            filter: String::new(),
+        }
+    }
+
+    /// Show the picker, returns the entries to open once the user validates the selection.
+    /// A double click opens only the clicked entry.
+    pub(crate) fn show(&mut self, ctx: &Context, open: &mut bool) -> Option<Vec<ArchiveEntry>> {
+        let mut chosen = None;
+        let title = self.path.file_name().map_or_else(
+            || "Archive".to_string(),
+            |name| name.to_string_lossy().into_owned(),
+        );
+        Window::new(title)
+            .open(open)
+            .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.add(TextEdit::singleline(&mut self.filter).hint_text("Filter"));
+                ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                    for (entry, selected) in self.entries.iter().zip(self.selected.iter_mut()) {
+                        if !entry.name.contains(&self.filter) {
+                            continue;
+                        }
+                        let text =
+                            format!("{} ({})", entry.name, format_size_i(entry.size, DECIMAL));
+                        let response = ui.toggle_value(selected, text);
+                        if response.double_clicked() {
+                            chosen = Some(vec![entry.clone()]);
+                        }
+                    }
+                });
+                ui.horizontal(|ui| {
+                    let selection = self.selection();
+                    if ui
+                        .add_enabled(!selection.is_empty(), egui::Button::new("Open"))
+                        .on_hover_text("The selected files are opened one after the other")
+                        .clicked()
+                    {
+                        chosen = Some(selection);
+                    }
+                });
+            });
+
+        if ctx.input(|i| i.key_pressed(Key::Escape)) {
+            *open = false;
+        }
+        if chosen.is_some() {
+            *open = false;
+        }
+        chosen
+    }
+
+    fn selection(&self) -> Vec<ArchiveEntry> {
+        self.entries
+            .iter()
+            .zip(&self.selected)
+            .filter(|(_, selected)| **selected)
+            .map(|(entry, _)| entry.clone())
+            .collect()
+    }
+}
@@ -24,16 +24,47 @@ impl HelpPanel {
                             ui.label("Go to the specified line");
                             ui.end_row();
 
+                            ui.label("@<time>");
+                            ui.label("Go to the first line logged at or after the time (ex: @14:03:12, @2026-10-17T14:03)");
+                            ui.end_row();
+
                             ui.label("s <text>");
-                            ui.label("Search the given text");
+                            ui.label("Search the given text, F3 and Shift+F3 go to the next and previous occurrences");
+                            ui.end_row();
+
+                            ui.label("si <text>");
+                            ui.label("Search the given text ignoring the case");
+                            ui.end_row();
+
+                            ui.label("s/<regex>/");
+                            ui.label("Search the given regular expression");
+                            ui.end_row();
+
+                            ui.label("highlight [#rrggbb] <text>");
+                            ui.label("Paint every occurrence of the text in its own colour, also marked in the overview ruler");
+                            ui.end_row();
+
+                            ui.label("unhighlight <text>");
+                            ui.label("Remove the highlight of the text, or the one at the given position in the list");
+                            ui.end_row();
+
+                            ui.label("highlights");
+                            ui.label("List the highlights to change their colour or remove them");
                             ui.end_row();
 
                             ui.label("zoom <value>");
                             ui.label("Change the police size (ex: zoom 1.5, zoom reset)");
                             ui.end_row();
 
-                            ui.label("filter <text>");
-                            ui.label("Keep only the lines containing the given text. The text can be prefixed with ! to reverse the filter (use & for multiple conditions)");
+                            ui.label("filter <expression>");
+                            ui.label("Keep only the lines matching the expression. Terms can be combined with & (and), | (or), ! (not) and parentheses");
+                            ui.end_row();
+                            ui.label("");
+                            ui.label("ex: filter (ERROR | FATAL) & !healthcheck & \"user 42\"");
+                            ui.end_row();
+
+                            ui.label("refilter <regex>");
+                            ui.label("Keep only the lines matching the given regular expression");
                             ui.end_row();
 
                             ui.label("l <range>");
@@ -49,8 +80,39 @@ impl HelpPanel {
                             ui.label("ex: l 10.. will drop the lines 10 to the end");
                             ui.end_row();
 
+                            ui.label("time <from>..<to>");
+                            ui.label("Keep only the lines logged within the time window, the lines without timestamp follow the line before them");
+                            ui.end_row();
+                            ui.label("");
+                            ui.label("ex: time 14:02..14:05:30, time 2024-01-31T14:00:00..");
+                            ui.end_row();
+
+                            ui.label("unfilter");
+                            ui.label("Remove the last filter, the filters are only hiding lines");
+                            ui.end_row();
+
+                            ui.label("follow");
+                            ui.label("Start or stop following the lines appended to the file (like tail -f)");
+                            ui.end_row();
+
+                            ui.label("undo / redo");
+                            ui.label("Revert or apply again the last edit or command (Ctrl+Z / Ctrl+Shift+Z)");
+                            ui.end_row();
+
                             ui.label("close");
-                            ui.label("Close the current file");
+                            ui.label("Close the current tab (Ctrl+W), Ctrl+Tab switches to the next tab");
+                            ui.end_row();
+
+                            ui.label("merge open [format]");
+                            ui.label("Merge several files into one timeline, ordered by the timestamps of the lines");
+                            ui.end_row();
+
+                            ui.label("split / vsplit");
+                            ui.label("Show a second pane below or beside the text, with its own caret and scroll");
+                            ui.end_row();
+
+                            ui.label("unsplit / sync");
+                            ui.label("Close the second pane, or synchronize the scroll of the two panes");
                             ui.end_row();
                         });
                     ui.add_space(16.0);
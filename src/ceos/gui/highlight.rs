@@ -0,0 +1,235 @@
+use crate::ceos::gui::textpane::renderer::highlight_renderer::HighlightRenderer;
+use crate::ceos::gui::textpane::renderer::renderer_manager::{HIGHLIGHT_LAYER, RendererManager};
+use egui::{Align2, Color32, Context, Grid, Ui, Window};
+use serde::{Deserialize, Serialize};
+
+/// The colours given to the new highlights, the first unused one is taken
+const PALETTE: [[u8; 3]; 8] = [
+    [255, 200, 0],
+    [0, 160, 255],
+    [60, 200, 60],
+    [255, 80, 80],
+    [180, 100, 255],
+    [0, 200, 190],
+    [255, 140, 30],
+    [240, 90, 200],
+];
+
+/// The opacity of the highlights painted behind the text, so it stays readable
+const HIGHLIGHT_ALPHA: u8 = 110;
+
+/// A text painted in its own colour wherever it appears
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Highlight {
+    pub(crate) text: String,
+    /// The RGB colour
+    pub(crate) color: [u8; 3],
+}
+
+impl Highlight {
+    fn background(&self) -> Color32 {
+        let [r, g, b] = self.color;
+        Color32::from_rgba_unmultiplied(r, g, b, HIGHLIGHT_ALPHA)
+    }
+
+    pub(crate) fn color32(&self) -> Color32 {
+        let [r, g, b] = self.color;
+        Color32::from_rgb(r, g, b)
+    }
+}
+
+/// The highlight commands typed in the command buffer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum HighlightCommand {
+    /// Highlight a text, with the next colour of the palette if none is given
+    Add(String, Option<[u8; 3]>),
+    /// Remove the highlight of a text, or the one at a position in the list starting at 1
+    Remove(String),
+    /// Show the list of the highlights
+    List,
+}
+
+impl TryFrom<&str> for HighlightCommand {
+    type Error = ();
+
+    fn try_from(command: &str) -> Result<Self, Self::Error> {
+        if command == "highlights" {
+            return Ok(HighlightCommand::List);
+        }
+        if let Some(text) = command.strip_prefix("unhighlight ")
+            && !text.is_empty()
+        {
+            return Ok(HighlightCommand::Remove(text.to_string()));
+        }
+        let text = command.strip_prefix("highlight ").ok_or(())?;
+        if let Some((color, rest)) = text.split_once(' ')
+            && let Some(color) = parse_color(color)
+            && !rest.is_empty()
+        {
+            return Ok(HighlightCommand::Add(rest.to_string(), Some(color)));
+        }
+        if text.is_empty() {
+            return Err(());
+        }
+        Ok(HighlightCommand::Add(text.to_string(), None))
+    }
+}
+
+/// Parse a colour like `#ff8800`
+fn parse_color(text: &str) -> Option<[u8; 3]> {
+    let hex = text.strip_prefix('#')?;
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let component = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some([component(0)?, component(2)?, component(4)?])
+}
+
+/// The highlights shared by all the tabs, each one is painted by its own renderer
+#[derive(Debug, Default)]
+pub(crate) struct HighlightManager {
+    highlights: Vec<Highlight>,
+}
+
+impl HighlightManager {
+    pub(crate) const fn new(highlights: Vec<Highlight>) -> Self {
+        Self { highlights }
+    }
+
+    pub(crate) fn highlights(&self) -> &[Highlight] {
+        &self.highlights
+    }
+
+    /// Highlight a text, only the colour is changed if the text is already highlighted
+    pub(crate) fn add(&mut self, text: String, color: Option<[u8; 3]>) {
+        match self.highlights.iter_mut().find(|h| h.text == text) {
+            Some(highlight) => {
+                if let Some(color) = color {
+                    highlight.color = color;
+                }
+            }
+            None => {
+                let color = color.unwrap_or_else(|| self.next_color());
+                self.highlights.push(Highlight { text, color });
+            }
+        }
+    }
+
+    /// Returns the first colour of the palette not used yet, they are reused once all taken
+    fn next_color(&self) -> [u8; 3] {
+        PALETTE
+            .into_iter()
+            .find(|color| self.highlights.iter().all(|h| h.color != *color))
+            .unwrap_or(PALETTE[self.highlights.len() % PALETTE.len()])
+    }
+
+    /// Remove the highlight of a text, or the one at a position in the list starting at 1.
+    /// Returns false if there is no such highlight.
+    pub(crate) fn remove(&mut self, text: &str) -> bool {
+        let index = self
+            .highlights
+            .iter()
+            .position(|h| h.text == text)
+            .or_else(|| {
+                text.parse::<usize>()
+                    .ok()
+                    .filter(|position| (1..=self.highlights.len()).contains(position))
+                    .map(|position| position - 1)
+            });
+        match index {
+            Some(index) => {
+                self.highlights.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Replace the highlight renderers of a text area by the current highlights
+    pub(crate) fn register(&self, renderer_manager: &mut RendererManager) {
+        renderer_manager.remove_layer(HIGHLIGHT_LAYER);
+        for highlight in &self.highlights {
+            let renderer = HighlightRenderer::new(highlight.text.clone(), highlight.background());
+            renderer_manager.add_renderer(HIGHLIGHT_LAYER, Box::new(renderer));
+        }
+    }
+
+    /// Show the list of the highlights to change their colour or remove them.
+    /// Returns true if the highlights were changed.
+    pub(crate) fn show(&mut self, ctx: &Context, open: &mut bool) -> bool {
+        let mut changed = false;
+        let mut removed = None;
+        Window::new("Highlights")
+            .open(open)
+            .anchor(Align2::RIGHT_TOP, [-16.0, 48.0])
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                if self.highlights.is_empty() {
+                    ui.label("No highlight, add one with: highlight <text>");
+                    return;
+                }
+                Grid::new("highlights_grid")
+                    .spacing([12.0, 6.0])
+                    .show(ui, |ui: &mut Ui| {
+                        for (index, highlight) in self.highlights.iter_mut().enumerate() {
+                            ui.label(format!("{}", index + 1));
+                            changed |= ui.color_edit_button_srgb(&mut highlight.color).changed();
+                            ui.label(&highlight.text);
+                            if ui
+                                .add(egui::Button::new("x").frame(false).small())
+                                .on_hover_text("Remove the highlight (unhighlight <text>)")
+                                .clicked()
+                            {
+                                removed = Some(index);
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+        if let Some(index) = removed {
+            self.highlights.remove(index);
+            changed = true;
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("highlight WARN", Ok(HighlightCommand::Add("WARN".into(), None)))]
+    #[case(
+        "highlight #ff8800 orderId=1234",
+        Ok(HighlightCommand::Add("orderId=1234".into(), Some([255, 136, 0])))
+    )]
+    #[case("highlight #tag value", Ok(HighlightCommand::Add("#tag value".into(), None)))]
+    #[case("unhighlight 2", Ok(HighlightCommand::Remove("2".into())))]
+    #[case("highlights", Ok(HighlightCommand::List))]
+    #[case("highlight ", Err(()))]
+    #[case("unhighlight ", Err(()))]
+    fn test_try_from(#[case] command: &str, #[case] expected: Result<HighlightCommand, ()>) {
+        assert_eq!(HighlightCommand::try_from(command), expected);
+    }
+
+    #[test]
+    fn test_add_remove() {
+        let mut manager = HighlightManager::default();
+        manager.add("WARN".into(), None);
+        manager.add("host-1".into(), None);
+        manager.add("WARN".into(), Some([1, 2, 3]));
+        assert_eq!(2, manager.highlights().len());
+        assert_eq!([1, 2, 3], manager.highlights()[0].color);
+        // the freed colour of the palette is given to the next highlight
+        manager.add("ERROR".into(), None);
+        assert_eq!(PALETTE[0], manager.highlights()[2].color);
+
+        assert!(manager.remove("host-1"));
+        assert!(manager.remove("1"));
+        assert!(!manager.remove("2"));
+        assert_eq!("ERROR", manager.highlights()[0].text);
+    }
+}
@@ -1,6 +1,10 @@
+pub(crate) mod archivepicker;
 pub(crate) mod frame_history;
 pub(crate) mod helppanel;
+pub(crate) mod highlight;
+pub(crate) mod overview;
 pub(crate) mod searchpanel;
+pub(crate) mod split;
 pub(crate) mod textpane;
 pub(crate) mod theme;
 pub(crate) mod tools;
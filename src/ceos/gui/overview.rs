@@ -0,0 +1,306 @@
+use crate::ceos::buffer::buffer::Buffer;
+use crate::ceos::buffer::line_group::LineGroup;
+use crate::ceos::buffer::line_view::LineView;
+use crate::ceos::gui::highlight::Highlight;
+use crate::ceos::gui::theme::Theme;
+use crate::ceos::tools::cancellation::CancellationToken;
+use crate::event::Event;
+use eframe::emath::{Pos2, Rect};
+use egui::{Color32, Stroke, StrokeKind, Ui};
+use log::debug;
+use std::thread;
+use std::time::Instant;
+
+/// The width of the overview ruler on the right of the text area
+pub(crate) const RULER_WIDTH: f32 = 14.0;
+
+/// The maximum number of marks of each kind, there is at most one mark per slice of the lines
+const MAX_MARKS: usize = 1000;
+const MARK_HEIGHT: f32 = 2.0;
+
+/// The levels of the lines marked as errors
+const ERROR_LEVELS: [&str; 4] = ["ERROR", "FATAL", "SEVERE", "CRITICAL"];
+
+/// What a mark of the ruler shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MarkKind {
+    Error,
+    /// A line containing the text of a highlight, given by its index
+    Highlight(usize),
+}
+
+/// A line shown in the overview ruler
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Mark {
+    /// The line in the displayed view
+    line: usize,
+    kind: MarkKind,
+}
+
+/// The overview ruler of a tab, it shows where the search results, the highlighted texts and
+/// the errors are in the whole view.
+///
+/// The highlighted and error lines are searched in a background thread from a snapshot of the
+/// buffer, the search results are taken from the search.
+#[derive(Debug, Default)]
+pub(crate) struct Overview {
+    marks: Vec<Mark>,
+    /// The colours of the highlights, they can change without searching the lines again
+    highlight_colors: Vec<Color32>,
+    /// Incremented by each update, the results of the previous ones are dropped
+    generation: usize,
+    /// Stops the search of the last update when a new one starts
+    cancellation: CancellationToken,
+}
+
+impl Overview {
+    /// Search the highlighted and error lines of the view in a background thread.
+    /// The compressed groups are shared with the snapshot, they are decompressed one at a time.
+    pub(crate) fn update(
+        &mut self,
+        tab: usize,
+        buffer: &Buffer,
+        view: Option<&LineView>,
+        highlights: &[Highlight],
+    ) {
+        self.set_colors(highlights);
+        self.generation += 1;
+        let generation = self.generation;
+        self.cancellation.cancel();
+        self.cancellation = CancellationToken::default();
+        let cancellation = self.cancellation.clone();
+        let snapshot = buffer.snapshot();
+        let view = view.cloned();
+        let texts: Vec<String> = highlights.iter().map(|h| h.text.clone()).collect();
+        let sender = buffer.sender.clone();
+        thread::spawn(move || {
+            let start = Instant::now();
+            let marks = scan(snapshot.line_groups(), view.as_ref(), &texts, &cancellation);
+            if cancellation.is_cancelled() {
+                return;
+            }
+            debug!(
+                "Overview of tab {tab} found {} marks in {}ms",
+                marks.len(),
+                start.elapsed().as_millis()
+            );
+            let _ = sender.send(Event::OverviewComputed(tab, generation, marks));
+        });
+    }
+
+    /// Keep the marks if they come from the last update
+    pub(crate) fn set_marks(&mut self, generation: usize, marks: Vec<Mark>) {
+        if generation == self.generation {
+            self.marks = marks;
+        }
+    }
+
+    /// Use the current colours of the highlights
+    pub(crate) fn set_colors(&mut self, highlights: &[Highlight]) {
+        self.highlight_colors = highlights.iter().map(Highlight::color32).collect();
+    }
+
+    /// Paint the ruler, the search results on the left, then the highlights and the errors.
+    /// The visible lines are framed.
+    pub(crate) fn paint(
+        &self,
+        ui: &Ui,
+        rect: Rect,
+        theme: &Theme,
+        search_lines: &[usize],
+        line_count: usize,
+        visible: (usize, usize),
+    ) {
+        let painter = ui.painter();
+        painter.rect_filled(rect, 0.0, theme.background_faint);
+        let lane_width = rect.width() / 3.0;
+        let line_count = line_count.max(1) as f32;
+        let y = |line: usize| rect.top() + line as f32 / line_count * rect.height();
+        let paint_mark = |lane: f32, line: usize, color: Color32| {
+            let left = rect.left() + lane * lane_width;
+            let top = y(line);
+            let mark = Rect::from_min_max(
+                Pos2::new(left, top),
+                Pos2::new(left + lane_width, top + MARK_HEIGHT),
+            );
+            painter.rect_filled(mark, 0.0, color);
+        };
+        for line in search_lines {
+            paint_mark(0.0, *line, theme.text);
+        }
+        for mark in &self.marks {
+            match mark.kind {
+                MarkKind::Highlight(index) => {
+                    if let Some(color) = self.highlight_colors.get(index) {
+                        paint_mark(1.0, mark.line, *color);
+                    }
+                }
+                MarkKind::Error => paint_mark(2.0, mark.line, theme.error),
+            }
+        }
+        let (first, last) = visible;
+        let frame = Rect::from_min_max(
+            Pos2::new(rect.left(), y(first)),
+            Pos2::new(rect.right(), y(last).max(y(first) + MARK_HEIGHT)),
+        );
+        painter.rect_stroke(
+            frame,
+            0.0,
+            Stroke::new(1.0, theme.text_faint),
+            StrokeKind::Inside,
+        );
+    }
+}
+
+impl Drop for Overview {
+    fn drop(&mut self) {
+        self.cancellation.cancel();
+    }
+}
+
+/// Returns the line displayed at the height of the ruler
+pub(crate) fn line_at(rect: Rect, y: f32, line_count: usize) -> usize {
+    let ratio = ((y - rect.top()) / rect.height()).clamp(0.0, 1.0);
+    ((ratio * line_count as f32) as usize).min(line_count.saturating_sub(1))
+}
+
+/// Keep at most one line per slice of the view, the lines must be sorted
+pub(crate) fn sample(lines: impl Iterator<Item = usize>, line_count: usize) -> Vec<usize> {
+    let slice = slice_size(line_count);
+    let mut sampled: Vec<usize> = lines.collect();
+    sampled.dedup_by_key(|line| *line / slice);
+    sampled
+}
+
+/// The number of lines sharing a mark of the ruler
+fn slice_size(line_count: usize) -> usize {
+    line_count.div_ceil(MAX_MARKS).max(1)
+}
+
+fn is_error(text: &str) -> bool {
+    ERROR_LEVELS.iter().any(|level| text.contains(level))
+}
+
+/// Returns the marks of the highlighted and error lines of the view.
+/// If it is cancelled, the marks of the remaining groups are missing.
+fn scan(
+    groups: &[LineGroup],
+    view: Option<&LineView>,
+    highlights: &[String],
+    cancellation: &CancellationToken,
+) -> Vec<Mark> {
+    let line_count = view.map_or_else(
+        || groups.iter().map(LineGroup::line_count).sum(),
+        LineView::len,
+    );
+    let slice = slice_size(line_count);
+    let mut marks = Vec::new();
+    let mut last_error = None;
+    let mut last_highlight = None;
+    for group in groups {
+        if cancellation.is_cancelled() {
+            break;
+        }
+        let first_line = group.first_line();
+        if let Some(view) = view
+            && view.view_line(first_line) == view.view_line(first_line + group.line_count())
+        {
+            // no visible line in this group, no need to decompress it
+            continue;
+        }
+        for (i, line) in group.lines().iter().enumerate() {
+            let buffer_line = first_line + i;
+            if view.is_some_and(|view| !view.contains(buffer_line)) {
+                continue;
+            }
+            let line_index = view.map_or(buffer_line, |view| view.view_line(buffer_line));
+            let row = Some(line_index / slice);
+            let text = line.content();
+            if last_error != row && is_error(text) {
+                marks.push(Mark {
+                    line: line_index,
+                    kind: MarkKind::Error,
+                });
+                last_error = row;
+            }
+            if last_highlight != row
+                && let Some(index) = highlights.iter().position(|h| text.contains(h.as_str()))
+            {
+                marks.push(Mark {
+                    line: line_index,
+                    kind: MarkKind::Highlight(index),
+                });
+                last_highlight = row;
+            }
+        }
+    }
+    marks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eframe::emath::pos2;
+    use rstest::rstest;
+
+    const CONTENT: &str = "start host-1\n\
+        ERROR failed on host-1\n\
+        retry\n\
+        FATAL host-2 down\n";
+
+    #[test]
+    fn test_scan() {
+        let (sender, _) = std::sync::mpsc::channel();
+        let buffer = Buffer::new_from_string(sender, CONTENT, 2);
+        let snapshot = buffer.snapshot();
+        let highlights = vec!["host-2".to_string(), "host-1".to_string()];
+        let mark = |line, kind| Mark { line, kind };
+        assert_eq!(
+            vec![
+                mark(0, MarkKind::Highlight(1)),
+                mark(1, MarkKind::Error),
+                mark(1, MarkKind::Highlight(1)),
+                mark(3, MarkKind::Error),
+                mark(3, MarkKind::Highlight(0)),
+            ],
+            scan(
+                snapshot.line_groups(),
+                None,
+                &highlights,
+                &CancellationToken::default()
+            )
+        );
+        let view = LineView::new(vec![2, 3]);
+        assert_eq!(
+            vec![mark(1, MarkKind::Error), mark(1, MarkKind::Highlight(0))],
+            scan(
+                snapshot.line_groups(),
+                Some(&view),
+                &highlights,
+                &CancellationToken::default()
+            )
+        );
+        let cancellation = CancellationToken::default();
+        cancellation.cancel();
+        assert!(scan(snapshot.line_groups(), None, &highlights, &cancellation).is_empty());
+    }
+
+    #[test]
+    fn test_sample() {
+        assert_eq!(vec![1, 5, 8], sample([1, 5, 8].into_iter(), 100));
+        assert_eq!(
+            vec![1, 2_500],
+            sample([1, 3, 2_500, 2_501].into_iter(), 100_000)
+        );
+    }
+
+    #[rstest]
+    #[case(0.0, 0)]
+    #[case(50.0, 500)]
+    #[case(100.0, 999)]
+    #[case(150.0, 999)]
+    fn test_line_at(#[case] y: f32, #[case] expected: usize) {
+        let rect = Rect::from_min_max(pos2(0.0, 0.0), pos2(RULER_WIDTH, 100.0));
+        assert_eq!(expected, line_at(rect, y, 1_000));
+    }
+}
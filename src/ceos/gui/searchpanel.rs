@@ -1,6 +1,6 @@
 use crate::ceos::buffer::buffer::Buffer;
 use crate::ceos::command::direct::goto::Goto;
-use crate::ceos::command::search::Search;
+use crate::ceos::command::search::{Search, SearchMatch};
 use crate::event::Event;
 use crate::event::Event::GotoLine;
 use egui::{Label, ScrollArea, Sense, TextWrapMode, WidgetText};
@@ -29,16 +29,16 @@ impl SearchPanel {
                 .column(Column::remainder())
                 .body(|body| {
                     body.rows(30.0, self.search.result_count(), |mut row| {
-                        let row_index = row.index();
-                        let line_number = self.search.line_number(row_index);
-                        self.add_column(&mut row, line_number, (line_number + 1).to_string());
-                        self.add_column(&mut row, line_number, buffer.line_text(line_number));
+                        let found = self.search.result(row.index());
+                        let position = format!("{}:{}", found.line + 1, found.start + 1);
+                        self.add_column(&mut row, found, position);
+                        self.add_column(&mut row, found, buffer.line_text(found.line));
                     });
                 });
         });
     }
 
-    fn add_column(&self, row: &mut TableRow, line_number: usize, text: impl Into<WidgetText>) {
+    fn add_column(&self, row: &mut TableRow, found: SearchMatch, text: impl Into<WidgetText>) {
         let label = Label::new(text)
             .wrap_mode(TextWrapMode::Extend)
             .selectable(false);
@@ -48,7 +48,7 @@ impl SearchPanel {
         .1
         .clicked()
         .then(|| {
-            let _ = self.sender.send(GotoLine(Goto::from(line_number)));
+            let _ = self.sender.send(GotoLine(Goto::at(found.line, found.start)));
         });
     }
 }
@@ -0,0 +1,140 @@
+use crate::ceos::gui::textpane::textareaproperties::PaneState;
+use eframe::emath::{Rect, Vec2};
+use std::fmt::{Display, Formatter};
+
+/// The separation between the two panes
+const SEPARATOR_WIDTH: f32 = 4.0;
+
+/// How the text area is divided between the two panes
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum SplitOrientation {
+    /// The panes are stacked one above the other
+    Horizontal,
+    /// The panes are side by side
+    Vertical,
+}
+
+impl Display for SplitOrientation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SplitOrientation::Horizontal => write!(f, "split"),
+            SplitOrientation::Vertical => write!(f, "vsplit"),
+        }
+    }
+}
+
+impl TryFrom<&str> for SplitOrientation {
+    type Error = ();
+
+    fn try_from(command: &str) -> Result<Self, Self::Error> {
+        match command {
+            "split" => Ok(SplitOrientation::Horizontal),
+            "vsplit" => Ok(SplitOrientation::Vertical),
+            _ => Err(()),
+        }
+    }
+}
+
+/// The second pane displayed next to the active tab.
+/// It shows a tab with its own caret, selection and scroll, when it shows the active tab
+/// both panes share the buffer, so the edits and the filters are visible in both.
+#[derive(Debug)]
+pub(crate) struct Split {
+    pub(crate) orientation: SplitOrientation,
+    /// The id of the tab shown in the second pane
+    pub(crate) tab: usize,
+    /// The caret, selection and scroll of the second pane
+    pub(crate) pane: PaneState,
+    /// Scrolling a pane scrolls the other one by the same amount
+    pub(crate) synchronized: bool,
+}
+
+impl Split {
+    pub(crate) fn new(orientation: SplitOrientation, tab: usize, pane: PaneState) -> Self {
+        Self {
+            orientation,
+            tab,
+            pane,
+            synchronized: false,
+        }
+    }
+
+    /// Divide the rect in two halves, the first one is given to the active tab
+    pub(crate) fn pane_rects(&self, rect: Rect) -> (Rect, Rect) {
+        let mut first = rect;
+        let mut second = rect;
+        match self.orientation {
+            SplitOrientation::Horizontal => {
+                let middle = rect.center().y;
+                first.max.y = middle - SEPARATOR_WIDTH / 2.0;
+                second.min.y = middle + SEPARATOR_WIDTH / 2.0;
+            }
+            SplitOrientation::Vertical => {
+                let middle = rect.center().x;
+                first.max.x = middle - SEPARATOR_WIDTH / 2.0;
+                second.min.x = middle + SEPARATOR_WIDTH / 2.0;
+            }
+        }
+        (first, second)
+    }
+}
+
+/// Returns the scroll offsets of both panes once they are rendered.
+/// When the scroll is synchronized, the vertical move of a pane is applied to the other one.
+pub(crate) fn synchronize(before: [Vec2; 2], after: [Vec2; 2]) -> [Vec2; 2] {
+    let [first, second] = after;
+    if first.y != before[0].y {
+        let y = (before[1].y + first.y - before[0].y).max(0.0);
+        [first, Vec2::new(second.x, y)]
+    } else if second.y != before[1].y {
+        let y = (before[0].y + second.y - before[1].y).max(0.0);
+        [Vec2::new(first.x, y), second]
+    } else {
+        after
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eframe::emath::pos2;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("split", Ok(SplitOrientation::Horizontal))]
+    #[case("vsplit", Ok(SplitOrientation::Vertical))]
+    #[case("splitter", Err(()))]
+    fn test_try_from(#[case] command: &str, #[case] expected: Result<SplitOrientation, ()>) {
+        assert_eq!(SplitOrientation::try_from(command), expected);
+    }
+
+    #[rstest]
+    #[case(
+        SplitOrientation::Horizontal,
+        Rect::from_min_max(pos2(0.0, 0.0), pos2(100.0, 48.0)),
+        Rect::from_min_max(pos2(0.0, 52.0), pos2(100.0, 100.0))
+    )]
+    #[case(
+        SplitOrientation::Vertical,
+        Rect::from_min_max(pos2(0.0, 0.0), pos2(48.0, 100.0)),
+        Rect::from_min_max(pos2(52.0, 0.0), pos2(100.0, 100.0))
+    )]
+    fn test_pane_rects(
+        #[case] orientation: SplitOrientation,
+        #[case] first: Rect,
+        #[case] second: Rect,
+    ) {
+        let split = Split::new(orientation, 0, PaneState::default());
+        let rect = Rect::from_min_max(pos2(0.0, 0.0), pos2(100.0, 100.0));
+        assert_eq!(split.pane_rects(rect), (first, second));
+    }
+
+    #[rstest]
+    #[case([Vec2::new(0.0, 100.0), Vec2::new(0.0, 500.0)], [Vec2::new(0.0, 100.0), Vec2::new(0.0, 500.0)])]
+    #[case([Vec2::new(5.0, 150.0), Vec2::new(0.0, 500.0)], [Vec2::new(5.0, 150.0), Vec2::new(0.0, 550.0)])]
+    #[case([Vec2::new(0.0, 100.0), Vec2::new(0.0, 300.0)], [Vec2::new(0.0, 0.0), Vec2::new(0.0, 300.0)])]
+    fn test_synchronize(#[case] after: [Vec2; 2], #[case] expected: [Vec2; 2]) {
+        let before = [Vec2::new(0.0, 100.0), Vec2::new(0.0, 500.0)];
+        assert_eq!(synchronize(before, after), expected);
+    }
+}
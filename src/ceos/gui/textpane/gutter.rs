@@ -45,7 +45,7 @@ impl Widget for Gutter<'_> {
             painter.text(
                 pos,
                 egui::Align2::RIGHT_TOP,
-                format!("{}", line + 1),
+                format!("{}", self.textarea_properties.buffer_line(line) + 1),
                 self.textarea_properties.font_id.clone(),
                 ui.visuals().text_color(),
             );
@@ -1,10 +1,12 @@
 use crate::ceos::command::Command;
 use crate::ceos::command::search::Search;
+use crate::ceos::gui::overview;
+use crate::ceos::gui::overview::{Overview, RULER_WIDTH};
 use crate::ceos::gui::theme::Theme;
 use crate::event::Event;
-use eframe::epaint::Vec2;
+use eframe::epaint::{Rect, Vec2};
 use egui::scroll_area::ScrollBarVisibility::AlwaysHidden;
-use egui::{Response, Ui, Widget};
+use egui::{Response, Sense, Ui, Widget};
 use gutter::Gutter;
 use std::sync::mpsc::Sender;
 use textarea::TextArea;
@@ -23,6 +25,7 @@ pub(crate) struct TextPane<'a> {
     textarea_properties: &'a mut TextAreaProperties,
     current_command: &'a Option<Box<dyn Command + Send + Sync + 'static>>,
     search: &'a Search,
+    overview: &'a Overview,
     theme: &'a Theme,
     sender: &'a Sender<Event>,
 }
@@ -34,6 +37,7 @@ impl<'a> TextPane<'a> {
         theme: &'a Theme,
         sender: &'a Sender<Event>,
         search: &'a Search,
+        overview: &'a Overview,
     ) -> Self {
         Self {
             textarea_properties,
@@ -41,12 +45,40 @@ impl<'a> TextPane<'a> {
             theme,
             sender,
             search,
+            overview,
+        }
+    }
+}
+
+impl TextPane<'_> {
+    /// Show the overview ruler, clicking or dragging in it scrolls to the line at the pointer
+    fn overview_ruler(&mut self, ui: &mut Ui, rect: Rect) {
+        let properties = &mut *self.textarea_properties;
+        let line_count = properties.line_count();
+        let first = (properties.scroll_offset.y / properties.line_height) as usize;
+        let last = first + (properties.viewport_height / properties.line_height) as usize;
+        self.overview.paint(
+            ui,
+            rect,
+            self.theme,
+            self.search.overview_lines(),
+            line_count,
+            (first, last),
+        );
+        let response = ui.interact(rect, ui.id().with("overview_ruler"), Sense::click_and_drag());
+        if (response.clicked() || response.dragged())
+            && let Some(pos) = response.interact_pointer_pos()
+        {
+            let line = overview::line_at(rect, pos.y, line_count);
+            properties.scroll_offset.y = (line as f32 * properties.line_height
+                - properties.viewport_height / 2.0)
+                .max(0.0);
         }
     }
 }
 
 impl Widget for TextPane<'_> {
-    fn ui(self, ui: &mut Ui) -> Response {
+    fn ui(mut self, ui: &mut Ui) -> Response {
         let response = ui.horizontal_top(|ui| {
             // remove the spacing between the gutter and the text area
             ui.spacing_mut().item_spacing = Vec2::ZERO;
@@ -64,10 +96,12 @@ impl Widget for TextPane<'_> {
                 .show_viewport(ui, |ui, rect| {
                     Gutter::new(self.textarea_properties, gutter_rect, rect).ui(ui);
                 });
-            let text_area_rect = ui.available_rect_before_wrap();
+            let mut text_area_rect = ui.available_rect_before_wrap();
+            text_area_rect.max.x -= RULER_WIDTH;
             let scroll_result_textarea = egui::ScrollArea::both()
                 .id_salt("textarea_scroll")
                 .auto_shrink(false)
+                .max_width(text_area_rect.width())
                 .scroll_offset(old_scroll_offset)
                 .show_viewport(ui, |ui, rect| {
                     TextArea::new(
@@ -82,6 +116,7 @@ impl Widget for TextPane<'_> {
                     .ui(ui)
                 });
 
+            self.textarea_properties.viewport_height = scroll_result_textarea.inner_rect.height();
             let mut offset = scroll_result_textarea.state.offset;
             if offset != old_scroll_offset {
                 offset.y = if scroll_result_gutter.state.offset.y != self.textarea_properties.scroll_offset.y {
@@ -95,6 +130,12 @@ impl Widget for TextPane<'_> {
                     self.textarea_properties.scroll_offset = offset;
                 }
             }
+            let viewport = scroll_result_textarea.inner_rect;
+            let ruler_rect = Rect::from_min_max(
+                viewport.right_top(),
+                viewport.right_bottom() + Vec2::new(RULER_WIDTH, 0.0),
+            );
+            self.overview_ruler(ui, ruler_rect);
             scroll_result_textarea.inner
         }).inner;
         ui.allocate_rect(response.rect, egui::Sense::hover());
@@ -0,0 +1,45 @@
+use crate::ceos::gui::textpane::renderer::Renderer;
+use crate::ceos::gui::textpane::textareaproperties::TextAreaProperties;
+use crate::ceos::gui::theme::Theme;
+use eframe::emath::{Pos2, Rect};
+use egui::{Color32, Ui};
+
+/// Paint every occurrence of a text behind the characters
+#[derive(Debug)]
+pub(crate) struct HighlightRenderer {
+    text: String,
+    color: Color32,
+}
+
+impl HighlightRenderer {
+    pub(crate) const fn new(text: String, color: Color32) -> Self {
+        Self { text, color }
+    }
+}
+
+impl Renderer for HighlightRenderer {
+    fn paint_line(
+        &self,
+        ui: &mut Ui,
+        _theme: &Theme,
+        textarea_properties: &TextAreaProperties,
+        line: usize,
+        drawing_pos: Pos2,
+        _has_focus: bool,
+    ) {
+        let painter = ui.painter();
+        let char_width = textarea_properties.char_width;
+        for (start, text) in textarea_properties
+            .line(line)
+            .content()
+            .match_indices(&self.text)
+        {
+            let top_left = Pos2::new(drawing_pos.x + start as f32 * char_width, drawing_pos.y);
+            let bottom_right = Pos2::new(
+                top_left.x + text.len() as f32 * char_width,
+                drawing_pos.y + textarea_properties.line_height,
+            );
+            painter.rect_filled(Rect::from_min_max(top_left, bottom_right), 0.0, self.color);
+        }
+    }
+}
@@ -23,6 +23,11 @@ impl RendererManager {
         self.renderers.insert(index, entry);
     }
 
+    /// Remove all the renderers of a layer
+    pub(crate) fn remove_layer(&mut self, layer: u8) {
+        self.renderers.retain(|e| e.layer != layer);
+    }
+
     pub(crate) fn before_frame(&mut self) {
         self.renderers
             .iter_mut()
@@ -29,7 +29,7 @@ impl Renderer for SelectionRenderer {
             let end_column = if selection.end.line == line {
                 selection.end.column
             } else if selection.end.line > line {
-                textarea_properties.buffer.line_text(line).len()
+                textarea_properties.line_text(line).len()
             } else {
                 return;
             } as f32;
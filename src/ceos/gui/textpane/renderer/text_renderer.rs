@@ -26,7 +26,7 @@ impl Renderer for TextRenderer {
         mut drawing_pos: Pos2,
         _has_focus: bool,
     ) {
-        let text = textarea.buffer.line_text(line);
+        let text = textarea.line_text(line);
         if text.trim().is_empty() {
             return;
         }
@@ -17,7 +17,7 @@ use crate::ceos::gui::textpane::textareaproperties::TextAreaProperties;
 use crate::ceos::gui::theme::Theme;
 use crate::ceos::tools::text_tool::TextTool;
 use crate::event::Event;
-use crate::event::Event::{ClearCommand, NewFont, OpenFile, SetCommand};
+use crate::event::Event::{ClearCommand, NewFont, Redo, SetCommand, Undo};
 
 #[derive(Debug)]
 pub(crate) struct TextArea<'a> {
@@ -106,10 +106,7 @@ impl TextArea<'_> {
     fn handle_double_click(&mut self, rect: Rect, response: &mut Response, pointer_pos: &Pos2) {
         self.handle_click(rect, response, pointer_pos);
         let caret_position = self.textarea_properties.caret_position;
-        let text = self
-            .textarea_properties
-            .buffer
-            .line_text(caret_position.line);
+        let text = self.textarea_properties.line_text(caret_position.line);
         let text_tool = TextTool::new(text);
         let start_col = text_tool.find_word_start(caret_position.column);
 
@@ -218,7 +215,6 @@ impl TextArea<'_> {
         }
         // Ensure the buffer has decompressed the groups needed for the visible range
         self.textarea_properties
-            .buffer
             .prepare_range_for_read(row_range.clone());
         row_range.into_iter().for_each(|line| {
             if self.search.has_results() {
@@ -261,10 +257,9 @@ impl TextArea<'_> {
         let mut new_caret_position = self.build_position(rect, pos);
         new_caret_position.line = new_caret_position
             .line
-            .min(self.textarea_properties.buffer.line_count() - 1);
+            .min(self.textarea_properties.line_count().saturating_sub(1));
         new_caret_position.column = new_caret_position.column.min(
             self.textarea_properties
-                .buffer
                 .line_text(new_caret_position.line)
                 .len(),
         );
@@ -300,8 +295,6 @@ impl TextArea<'_> {
         let old_caret_position = self.textarea_properties.caret_position;
 
         ctx.input(|i| {
-            self.handle_dropped_file(i);
-
             if i.pointer.primary_clicked()
                 && let Some(mut pos) = i.pointer.latest_pos()
             {
@@ -395,7 +388,6 @@ impl TextArea<'_> {
             egui::Key::ArrowRight => {
                 self.textarea_properties.caret_position.column = self
                     .textarea_properties
-                    .buffer
                     .line_length(self.textarea_properties.caret_position.line)
                     .min(self.textarea_properties.caret_position.column + 1);
             }
@@ -411,7 +403,6 @@ impl TextArea<'_> {
             egui::Key::ArrowDown => {
                 self.textarea_properties.caret_position.line = self
                     .textarea_properties
-                    .buffer
                     .line_count()
                     .min(self.textarea_properties.caret_position.line + 1);
                 self.textarea_properties
@@ -429,7 +420,7 @@ impl TextArea<'_> {
             }
             egui::Key::PageDown => {
                 let visible_lines = self.visible_line_count();
-                let line_count = self.textarea_properties.buffer.line_count();
+                let line_count = self.textarea_properties.line_count();
                 self.textarea_properties.caret_position.line =
                     (self.textarea_properties.caret_position.line + visible_lines)
                         .min(line_count.saturating_sub(1));
@@ -439,6 +430,10 @@ impl TextArea<'_> {
             egui::Key::Delete => self.textarea_properties.input_delete(),
             egui::Key::Backspace => self.textarea_properties.input_backspace(),
             egui::Key::Enter => self.textarea_properties.input_enter(),
+            egui::Key::Z if Self::is_control_pressed(i) => {
+                let event = if i.modifiers.shift { Redo } else { Undo };
+                self.sender.send(event).unwrap();
+            }
             _ => {}
         }
     }
@@ -451,14 +446,6 @@ impl TextArea<'_> {
         }
     }
 
-    fn handle_dropped_file(&self, i: &InputState) {
-        if let Some(file) = i.raw.dropped_files.first()
-            && let Some(path) = &file.path
-        {
-            self.sender.send(OpenFile(path.to_owned())).unwrap();
-        }
-    }
-
     fn handle_mouse_wheel(&self, input_state: &InputState, delta: &Vec2) {
         #[cfg(target_os = "macos")]
         if input_state.modifiers.command {
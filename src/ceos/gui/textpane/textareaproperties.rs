@@ -1,5 +1,9 @@
 use crate::ceos::buffer::buffer::Buffer;
+use crate::ceos::buffer::line::Line;
+use crate::ceos::buffer::line_group::LineGroup;
+use crate::ceos::buffer::line_view::LineView;
 use crate::ceos::buffer::text_range::TextRange;
+use crate::ceos::buffer::undo::{BufferSnapshot, Edit, UndoManager};
 use crate::ceos::gui::textpane::gutter;
 use crate::ceos::gui::textpane::interaction_mode::InteractionMode;
 use crate::ceos::gui::textpane::position::Position;
@@ -13,7 +17,7 @@ use crate::ceos::gui::textpane::selection::Selection;
 use crate::event::Event;
 use eframe::emath::{Pos2, Rect, Vec2};
 use eframe::epaint::FontId;
-use log::{debug, info};
+use log::{debug, info, warn};
 use std::cmp;
 use std::ops::Range;
 use std::sync::mpsc::Sender;
@@ -32,6 +36,22 @@ pub(crate) struct TextAreaProperties {
     pub(crate) selection: Option<Selection>,
     pub(crate) interaction_mode: InteractionMode,
     pub(crate) scroll_offset: Vec2,
+    /// The height of the visible part of the text area
+    pub(crate) viewport_height: f32,
+    /// The stack of filtered views, the last one is displayed.
+    /// All the line indexes handled by the text area are indexes in this view.
+    views: Vec<LineView>,
+    undo_manager: UndoManager,
+}
+
+/// The caret, selection and scroll of a pane.
+/// When the text area is shown in several panes, they are swapped while each pane is rendered.
+#[derive(Debug, Default)]
+pub(crate) struct PaneState {
+    caret_position: Position,
+    selection: Option<Selection>,
+    pub(crate) scroll_offset: Vec2,
+    viewport_height: f32,
 }
 
 impl TextAreaProperties {
@@ -51,6 +71,42 @@ impl TextAreaProperties {
             selection: None,
             interaction_mode: InteractionMode::Selection,
             scroll_offset: Vec2::ZERO,
+            viewport_height: 0.0,
+            views: Vec::new(),
+            undo_manager: UndoManager::default(),
+        }
+    }
+
+    /// A pane state starting at the caret and scroll position of this text area
+    pub(crate) fn pane_state(&self) -> PaneState {
+        PaneState {
+            caret_position: self.caret_position,
+            selection: None,
+            scroll_offset: self.scroll_offset,
+            viewport_height: self.viewport_height,
+        }
+    }
+
+    /// Exchange the caret, selection and scroll with the pane ones.
+    /// They are adjusted to the line count as the buffer may have changed since the pane was shown.
+    pub(crate) fn swap_pane(&mut self, pane: &mut PaneState) {
+        std::mem::swap(&mut self.caret_position, &mut pane.caret_position);
+        std::mem::swap(&mut self.selection, &mut pane.selection);
+        std::mem::swap(&mut self.scroll_offset, &mut pane.scroll_offset);
+        std::mem::swap(&mut self.viewport_height, &mut pane.viewport_height);
+        let line_count = self.line_count();
+        if self.caret_position.line >= line_count {
+            self.caret_position = Position {
+                line: line_count.saturating_sub(1),
+                column: 0,
+            };
+        }
+        if self
+            .selection
+            .as_ref()
+            .is_some_and(|selection| selection.end.line >= line_count)
+        {
+            self.selection = None;
         }
     }
 
@@ -74,7 +130,224 @@ impl TextAreaProperties {
             buffer.path,
             buffer.line_count()
         );
-        self.buffer = buffer
+        self.buffer = buffer;
+        self.views.clear();
+        self.undo_manager.clear();
+    }
+
+    /// Give back the buffer and the views after a command execution, the views are still valid
+    /// as long as the command did not change the line count, they are dropped otherwise.
+    /// If the command built a new view, it is pushed on top of the others, otherwise the snapshot
+    /// of the buffer before the command is kept to undo it.
+    pub(crate) fn set_filtered_buffer(
+        &mut self,
+        buffer: Buffer,
+        views: Vec<LineView>,
+        new_view: Option<LineView>,
+        snapshot: Option<BufferSnapshot>,
+    ) {
+        info!(
+            "set filtered buffer: {:?}, line count: {}, views: {}",
+            buffer.path,
+            buffer.line_count(),
+            views.len()
+        );
+        self.buffer = buffer;
+        self.views = views;
+        if let Some(before) = &snapshot
+            && before.line_count() != self.buffer.line_count()
+        {
+            warn!("The command changed the line count, the filtered views are dropped");
+            self.views.clear();
+        }
+        let buffer_line = self.caret_buffer_line();
+        if let Some(new_view) = new_view {
+            self.views.push(new_view);
+            self.undo_manager.record(Edit::PopView);
+        } else if let Some(snapshot) = snapshot {
+            self.undo_manager.record(Edit::Restore(snapshot));
+        }
+        self.restore_caret(buffer_line);
+    }
+
+    /// Spill the compressed lines of the buffer and of the snapshots kept to undo the commands
+    /// while they exceed the threshold
+    pub(crate) fn spill_groups(&mut self, threshold: usize) {
+        let snapshots: Vec<&LineGroup> = self.undo_manager.snapshot_groups().collect();
+        self.buffer.spill_groups(threshold, &snapshots);
+    }
+
+    /// Take the views, they are given back with `set_filtered_buffer`
+    pub(crate) fn take_views(&mut self) -> Vec<LineView> {
+        std::mem::take(&mut self.views)
+    }
+
+    /// Remove the last filtered view. Returns false if there was no view.
+    pub(crate) fn pop_view(&mut self) -> bool {
+        let buffer_line = self.caret_buffer_line();
+        let Some(view) = self.views.pop() else {
+            return false;
+        };
+        self.undo_manager.record(Edit::PushView(view));
+        self.restore_caret(buffer_line);
+        true
+    }
+
+    /// Revert the last edit. Returns false if there was nothing to undo.
+    pub(crate) fn undo(&mut self) -> bool {
+        let Some(edit) = self.undo_manager.pop_undo() else {
+            return false;
+        };
+        info!("undo {edit:?}");
+        if let Some(inverse) = self.apply_edit(edit) {
+            self.undo_manager.push_redo(inverse);
+        }
+        true
+    }
+
+    /// Apply again the last undone edit. Returns false if there was nothing to redo.
+    pub(crate) fn redo(&mut self) -> bool {
+        let Some(edit) = self.undo_manager.pop_redo() else {
+            return false;
+        };
+        info!("redo {edit:?}");
+        if let Some(inverse) = self.apply_edit(edit) {
+            self.undo_manager.push_undo_after_redo(inverse);
+        }
+        true
+    }
+
+    /// Apply an edit and returns its inverse
+    fn apply_edit(&mut self, edit: Edit) -> Option<Edit> {
+        self.selection = None;
+        match edit {
+            Edit::Insert { line, column, text } => {
+                let (end_line, end_column) = self.buffer.insert_text(line, column, &text);
+                self.caret_position = Position {
+                    line: end_line,
+                    column: end_column,
+                };
+                Some(Edit::Delete { line, column, text })
+            }
+            Edit::Delete { line, column, text } => {
+                self.buffer
+                    .delete_range(Edit::text_range(line, column, &text));
+                self.caret_position = Position { line, column };
+                Some(Edit::Insert { line, column, text })
+            }
+            Edit::Restore(snapshot) => {
+                let buffer_line = self.caret_buffer_line();
+                let snapshot = self.buffer.restore(snapshot);
+                self.restore_caret(buffer_line);
+                Some(Edit::Restore(snapshot))
+            }
+            Edit::PushView(view) => {
+                let buffer_line = self.caret_buffer_line();
+                self.views.push(view);
+                self.restore_caret(buffer_line);
+                Some(Edit::PopView)
+            }
+            Edit::PopView => {
+                let buffer_line = self.caret_buffer_line();
+                let view = self.views.pop()?;
+                self.restore_caret(buffer_line);
+                Some(Edit::PushView(view))
+            }
+        }
+    }
+
+    /// Append the lines read from a followed file.
+    /// The text area keeps showing the end of the buffer if it was already scrolled to the bottom.
+    pub(crate) fn append_lines(&mut self, lines: Vec<String>) {
+        let at_bottom =
+            self.scroll_offset.y + self.viewport_height + self.line_height >= self.text_height();
+        self.buffer.append_lines(lines);
+        if at_bottom {
+            self.scroll_offset.y = (self.text_height() - self.viewport_height).max(0.0);
+        }
+    }
+
+    /// Move the caret at the beginning of a buffer line, or the next visible one, and scroll to it
+    pub(crate) fn restore_caret(&mut self, buffer_line: usize) {
+        self.selection = None;
+        self.caret_position.line = self
+            .view_line(buffer_line)
+            .min(self.line_count().saturating_sub(1));
+        self.caret_position.column = 0;
+        self.set_first_line(self.caret_position.line);
+    }
+
+    fn caret_buffer_line(&self) -> usize {
+        match self.line_count() {
+            0 => 0,
+            line_count => self.buffer_line(self.caret_position.line.min(line_count - 1)),
+        }
+    }
+
+    pub(crate) fn view(&self) -> Option<&LineView> {
+        self.views.last()
+    }
+
+    pub(crate) fn is_filtered(&self) -> bool {
+        !self.views.is_empty()
+    }
+
+    /// Returns the number of visible lines
+    pub(crate) fn line_count(&self) -> usize {
+        match self.view() {
+            Some(view) => view.len(),
+            None => self.buffer.line_count(),
+        }
+    }
+
+    /// Returns the buffer line displayed at the given line
+    pub(crate) fn buffer_line(&self, line: usize) -> usize {
+        match self.view() {
+            Some(view) => view.buffer_line(line),
+            None => line,
+        }
+    }
+
+    /// Returns the line displaying the given buffer line, or the next visible line if it is
+    /// filtered out
+    pub(crate) fn view_line(&self, buffer_line: usize) -> usize {
+        match self.view() {
+            Some(view) => view.view_line(buffer_line),
+            None => buffer_line,
+        }
+    }
+
+    pub(crate) fn line(&self, line: usize) -> &Line {
+        &self.buffer[self.buffer_line(line)]
+    }
+
+    pub(crate) fn line_text(&self, line: usize) -> &str {
+        self.buffer.line_text(self.buffer_line(line))
+    }
+
+    pub(crate) fn line_length(&self, line: usize) -> usize {
+        self.buffer.line_length(self.buffer_line(line))
+    }
+
+    /// Decompress the buffer groups needed to display the given lines
+    pub(crate) fn prepare_range_for_read(&mut self, range: Range<usize>) {
+        match self.views.last() {
+            Some(view) => {
+                let end = range.end.min(view.len());
+                let start = range.start.min(end);
+                self.buffer.prepare_lines_for_read(&view.lines()[start..end]);
+            }
+            None => self.buffer.prepare_range_for_read(range),
+        }
+    }
+
+    /// Filtered views cannot be edited as they do not map the hidden lines
+    fn is_read_only(&self) -> bool {
+        if self.is_filtered() {
+            debug!("the filtered view is read only");
+            return true;
+        }
+        false
     }
 
     /// ```
@@ -90,7 +363,7 @@ impl TextAreaProperties {
     /// ```
     pub(crate) fn set_first_line(&mut self, line: usize) {
         info!("set first line: {line}");
-        let line = line.min(self.buffer.line_count());
+        let line = line.min(self.line_count());
         self.scroll_offset.y = self.line_height * (line.saturating_sub(1) as f32);
     }
 
@@ -126,39 +399,59 @@ impl TextAreaProperties {
 
     #[inline]
     pub(crate) fn text_height(&self) -> f32 {
-        self.line_height * self.buffer.line_count() as f32
+        self.line_height * self.line_count() as f32
     }
 
     pub(crate) fn get_row_range_for_rect(&self, rect: Rect) -> Range<usize> {
         let min_row = (rect.top() / self.line_height) as usize;
         let max_row = cmp::min(
             1 + (rect.bottom() / self.line_height) as usize,
-            self.buffer.line_count(),
+            self.line_count(),
         );
         min_row..max_row
     }
 
     pub(crate) fn handle_text(&mut self, text: &String) {
+        if self.is_read_only() {
+            return;
+        }
         self.delete_selection();
-        for ch in text.chars() {
-            if ch == '\r' || ch == '\x08' || ch == '\x7f' {
-                continue;
-            }
-            self.buffer
-                .insert_char(self.caret_position.line, self.caret_position.column, ch);
-            if ch == '\n' {
-                self.caret_position.line += 1;
-                self.caret_position.column = 0;
-            } else {
-                self.caret_position.column += 1;
-            }
+        let text: String = text
+            .chars()
+            .filter(|ch| !matches!(ch, '\r' | '\x08' | '\x7f'))
+            .collect();
+        self.insert_text(text);
+    }
+
+    /// Insert a text at the caret position and move the caret after it
+    fn insert_text(&mut self, text: String) {
+        if text.is_empty() {
+            return;
         }
+        let Position { line, column } = self.caret_position;
+        let (end_line, end_column) = self.buffer.insert_text(line, column, &text);
+        self.caret_position = Position {
+            line: end_line,
+            column: end_column,
+        };
+        self.undo_manager.record(Edit::Delete { line, column, text });
+    }
+
+    /// Delete the text in the range, keeping it to undo the deletion
+    fn delete_range(&mut self, range: TextRange) {
+        let text = self.buffer.text(&range);
+        let (line, column) = range.start();
+        self.buffer.delete_range(range);
+        self.undo_manager.record(Edit::Insert { line, column, text });
     }
 
     /// Delete the selection content if there is one.
     pub(crate) fn delete_selection(&mut self) {
+        if self.is_read_only() {
+            return;
+        }
         if let Some(selection) = self.selection.take() {
-            self.buffer.delete_range(TextRange::from(&selection));
+            self.delete_range(TextRange::from(&selection));
             self.caret_position = selection.start;
         }
     }
@@ -171,7 +464,7 @@ impl TextAreaProperties {
             let end_line = selection.end.line;
 
             for line_idx in start_line..=end_line {
-                let line_text = self.buffer.line_text(line_idx);
+                let line_text = self.line_text(line_idx);
                 let start_col = if line_idx == start_line { selection.start.column } else { 0 };
                 let end_col = if line_idx == end_line { selection.end.column } else { line_text.len() };
 
@@ -207,27 +500,28 @@ impl TextAreaProperties {
 
     pub(crate) fn go_to_end_of_line(&mut self) {
         self.selection = None;
-        let current_line_length = self.buffer.line_text(self.caret_position.line);
+        let current_line_length = self.line_text(self.caret_position.line);
         self.caret_position.column = current_line_length.len().saturating_sub(1);
     }
 
     pub(crate) fn go_to_end_of_buffer(&mut self) {
         self.selection = None;
-        let current_line_length = self.buffer.line_text(self.buffer.line_count() - 1);
-        self.caret_position.line = self.buffer.line_count().saturating_sub(1);
-        self.caret_position.column = current_line_length.len().saturating_sub(1);
+        let current_line_length = self.line_text(self.line_count() - 1).len();
+        self.caret_position.line = self.line_count().saturating_sub(1);
+        self.caret_position.column = current_line_length.saturating_sub(1);
     }
 
     pub(crate) fn input_enter(&mut self) {
-        self.buffer.insert_newline(
-            self.caret_position.line,
-            self.caret_position.column,
-        );
-        self.caret_position.line += 1;
-        self.caret_position.column = 0;
+        if self.is_read_only() {
+            return;
+        }
+        self.insert_text("\n".to_string());
     }
 
     pub(crate) fn input_backspace(&mut self) {
+        if self.is_read_only() {
+            return;
+        }
         if self.selection.is_some() {
             self.delete_selection();
             return;
@@ -240,19 +534,22 @@ impl TextAreaProperties {
                 self.caret_position.line,
                 self.caret_position.column,
             );
-            self.buffer.delete_range(range);
+            self.delete_range(range);
             self.caret_position.column -= 1;
         } else if self.caret_position.line > 0 {
             let prev_line_idx = self.caret_position.line - 1;
             let prev_line_len = self.buffer.line_text(prev_line_idx).len();
             let range = TextRange::new(prev_line_idx, prev_line_len, self.caret_position.line, 0);
-            self.buffer.delete_range(range);
+            self.delete_range(range);
             self.caret_position.line = prev_line_idx;
             self.caret_position.column = prev_line_len;
         }
     }
 
     pub(crate) fn input_delete(&mut self) {
+        if self.is_read_only() {
+            return;
+        }
         if self.selection.is_some() {
             self.delete_selection();
             return;
@@ -267,7 +564,7 @@ impl TextAreaProperties {
                 self.caret_position.line,
                 self.caret_position.column + 1,
             );
-            self.buffer.delete_range(range);
+            self.delete_range(range);
         } else if self.caret_position.line + 1 < line_count {
             let range = TextRange::new(
                 self.caret_position.line,
@@ -275,7 +572,145 @@ impl TextAreaProperties {
                 self.caret_position.line + 1,
                 0,
             );
-            self.buffer.delete_range(range);
+            self.delete_range(range);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text_area(text: &str) -> TextAreaProperties {
+        let (sender, _) = std::sync::mpsc::channel();
+        let mut textarea_properties = TextAreaProperties::new(sender.clone());
+        textarea_properties.set_buffer(Buffer::new_from_string(sender, text, 2));
+        textarea_properties
+    }
+
+    fn content(textarea_properties: &mut TextAreaProperties) -> Vec<String> {
+        textarea_properties.buffer.prepare_range_for_read(..);
+        (0..textarea_properties.buffer.line_count())
+            .map(|line| textarea_properties.buffer.line_text(line).to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_undo_redo_typing() {
+        let mut textarea_properties = text_area("abc\ndef\nghi");
+        textarea_properties.caret_position = Position { line: 1, column: 1 };
+        textarea_properties.handle_text(&"xy".to_string());
+        textarea_properties.handle_text(&"z".to_string());
+        textarea_properties.input_enter();
+        textarea_properties.input_backspace();
+        textarea_properties.input_backspace();
+        assert_eq!(vec!["abc", "dxyef", "ghi"], content(&mut textarea_properties));
+
+        assert!(textarea_properties.undo());
+        assert_eq!(vec!["abc", "dxyzef", "ghi"], content(&mut textarea_properties));
+        assert!(textarea_properties.undo());
+        assert_eq!(vec!["abc", "dxyz", "ef", "ghi"], content(&mut textarea_properties));
+        assert!(textarea_properties.undo());
+        assert!(textarea_properties.undo());
+        assert_eq!(vec!["abc", "def", "ghi"], content(&mut textarea_properties));
+        assert_eq!(Position { line: 1, column: 1 }, textarea_properties.caret_position);
+        assert!(!textarea_properties.undo());
+
+        assert!(textarea_properties.redo());
+        assert_eq!(vec!["abc", "dxyzef", "ghi"], content(&mut textarea_properties));
+        assert_eq!(Position { line: 1, column: 4 }, textarea_properties.caret_position);
+    }
+
+    #[test]
+    fn test_undo_command() {
+        let mut textarea_properties = text_area("a1\nb2\na3");
+        let mut buffer = std::mem::replace(
+            &mut textarea_properties.buffer,
+            Buffer::new_empty_buffer(std::sync::mpsc::channel().0),
+        );
+        let snapshot = buffer.snapshot();
+        buffer.drain_line_mut(..1);
+        textarea_properties.set_filtered_buffer(buffer, Vec::new(), None, Some(snapshot));
+        assert_eq!(vec!["b2", "a3"], content(&mut textarea_properties));
+
+        assert!(textarea_properties.undo());
+        assert_eq!(vec!["a1", "b2", "a3"], content(&mut textarea_properties));
+        assert!(textarea_properties.redo());
+        assert_eq!(vec!["b2", "a3"], content(&mut textarea_properties));
+    }
+
+    #[test]
+    fn test_undo_snapshots_spilled() {
+        let mut textarea_properties = text_area("a1\nb2\na3");
+        let mut buffer = std::mem::replace(
+            &mut textarea_properties.buffer,
+            Buffer::new_empty_buffer(std::sync::mpsc::channel().0),
+        );
+        let snapshot = buffer.snapshot();
+        buffer.drain_line_mut(..1);
+        textarea_properties.set_filtered_buffer(buffer, Vec::new(), None, Some(snapshot));
+        textarea_properties.buffer.compress_all_groups();
+        textarea_properties.spill_groups(0);
+        assert_eq!(0, textarea_properties.buffer.compressed_size());
+        let undo_manager = &textarea_properties.undo_manager;
+        assert!(undo_manager.snapshot_groups().all(|g| g.compressed_size() == 0));
+
+        assert!(textarea_properties.undo());
+        assert_eq!(vec!["a1", "b2", "a3"], content(&mut textarea_properties));
+    }
+
+    #[test]
+    fn test_views_dropped_when_line_count_changes() {
+        let mut textarea_properties = text_area("a1\nb2\na3");
+        let mut buffer = std::mem::replace(
+            &mut textarea_properties.buffer,
+            Buffer::new_empty_buffer(std::sync::mpsc::channel().0),
+        );
+        let snapshot = buffer.snapshot();
+        buffer.drain_line_mut(..1);
+        let views = vec![LineView::new(vec![0, 2])];
+        textarea_properties.set_filtered_buffer(buffer, views, None, Some(snapshot));
+        assert!(!textarea_properties.is_filtered());
+        assert_eq!(vec!["b2", "a3"], content(&mut textarea_properties));
+    }
+
+    #[test]
+    fn test_undo_view() {
+        let mut textarea_properties = text_area("a1\nb2\na3");
+        let buffer = std::mem::replace(
+            &mut textarea_properties.buffer,
+            Buffer::new_empty_buffer(std::sync::mpsc::channel().0),
+        );
+        textarea_properties.set_filtered_buffer(buffer, Vec::new(), Some(LineView::new(vec![0, 2])), None);
+        assert_eq!(2, textarea_properties.line_count());
+        assert!(textarea_properties.undo());
+        assert!(!textarea_properties.is_filtered());
+        assert!(textarea_properties.redo());
+        assert_eq!(2, textarea_properties.line_count());
+        assert!(textarea_properties.pop_view());
+        assert!(textarea_properties.undo());
+        assert_eq!(2, textarea_properties.line_count());
+    }
+
+    #[test]
+    fn test_swap_pane() {
+        let mut textarea_properties = text_area("a1\nb2\na3\nb4");
+        textarea_properties.caret_position = Position { line: 3, column: 1 };
+        let mut pane = textarea_properties.pane_state();
+        pane.caret_position = Position { line: 1, column: 0 };
+        textarea_properties.swap_pane(&mut pane);
+        assert_eq!(Position { line: 1, column: 0 }, textarea_properties.caret_position);
+        textarea_properties.swap_pane(&mut pane);
+        assert_eq!(Position { line: 3, column: 1 }, textarea_properties.caret_position);
+        // the buffer is filtered in the other pane
+        let buffer = std::mem::replace(
+            &mut textarea_properties.buffer,
+            Buffer::new_empty_buffer(std::sync::mpsc::channel().0),
+        );
+        let view = Some(LineView::new(vec![0, 2]));
+        textarea_properties.set_filtered_buffer(buffer, Vec::new(), view, None);
+        textarea_properties.swap_pane(&mut pane);
+        textarea_properties.swap_pane(&mut pane);
+        assert_eq!(Position { line: 1, column: 0 }, textarea_properties.caret_position);
+    }
+}
@@ -1,101 +1,175 @@
-use crate::ceos::command::Command;
+use crate::ceos::buffer::archive::{Archive, ArchiveEntry};
+use crate::ceos::buffer::budget;
+use crate::ceos::buffer::follower::FOLLOW_INTERVAL;
+use crate::ceos::buffer::line_group::LineGroup;
+use crate::ceos::buffer::spill;
+use crate::ceos::tools::cancellation::CancellationToken;
+use crate::ceos::tools::encoding::Encoding;
+use crate::ceos::tools::timestamp::TimestampFormat;
 use crate::ceos::command::direct::goto::Goto;
-use crate::ceos::command::search::Search;
+use crate::ceos::gui::archivepicker::ArchivePicker;
 use crate::ceos::gui::frame_history::FrameHistory;
 use crate::ceos::gui::helppanel::HelpPanel;
-use crate::ceos::gui::searchpanel::SearchPanel;
+use crate::ceos::gui::highlight::{HighlightCommand, HighlightManager};
+use crate::ceos::gui::split;
+use crate::ceos::gui::split::{Split, SplitOrientation};
 use crate::ceos::gui::textpane::TextPane;
 use crate::ceos::gui::textpane::interaction_mode::InteractionMode;
 use crate::ceos::options::Options;
-use crate::ceos::progress_manager::{BUFFER_LOADING, BUFFER_SAVING, ProgressManager};
+use crate::ceos::progress_manager::{
+    BUFFER_SAVING, ProgressManager, loading_id, new_loading, operation_label,
+};
+use crate::ceos::tab::Tab;
+use crate::cli;
+use crate::cli::{Arguments, STDIN};
 use crate::event::Event;
 use crate::event::Event::{BufferClosed, BufferLoaded, GotoLine};
 use Event::NewFont;
 use buffer::buffer::Buffer;
+use buffer::merge;
+use buffer::stream;
 use eframe::Frame;
-use eframe::emath::Align;
-use egui::{Context, Key, Layout, ProgressBar, Ui, Visuals, Widget};
-use gui::textpane::textareaproperties::TextAreaProperties;
+use eframe::emath::Rect;
+use egui::{
+    Context, Key, Modifiers, ProgressBar, Response, Ui, UiBuilder, ViewportCommand,
+    Visuals, Widget,
+};
 use gui::theme::Theme;
 use humansize::{DECIMAL, format_size_i};
-use log::{debug, error, info, warn};
-use std::fs::File;
-use std::io::{LineWriter, Write};
+use log::{debug, info, warn};
+use std::collections::VecDeque;
 use std::path::PathBuf;
 use std::sync::mpsc::{Receiver, Sender, channel};
 use std::thread;
 
+pub(crate) mod batch;
 pub(crate) mod buffer;
 pub(crate) mod command;
 pub(crate) mod gui;
 mod options;
 mod progress_manager;
 mod syntax;
-mod tools;
+mod tab;
+pub(crate) mod tools;
 
 #[derive(Debug)]
 pub(crate) struct Ceos {
-    textarea_properties: TextAreaProperties,
+    /// The open buffers, there is always at least one tab
+    tabs: Vec<Tab>,
+    /// The index of the displayed tab
+    active_tab: usize,
+    /// The second pane, if the text area is split
+    split: Option<Split>,
+    /// The id given to the next tab
+    next_tab_id: usize,
     sender: Sender<Event>,
     receiver: Receiver<Event>,
-    command_buffer: String,
-    current_command: Option<Box<dyn Command + Send + Sync + 'static>>,
     frame_history: FrameHistory,
-    search_panel: SearchPanel,
     theme: Theme,
     initialized: bool,
     progress_manager: ProgressManager,
     show_options: bool,
     show_help: bool,
     options: Options,
+    /// The highlighted texts painted in all the tabs
+    highlight_manager: HighlightManager,
+    show_highlights: bool,
+    /// The line to go to once the file given on the command line is loaded
+    startup_line: Option<usize>,
+    /// The commands given on the command line, executed once the file is loaded
+    startup_commands: VecDeque<String>,
+    /// The entries of the archive being opened
+    archive_picker: Option<ArchivePicker>,
+    /// The title of the window, the name of the buffer
+    title: String,
+    /// The memory used by the decompressed lines of all the tabs
+    memory_used: usize,
+    /// False when the options must not be written to ceos.toml
+    save_options: bool,
 }
 
 impl Default for Ceos {
     fn default() -> Self {
+        let options = Options::load();
+        spill::clean_scratch_dir();
+        spill::set_threshold(options.spill_threshold_bytes());
+        Self::new(options, true)
+    }
+}
+
+impl Ceos {
+    fn new(options: Options, save_options: bool) -> Self {
         let (user_input_sender, user_input_receiver) = channel::<Event>();
-        let search_panel = SearchPanel::new(user_input_sender.clone());
+        let highlight_manager = HighlightManager::new(options.highlights.clone());
+        let mut tab = Tab::new(0, user_input_sender.clone());
+        highlight_manager.register(&mut tab.textarea_properties.renderer_manager);
         Self {
-            sender: user_input_sender.clone(),
+            tabs: vec![tab],
+            active_tab: 0,
+            split: None,
+            next_tab_id: 1,
+            sender: user_input_sender,
             receiver: user_input_receiver,
-            textarea_properties: TextAreaProperties::new(user_input_sender),
-            command_buffer: String::new(),
-            current_command: None,
             frame_history: Default::default(),
-            search_panel,
             theme: Theme::default(),
             initialized: false,
             progress_manager: Default::default(),
             show_options: false,
             show_help: false,
-            options: Options::load(),
+            options,
+            highlight_manager,
+            show_highlights: false,
+            startup_line: None,
+            startup_commands: VecDeque::new(),
+            archive_picker: None,
+            title: String::new(),
+            memory_used: 0,
+            save_options,
+        }
+    }
+
+    /// Create an instance that neither writes ceos.toml nor cleans the scratch directory
+    #[cfg(test)]
+    fn with_options(options: Options) -> Self {
+        Self::new(options, false)
+    }
+
+    fn save_options(&self) {
+        if self.save_options
+            && let Err(e) = self.options.save()
+        {
+            warn!("Impossible d'enregistrer ceos.toml: {e}");
         }
     }
-}
 
-impl Ceos {
     pub(crate) fn process_event(&mut self, ctx: &Context, event: Event) {
         match event {
             Event::ClearCommand => {
-                self.clear_command();
+                self.tab_mut().clear_command();
             }
             Event::ShowHelp => {
                 self.show_help = true;
             }
             Event::SetCommand(command) => {
-                self.command_buffer = command;
-                self.try_filter_command();
+                let tab = self.tab_mut();
+                tab.command_buffer = command;
+                tab.try_filter_command();
             }
-            Event::OpenFile(path) => self.open_file(path),
-            Event::BufferLoadingStarted(path, size) => {
-                self.progress_manager
-                    .add(BUFFER_LOADING.into(), format!("Loading {path:?}"), size)
+            Event::OpenFile(path) => self.open_files(vec![path], None),
+            Event::BufferLoadingStarted(loading, path, size, cancellation) => {
+                self.progress_manager.add(
+                    loading_id(loading),
+                    format!("Loading {path:?}"),
+                    size,
+                    Some(cancellation),
+                )
             }
-            Event::BufferLoading(_, current, _) => {
-                self.progress_manager.update(BUFFER_LOADING, current)
+            Event::BufferLoading(loading, current, _) => {
+                self.progress_manager.update(&loading_id(loading), current)
             }
             Event::BufferSavingStarted(path, size) => {
                 self.progress_manager
-                    .add(BUFFER_SAVING.into(), format!("Saving {path:?}"), size)
+                    .add(BUFFER_SAVING.into(), format!("Saving {path:?}"), size, None)
             }
             Event::BufferSaving(_, current, _) => {
                 self.progress_manager.update(BUFFER_SAVING, current)
@@ -103,86 +177,387 @@ impl Ceos {
             Event::BufferSaved(path) => {
                 self.progress_manager.remove(BUFFER_SAVING);
                 // Marquer le buffer comme non-dirty si c'est le même fichier
-                if let Some(current_path) = &self.textarea_properties.buffer.path
-                    && current_path == &path
-                {
-                    self.textarea_properties.buffer.dirty = false;
+                for tab in &mut self.tabs {
+                    if let Some(current_path) = &tab.textarea_properties.buffer.path
+                        && current_path == &path
+                    {
+                        tab.textarea_properties.buffer.dirty = false;
+                    }
                 }
             }
             Event::BufferSaveFailed(_) => {
                 // Retirer la progression en cas d'échec
                 self.progress_manager.remove(BUFFER_SAVING);
             }
+            Event::BufferGroupsLoaded(loading, path, groups) => {
+                let index = match self.loading_tab(loading) {
+                    Some(index) => index,
+                    None => {
+                        self.active_tab = self.new_tab();
+                        self.tab_mut().start_loading(loading, &path);
+                        self.active_tab
+                    }
+                };
+                self.tabs[index]
+                    .textarea_properties
+                    .buffer
+                    .append_groups(groups);
+            }
+            Event::BufferLoadingFailed(loading) => {
+                if let Some(index) = self.loading_tab(loading) {
+                    // the partial content is dropped
+                    self.close_tab(index);
+                }
+            }
             BufferLoaded(buffer) => {
-                self.search_panel.search.reset();
-                self.progress_manager.remove(BUFFER_LOADING);
-                self.textarea_properties.set_buffer(buffer);
+                self.active_tab = match self.loading_tab(buffer.loading) {
+                    Some(index) => index,
+                    None => self.new_tab(),
+                };
+                self.tab_mut().set_buffer(buffer);
+                self.update_overview(self.active_tab);
+                self.run_startup_commands();
+            }
+            Event::ArchiveOpened(path, archive, entries) => {
+                self.archive_picker = Some(ArchivePicker::new(path, archive, entries));
+            }
+            Event::BufferAppended(path, lines) => {
+                match self.tabs.iter_mut().find(|tab| {
+                    tab.follower
+                        .as_ref()
+                        .is_some_and(|follower| follower.path() == path)
+                }) {
+                    Some(tab) => tab.append_lines(lines),
+                    None => debug!("Ignore lines appended to {path:?}"),
+                }
+            }
+            Event::BufferStreaming(lines, read) => {
+                if let Some(tab) = self.tabs.iter_mut().find(|tab| tab.streamed.is_some()) {
+                    tab.streamed = Some(read);
+                    tab.append_lines(lines);
+                }
+            }
+            Event::BufferStreamed => {
+                for index in 0..self.tabs.len() {
+                    if self.tabs[index].streamed.take().is_some() {
+                        self.update_overview(index);
+                        if !self.startup_commands.is_empty() || self.startup_line.is_some() {
+                            // the command line applies to the standard input once it is read
+                            self.active_tab = index;
+                            self.run_startup_commands();
+                        }
+                    }
+                }
+            }
+            Event::ToggleFollow => self.toggle_follow(),
+            Event::MergeOpen(format) => self.browse_merge_files(format),
+            Event::CommandExecuted(id, buffer, views, new_view, snapshot) => {
+                match self.tab_index(id) {
+                    Some(index) => {
+                        self.tabs[index].command_executed(buffer, views, new_view, snapshot);
+                        self.update_overview(index);
+                        self.run_startup_commands();
+                    }
+                    None => debug!("The tab {id} was closed while the command was running"),
+                }
+            }
+            Event::PopView => {
+                let tab = self.tab_mut();
+                if tab.textarea_properties.pop_view() {
+                    tab.reset_search();
+                    self.update_overview(self.active_tab);
+                }
+            }
+            Event::Undo => {
+                let tab = self.tab_mut();
+                if tab.textarea_properties.undo() {
+                    tab.reset_search();
+                    self.update_overview(self.active_tab);
+                }
             }
-            BufferClosed => self
-                .textarea_properties
-                .set_buffer(Buffer::new_empty_buffer(self.sender.clone())),
-            GotoLine(goto) => goto.execute(&mut self.textarea_properties),
-            NewFont(font_id) => self.textarea_properties.set_font_id(font_id),
-            Event::OperationStarted(label, length) => {
-                self.progress_manager.add(label.clone(), label, length)
+            Event::Redo => {
+                let tab = self.tab_mut();
+                if tab.textarea_properties.redo() {
+                    tab.reset_search();
+                    self.update_overview(self.active_tab);
+                }
+            }
+            BufferClosed => self.close_tab(self.active_tab),
+            GotoLine(goto) => goto.execute(&mut self.tab_mut().textarea_properties),
+            Event::Split(orientation) => self.split(orientation),
+            Event::Unsplit => self.split = None,
+            Event::ToggleScrollSync => {
+                if let Some(split) = &mut self.split {
+                    split.synchronized = !split.synchronized;
+                }
+            }
+            Event::Highlight(command) => self.highlight(command),
+            Event::OverviewComputed(id, generation, marks) => {
+                if let Some(index) = self.tab_index(id) {
+                    self.tabs[index].overview.set_marks(generation, marks);
+                }
+            }
+            Event::SearchCompleted(id, search) => {
+                if let Some(index) = self.tab_index(id) {
+                    self.tabs[index].search_completed(search);
+                }
             }
-            Event::OperationProgress(label, value) => self.progress_manager.update(&label, value),
-            Event::OperationIncrement(label, amount) => {
-                self.progress_manager.increment(&label, amount)
+            NewFont(font_id) => self
+                .tabs
+                .iter_mut()
+                .for_each(|tab| tab.textarea_properties.set_font_id(font_id.clone())),
+            Event::OperationStarted(id, length, cancellation) => {
+                let label = operation_label(&id).to_owned();
+                self.progress_manager.add(id, label, length, cancellation)
             }
-            Event::OperationFinished(label) => self.progress_manager.remove(&label),
+            Event::OperationProgress(id, value) => self.progress_manager.update(&id, value),
+            Event::OperationIncrement(id, amount) => self.progress_manager.increment(&id, amount),
+            Event::OperationFinished(id) => self.progress_manager.remove(&id),
         }
     }
 
-    fn clear_command(&mut self) {
-        self.command_buffer = String::new();
-        self.current_command = None;
+    fn tab(&self) -> &Tab {
+        &self.tabs[self.active_tab]
     }
 
-    pub(crate) fn try_search(&mut self) -> bool {
-        if let Ok(mut search) = Search::try_from(self.command_buffer.as_str()) {
-            search.init(&self.textarea_properties.buffer);
-            self.search_panel.search = search;
-            return true;
+    fn tab_mut(&mut self) -> &mut Tab {
+        &mut self.tabs[self.active_tab]
+    }
+
+    fn tab_index(&self, id: usize) -> Option<usize> {
+        self.tabs.iter().position(|tab| tab.id == id)
+    }
+
+    /// Returns the index of the tab receiving a new buffer, the active tab is reused if it is blank
+    fn new_tab(&mut self) -> usize {
+        if self.tab().is_blank() {
+            return self.active_tab;
         }
-        false
+        self.push_tab();
+        self.tabs.len() - 1
     }
 
-    pub(crate) fn try_filter_command(&mut self) {
-        let command_str = self.command_buffer.as_str();
-        if let Ok(command) =
-            crate::ceos::command::filter::linefilter::LineFilter::try_from(command_str)
-        {
-            self.current_command = Some(Box::new(command));
-        } else if let Ok(command) =
-            crate::ceos::command::filter::columnfilter::ColumnFilter::try_from(command_str)
+    /// Add a blank tab using the font of the active one
+    fn push_tab(&mut self) {
+        let mut tab = Tab::new(self.next_tab_id, self.sender.clone());
+        self.highlight_manager
+            .register(&mut tab.textarea_properties.renderer_manager);
+        if let Some(active) = self.tabs.get(self.active_tab) {
+            let font_id = active.textarea_properties.font_id.clone();
+            tab.textarea_properties.set_font_id(font_id);
+        }
+        self.tabs.push(tab);
+        self.next_tab_id += 1;
+    }
+
+    /// Returns the index of the tab showing the lines of a file while it is loaded
+    fn loading_tab(&self, loading: usize) -> Option<usize> {
+        self.tabs
+            .iter()
+            .position(|tab| tab.loading == Some(loading))
+    }
+
+    /// Close a tab, a blank tab replaces the last one.
+    /// The loading of its file is cancelled if it is not loaded yet.
+    fn close_tab(&mut self, index: usize) {
+        info!("Close tab {}", self.tabs[index].title());
+        if let Some(loading) = self.tabs[index].loading
+            && let Some(progress) = self.progress_manager.progress.get(&loading_id(loading))
         {
-            self.current_command = Some(Box::new(command));
-        } else if let Ok(command) =
-            crate::ceos::command::filter::linedrop::LineDrop::try_from(command_str)
+            progress.cancel();
+        }
+        if self.tabs.len() == 1 {
+            self.push_tab();
+        }
+        let closed = self.tabs.remove(index);
+        if self.active_tab > index || self.active_tab == self.tabs.len() {
+            self.active_tab -= 1;
+        }
+        if let Some(split) = &mut self.split
+            && split.tab == closed.id
         {
-            self.current_command = Some(Box::new(command));
+            // the second pane falls back to the active tab
+            split.tab = self.tabs[self.active_tab].id;
+            split.pane = self.tabs[self.active_tab].textarea_properties.pane_state();
+        }
+    }
+
+    /// Show the active tab in a second pane, or change the orientation of the split
+    fn split(&mut self, orientation: SplitOrientation) {
+        info!("{orientation}");
+        match &mut self.split {
+            Some(split) => split.orientation = orientation,
+            None => {
+                let tab = self.tab();
+                let pane = tab.textarea_properties.pane_state();
+                self.split = Some(Split::new(orientation, tab.id, pane));
+            }
+        }
+    }
+
+    fn highlight(&mut self, command: HighlightCommand) {
+        info!("Highlight {command:?}");
+        match command {
+            HighlightCommand::Add(text, color) => {
+                self.highlight_manager.add(text, color);
+                self.highlights_changed();
+            }
+            HighlightCommand::Remove(text) => {
+                if self.highlight_manager.remove(&text) {
+                    self.highlights_changed();
+                } else {
+                    warn!("No highlight {text}");
+                }
+            }
+            HighlightCommand::List => self.show_highlights = true,
+        }
+    }
+
+    /// Save the highlights and paint them in all the tabs.
+    /// The overview rulers are updated again only if the highlighted texts changed, not their
+    /// colour.
+    fn highlights_changed(&mut self) {
+        let highlights = self.highlight_manager.highlights();
+        let texts_changed = !self
+            .options
+            .highlights
+            .iter()
+            .map(|h| &h.text)
+            .eq(highlights.iter().map(|h| &h.text));
+        self.options.highlights = highlights.to_vec();
+        self.save_options();
+        for index in 0..self.tabs.len() {
+            let tab = &mut self.tabs[index];
+            self.highlight_manager
+                .register(&mut tab.textarea_properties.renderer_manager);
+            if texts_changed {
+                self.update_overview(index);
+            } else {
+                tab.overview.set_colors(self.highlight_manager.highlights());
+            }
+        }
+    }
+
+    /// Search the highlighted and error lines of a tab in the background for its overview ruler
+    fn update_overview(&mut self, index: usize) {
+        let tab = &mut self.tabs[index];
+        let textarea_properties = &tab.textarea_properties;
+        tab.overview.update(
+            tab.id,
+            &textarea_properties.buffer,
+            textarea_properties.view(),
+            self.highlight_manager.highlights(),
+        );
+    }
+
+    /// Open the file dropped on the window, once whatever the number of panes
+    fn handle_dropped_file(&self, ctx: &Context) {
+        let path = ctx.input(|i| i.raw.dropped_files.first().and_then(|file| file.path.clone()));
+        if let Some(path) = path {
+            let _ = self.sender.send(Event::OpenFile(path));
+        }
+    }
+
+    /// Activate the next tab, or the previous one if `forward` is false
+    fn switch_tab(&mut self, forward: bool) {
+        let count = self.tabs.len();
+        self.active_tab = if forward {
+            (self.active_tab + 1) % count
         } else {
-            self.current_command = None;
+            (self.active_tab + count - 1) % count
+        };
+    }
+
+    /// Open the files and prepare the commands given on the command line
+    pub(crate) fn open_arguments(&mut self, arguments: Arguments) {
+        self.startup_line = arguments.line;
+        self.startup_commands = arguments.commands.into();
+        let (stdin, files): (Vec<PathBuf>, Vec<PathBuf>) = arguments
+            .files
+            .into_iter()
+            .partition(|path| path.as_os_str() == STDIN);
+        if !stdin.is_empty() || (files.is_empty() && cli::is_stdin_piped()) {
+            self.open_stdin(arguments.encoding);
+        } else if files.is_empty() {
+            self.run_startup_commands();
+        }
+        if !files.is_empty() {
+            self.open_files(files, arguments.encoding);
         }
+    }
 
-        if let Some(command) = &self.current_command {
-            debug!("Found command {}", command);
+    /// Execute the commands given on the command line in the active tab.
+    /// When a command runs on the buffer, the next ones are executed after it.
+    fn run_startup_commands(&mut self) {
+        while let Some(command) = self.startup_commands.pop_front() {
+            info!("Execute startup command {command}");
+            let tab = self.tab_mut();
+            tab.command_buffer = command;
+            tab.command_changed();
+            let runs_on_buffer = tab.current_command.is_some();
+            tab.execute_command();
+            tab.command_buffer.clear();
+            if runs_on_buffer {
+                return;
+            }
+        }
+        if let Some(line) = self.startup_line.take() {
+            Goto::new(line).execute(&mut self.tab_mut().textarea_properties);
         }
     }
 
-    pub(crate) fn execute_command(&mut self) {
-        if let Some(command) = self.current_command.take() {
-            info!("Execute command {}", command);
-            let mut tmp_buffer = Buffer::new_empty_buffer(self.sender.clone());
-            std::mem::swap(&mut tmp_buffer, &mut self.textarea_properties.buffer);
-            let sender = self.sender.clone();
-            std::thread::spawn(move || {
-                command.execute(&mut tmp_buffer);
-                sender.send(Event::BufferLoaded(tmp_buffer)).unwrap();
-            });
-        } else if let Ok(command) = Event::try_from(self.command_buffer.as_str()) {
-            self.sender.send(command).unwrap();
+    fn toggle_follow(&mut self) {
+        if self.tab().follower.is_some() {
+            self.tab_mut().stop_follow();
+            return;
+        }
+        let path = &self.tab().textarea_properties.buffer.path;
+        let followed = self.tabs.iter().any(|tab| {
+            tab.follower
+                .as_ref()
+                .is_some_and(|follower| Some(follower.path()) == path.as_deref())
+        });
+        if followed {
+            warn!("{path:?} is already followed in another tab");
+            return;
+        }
+        self.tab_mut().start_follow();
+    }
+
+    /// Display the lines read from the standard input in a new buffer without path.
+    /// The input is decoded as UTF-8 if no encoding is given.
+    pub(crate) fn open_stdin(&mut self, encoding: Option<Encoding>) {
+        info!("Read the standard input");
+        self.active_tab = self.new_tab();
+        let encoding = encoding.unwrap_or_default();
+        let mut buffer = Buffer::new_stream_buffer(self.sender.clone());
+        buffer.encoding = encoding;
+        let tab = self.tab_mut();
+        tab.set_buffer(buffer);
+        tab.streamed = Some(0);
+        stream::stream_lines(std::io::stdin(), encoding, self.sender.clone());
+    }
+
+    /// Change the encoding of the buffer.
+    /// The file is loaded again if it was not modified, otherwise the encoding is used to save it.
+    fn set_encoding(&mut self, encoding: Encoding) {
+        let sender = self.sender.clone();
+        let tab = self.tab_mut();
+        let buffer = &mut tab.textarea_properties.buffer;
+        match &buffer.path {
+            Some(path) if !buffer.dirty => {
+                let path = path.clone();
+                // the lines are shown again while the file is loaded, the buffer replaces them
+                let loading = new_loading();
+                tab.start_loading(loading, &path);
+                thread::spawn(move || {
+                    load_file(path, loading, Some(encoding), &sender, &CancellationToken::default())
+                });
+            }
+            _ => {
+                info!("Save with encoding {encoding}");
+                buffer.encoding = encoding;
+            }
         }
     }
 }
@@ -196,69 +571,162 @@ impl eframe::App for Ceos {
         }
         self.frame_history
             .on_new_frame(ctx.input(|i| i.time), frame.info().cpu_usage);
+        let frame_start = LineGroup::use_clock();
         while let Ok(event) = self.receiver.try_recv() {
             self.process_event(ctx, event)
         }
+        self.handle_dropped_file(ctx);
 
-        if !self.progress_manager.is_empty() {
-            egui::CentralPanel::default().show(ctx, |ui| {
-                ui.with_layout(Layout::top_down_justified(Align::Center), |ui| {
-                    self.progress_manager
-                        .iter()
-                        .map(|(_key, progress)| {
-                            let percent = progress.percent();
-                            ProgressBar::new(percent)
-                                .text(format!(
-                                    "{} {}/100 %",
-                                    progress.label,
-                                    (percent * 100.0) as usize
-                                ))
-                                .corner_radius(10.0)
-                                .desired_width(600.0)
-                        })
-                        .for_each(|progress_bar| {
-                            ui.add(progress_bar);
-                        });
-                });
-            });
-            ctx.request_repaint_after(std::time::Duration::from_millis(50));
-            return;
+        if self
+            .tabs
+            .iter()
+            .any(|tab| tab.follower.is_some() || tab.streamed.is_some())
+        {
+            ctx.request_repaint_after(FOLLOW_INTERVAL);
         }
-
         self.build_menu_panel(ctx);
+        self.build_tab_bar(ctx);
         self.build_options_window(ctx);
         if self.show_help {
             HelpPanel::show(ctx, &mut self.show_help);
         }
+        if self.show_highlights && self.highlight_manager.show(ctx, &mut self.show_highlights) {
+            self.highlights_changed();
+        }
+        self.build_archive_picker(ctx);
+        self.update_title(ctx);
         self.build_bottom_panel(ctx);
+        self.build_progress_panel(ctx);
 
         egui::CentralPanel::default()
             .frame(egui::containers::Frame::NONE)
             .show(ctx, |ui| {
-                if self.textarea_properties.char_width == 0.0 {
-                    let char_width =
-                        gui::tools::char_width(self.textarea_properties.font_id.clone(), ui);
-                    self.textarea_properties.char_width = char_width;
+                if self.split.is_some() {
+                    self.build_split_panes(ui);
+                } else {
+                    let tab = &mut self.tabs[self.active_tab];
+                    Self::show_tab(tab, &self.theme, &self.sender, ui);
                 }
-                self.before_frame();
-                TextPane::new(
-                    &mut self.textarea_properties,
-                    &self.current_command,
-                    &self.theme,
-                    &self.sender,
-                    &self.search_panel.search,
-                )
-                .ui(ui)
             });
+        self.enforce_memory_budget(frame_start);
     }
 }
 
 impl Ceos {
-    fn before_frame(&mut self) {
-        if let Some(command) = &mut self.current_command {
+    fn show_tab(tab: &mut Tab, theme: &Theme, sender: &Sender<Event>, ui: &mut Ui) -> Response {
+        if tab.textarea_properties.char_width == 0.0 {
+            let char_width = gui::tools::char_width(tab.textarea_properties.font_id.clone(), ui);
+            tab.textarea_properties.char_width = char_width;
+        }
+        Self::before_frame(tab);
+        TextPane::new(
+            &mut tab.textarea_properties,
+            &tab.current_command,
+            theme,
+            sender,
+            &tab.search_panel.search,
+            &tab.overview,
+        )
+        .ui(ui)
+    }
+
+    /// Show the active tab in the first pane and the tab chosen for the second pane.
+    /// The caret, selection and scroll of the second pane are swapped into its tab while it is drawn.
+    fn build_split_panes(&mut self, ui: &mut Ui) {
+        let Some(split) = &mut self.split else {
+            return;
+        };
+        let active = self.active_tab;
+        let other = self
+            .tabs
+            .iter()
+            .position(|tab| tab.id == split.tab)
+            .unwrap_or(active);
+        let (first_rect, second_rect) = split.pane_rects(ui.available_rect_before_wrap());
+        let before = [
+            self.tabs[active].textarea_properties.scroll_offset,
+            split.pane.scroll_offset,
+        ];
+
+        let first_pane = UiBuilder::new().max_rect(first_rect).id_salt("first_pane");
+        ui.scope_builder(first_pane, |ui| {
+            Self::show_tab(&mut self.tabs[active], &self.theme, &self.sender, ui);
+        });
+        let separator = match split.orientation {
+            SplitOrientation::Horizontal => {
+                Rect::from_min_max(first_rect.left_bottom(), second_rect.right_top())
+            }
+            SplitOrientation::Vertical => {
+                Rect::from_min_max(first_rect.right_top(), second_rect.left_bottom())
+            }
+        };
+        let color = ui.visuals().widgets.noninteractive.bg_stroke.color;
+        ui.painter().rect_filled(separator, 0.0, color);
+        let mut unsplit = false;
+        let second_pane = UiBuilder::new().max_rect(second_rect).id_salt("second_pane");
+        ui.scope_builder(second_pane, |ui| {
+            unsplit = Self::split_header(split, &self.tabs, ui);
+            let tab = &mut self.tabs[other];
+            tab.textarea_properties.swap_pane(&mut split.pane);
+            Self::show_tab(tab, &self.theme, &self.sender, ui);
+            tab.textarea_properties.swap_pane(&mut split.pane);
+        });
+
+        if split.synchronized {
+            let after = [
+                self.tabs[active].textarea_properties.scroll_offset,
+                split.pane.scroll_offset,
+            ];
+            let [first, second] = split::synchronize(before, after);
+            self.tabs[active].textarea_properties.scroll_offset = first;
+            split.pane.scroll_offset = second;
+        }
+        if unsplit {
+            self.split = None;
+        }
+    }
+
+    /// The header of the second pane to choose its tab, returns true if the pane is closed
+    fn split_header(split: &mut Split, tabs: &[Tab], ui: &mut Ui) -> bool {
+        let mut unsplit = false;
+        ui.horizontal(|ui| {
+            let mut selected = split.tab;
+            let title = tabs
+                .iter()
+                .find(|tab| tab.id == selected)
+                .map(Tab::title)
+                .unwrap_or_default();
+            egui::ComboBox::from_id_salt("split_tab")
+                .selected_text(title)
+                .show_ui(ui, |ui| {
+                    for tab in tabs {
+                        ui.selectable_value(&mut selected, tab.id, tab.title());
+                    }
+                });
+            if selected != split.tab
+                && let Some(tab) = tabs.iter().find(|tab| tab.id == selected)
+            {
+                split.tab = selected;
+                split.pane = tab.textarea_properties.pane_state();
+            }
+            ui.checkbox(&mut split.synchronized, "Sync scroll")
+                .on_hover_text("Scrolling a pane scrolls the other one (sync)");
+            if ui
+                .add(egui::Button::new("x").frame(false).small())
+                .on_hover_text("Close the pane (unsplit)")
+                .clicked()
+            {
+                unsplit = true;
+            }
+        });
+        unsplit
+    }
+
+    fn before_frame(tab: &mut Tab) {
+        if let Some(command) = &mut tab.current_command {
             command.before_frame();
         }
-        self.textarea_properties.renderer_manager.before_frame();
+        tab.textarea_properties.renderer_manager.before_frame();
     }
 
     fn build_menu_panel(&mut self, ctx: &Context) {
@@ -268,6 +736,7 @@ impl Ceos {
             egui::MenuBar::new().ui(ui, |ui| {
                 // NOTE: no File->Quit on web pages!
                 self.file_menu(ui);
+                self.edit_menu(ui);
                 self.view_menu(ui);
                 self.options_menu(ui);
                 self.debug_menu(ui);
@@ -275,16 +744,47 @@ impl Ceos {
         });
     }
 
+    fn build_tab_bar(&mut self, ctx: &Context) {
+        egui::TopBottomPanel::top("tab_bar").show(ctx, |ui| {
+            ui.horizontal_wrapped(|ui| {
+                let mut closed = None;
+                for (index, tab) in self.tabs.iter().enumerate() {
+                    let response = ui.selectable_label(index == self.active_tab, tab.title());
+                    if response.clicked() {
+                        self.active_tab = index;
+                    }
+                    if let Some(path) = &tab.textarea_properties.buffer.path {
+                        response.on_hover_text(path.to_string_lossy());
+                    }
+                    if ui
+                        .add(egui::Button::new("x").frame(false).small())
+                        .on_hover_text("Close the tab (Ctrl+W)")
+                        .clicked()
+                    {
+                        closed = Some(index);
+                    }
+                    ui.separator();
+                }
+                if let Some(index) = closed {
+                    self.close_tab(index);
+                }
+            });
+        });
+    }
+
     fn file_menu(&mut self, ui: &mut Ui) {
         ui.menu_button("File", |ui| {
             if ui.button("Open...").clicked() {
                 self.browse_open_file();
             }
+            if ui.button("Merge open...").clicked() {
+                self.browse_merge_files(None);
+            }
             if ui.button("Save").clicked() {
-                self.save_file();
+                self.tab_mut().save_file();
             }
             if ui.button("Save as...").clicked() {
-                self.save_as();
+                self.tab_mut().save_as();
             }
             if ui.button("Close").clicked() {
                 self.sender.send(BufferClosed).unwrap();
@@ -296,6 +796,17 @@ impl Ceos {
         });
     }
 
+    fn edit_menu(&mut self, ui: &mut Ui) {
+        ui.menu_button("Edit", |ui| {
+            if ui.button("Undo").clicked() {
+                self.sender.send(Event::Undo).unwrap();
+            }
+            if ui.button("Redo").clicked() {
+                self.sender.send(Event::Redo).unwrap();
+            }
+        });
+    }
+
     fn view_menu(&mut self, ui: &mut Ui) {
         ui.menu_button("View", |ui| {
             if ui.button("☀ Solarized Light").clicked() {
@@ -307,6 +818,23 @@ impl Ceos {
             if ui.button("☀ jEdit").clicked() {
                 self.set_theme(Theme::jEdit(), ui.ctx());
             }
+            ui.separator();
+            if ui.button("Highlights...").clicked() {
+                self.show_highlights = true;
+            }
+            ui.separator();
+            if ui.button("Split horizontally").clicked() {
+                self.split(SplitOrientation::Horizontal);
+            }
+            if ui.button("Split vertically").clicked() {
+                self.split(SplitOrientation::Vertical);
+            }
+            if let Some(split) = &mut self.split {
+                ui.checkbox(&mut split.synchronized, "Synchronized scroll");
+                if ui.button("Unsplit").clicked() {
+                    self.split = None;
+                }
+            }
         });
     }
 
@@ -318,10 +846,8 @@ impl Ceos {
             // Quick toggle directly in the menu as well (optional convenience)
             ui.separator();
             let response = ui.checkbox(&mut self.options.compression, "Compression");
-            if response.changed()
-                && let Err(e) = self.options.save()
-            {
-                warn!("Impossible d'enregistrer ceos.toml: {e}");
+            if response.changed() {
+                self.save_options();
             }
         });
     }
@@ -336,7 +862,7 @@ impl Ceos {
                 decomressed_line_count,
                 compressed_size,
             ) = {
-                let buffer = &self.textarea_properties.buffer;
+                let buffer = &self.tab().textarea_properties.buffer;
                 (
                     buffer.line_count(),
                     buffer.group_count(),
@@ -359,7 +885,7 @@ impl Ceos {
 
             ui.separator();
             if ui.button("Compress").clicked() {
-                self.textarea_properties.buffer.compress_all_groups();
+                self.tab_mut().textarea_properties.buffer.compress_all_groups();
             }
         });
     }
@@ -373,16 +899,95 @@ impl Ceos {
                 ui.vertical(|ui| {
                     ui.heading("Paramètres");
                     let response = ui.checkbox(&mut self.options.compression, "Compression");
-                    if response.changed()
-                        && let Err(e) = self.options.save()
-                    {
-                        warn!("Impossible d'enregistrer ceos.toml: {e}");
+                    if response.changed() {
+                        self.save_options();
                     }
+                    ui.horizontal(|ui| {
+                        ui.label("Timestamp format");
+                        let response = ui
+                            .text_edit_singleline(&mut self.options.timestamp_format)
+                            .on_hover_text("auto, iso, syslog, epoch, log4j or a strftime format like %d/%m/%Y %H:%M:%S");
+                        if response.lost_focus() {
+                            self.save_options();
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Memory budget");
+                        let response = ui
+                            .add(
+                                egui::DragValue::new(&mut self.options.memory_budget)
+                                    .range(16..=1_000_000)
+                                    .suffix(" MB"),
+                            )
+                            .on_hover_text("The memory the decompressed lines may use");
+                        if response.drag_stopped() || response.lost_focus() {
+                            self.save_options();
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Spill threshold");
+                        let response = ui
+                            .add(
+                                egui::DragValue::new(&mut self.options.spill_threshold)
+                                    .range(16..=1_000_000)
+                                    .suffix(" MB"),
+                            )
+                            .on_hover_text(
+                                "The compressed lines of a file above it are moved to a scratch file",
+                            );
+                        if response.changed() {
+                            spill::set_threshold(self.options.spill_threshold_bytes());
+                        }
+                        if response.drag_stopped() || response.lost_focus() {
+                            self.save_options();
+                        }
+                    });
                 });
             });
         self.show_options = open;
     }
 
+    fn build_archive_picker(&mut self, ctx: &Context) {
+        let Some(picker) = &mut self.archive_picker else {
+            return;
+        };
+        let mut open = true;
+        let chosen = picker.show(ctx, &mut open);
+        let (path, archive) = (picker.path.clone(), picker.archive);
+        if !open {
+            self.archive_picker = None;
+        }
+        if let Some(entries) = chosen {
+            self.open_archive_entries(path, archive, entries);
+        }
+    }
+
+    /// Compress again the least recently used lines of all the tabs when they use more memory
+    /// than the budget, the lines read during this frame are kept. The compressed lines above
+    /// the spill threshold are then moved to the scratch files.
+    fn enforce_memory_budget(&mut self, frame_start: u64) {
+        let mut buffers: Vec<&mut Buffer> = self
+            .tabs
+            .iter_mut()
+            .map(|tab| &mut tab.textarea_properties.buffer)
+            .collect();
+        self.memory_used =
+            budget::enforce(&mut buffers, self.options.memory_budget_bytes(), frame_start);
+        let threshold = self.options.spill_threshold_bytes();
+        // the groups of a file being loaded are spilled by the loader, they are shared
+        for tab in self.tabs.iter_mut().filter(|tab| tab.loading.is_none()) {
+            tab.textarea_properties.spill_groups(threshold);
+        }
+    }
+
+    fn update_title(&mut self, ctx: &Context) {
+        let title = self.tab().textarea_properties.buffer.name();
+        if title != self.title {
+            ctx.send_viewport_cmd(ViewportCommand::Title(format!("Ceos - {title}")));
+            self.title = title;
+        }
+    }
+
     fn set_theme(&mut self, theme: Theme, ctx: &Context) {
         let visuals = Visuals::from(&theme);
         self.theme = theme;
@@ -391,7 +996,7 @@ impl Ceos {
 
     fn build_bottom_panel(&mut self, ctx: &Context) {
         let mut bottom = egui::TopBottomPanel::bottom("bottom_panel");
-        if self.search_panel.search.has_results() {
+        if self.tab().search_panel.search.has_results() {
             bottom = bottom
                 .max_height(200.0)
                 .default_height(200.0)
@@ -401,88 +1006,170 @@ impl Ceos {
             ui.vertical(|ui| {
                 ui.horizontal(|ui| {
                     ui.label("Command: ");
+                    let tab = self.tab_mut();
                     let response = ui.add_sized(
                         ui.available_size(),
-                        egui::TextEdit::singleline(&mut self.command_buffer),
+                        egui::TextEdit::singleline(&mut tab.command_buffer),
                     );
                     if response.changed() {
-                        if self.try_search() {
-                            self.sender
-                                .send(GotoLine(Goto::new(self.search_panel.search.line())))
-                                .unwrap();
-                        } else {
-                            self.try_filter_command();
-                        }
+                        tab.command_changed();
                     }
                 });
                 self.status_bar(ui);
             });
-            if self.search_panel.search.has_results() {
-                self.search_panel.ui(&self.textarea_properties.buffer, ui);
+            let tab = self.tab();
+            if tab.search_panel.search.has_results() {
+                tab.search_panel.ui(&tab.textarea_properties.buffer, ui);
             }
             self.frame_history.ui(ui);
             self.handle_keys(ui);
         });
     }
 
+    /// Show the progress of the operations running in the background above the command line,
+    /// the text can still be browsed while they run
+    fn build_progress_panel(&self, ctx: &Context) {
+        if self.progress_manager.is_empty() {
+            return;
+        }
+        egui::TopBottomPanel::bottom("progress_panel").show(ctx, |ui| {
+            for (_key, progress) in self.progress_manager.iter() {
+                let percent = progress.percent();
+                ui.horizontal(|ui| {
+                    ui.add(
+                        ProgressBar::new(percent)
+                            .text(format!(
+                                "{} {}/100 %",
+                                progress.label,
+                                (percent * 100.0) as usize
+                            ))
+                            .corner_radius(10.0)
+                            .desired_width(400.0),
+                    );
+                    if progress.is_cancellable() && ui.button("Cancel").clicked() {
+                        progress.cancel();
+                    }
+                });
+            }
+        });
+        ctx.request_repaint_after(std::time::Duration::from_millis(50));
+    }
+
     fn handle_keys(&mut self, ui: &Ui) {
         if ui.input(|i| i.key_pressed(Key::Escape)) {
             self.show_help = false;
         }
         #[allow(clippy::collapsible_if)]
         if ui.input(|i| i.key_pressed(Key::Enter)) {
-            self.execute_command();
-            self.command_buffer.clear();
+            let tab = self.tab_mut();
+            tab.execute_command();
+            tab.command_buffer.clear();
+        } else if ui.input_mut(|i| i.consume_key(Modifiers::CTRL | Modifiers::SHIFT, Key::Tab)) {
+            self.switch_tab(false);
+        } else if ui.input_mut(|i| i.consume_key(Modifiers::CTRL, Key::Tab)) {
+            self.switch_tab(true);
         } else if ui.input(|i| i.key_pressed(Key::W) && i.modifiers.ctrl) {
             let _ = self.sender.send(BufferClosed);
         } else if ui.input(|i| i.key_pressed(Key::O) && i.modifiers.ctrl) {
             self.browse_open_file();
         } else if ui.input(|i| i.key_pressed(Key::S) && i.modifiers.ctrl) {
-            self.save_file();
-        } else if ui.input(|i| i.key_pressed(Key::F3)) {
-            if self.search_panel.search.has_results() {
-                self.search_panel.search.next();
-                let _ = self
-                    .sender
-                    .send(GotoLine(Goto::from(self.search_panel.search.line())));
-            }
+            self.tab_mut().save_file();
         } else if ui.input(|i| i.key_pressed(Key::F3) && i.modifiers.shift) {
-            if self.search_panel.search.has_results() {
-                self.search_panel.search.prev();
-                let _ = self
-                    .sender
-                    .send(GotoLine(Goto::from(self.search_panel.search.line())));
+            let search = &mut self.tabs[self.active_tab].search_panel.search;
+            search.prev();
+            if let Some(goto) = search.goto() {
+                let _ = self.sender.send(GotoLine(goto));
+            }
+        } else if ui.input(|i| i.key_pressed(Key::F3)) {
+            let search = &mut self.tabs[self.active_tab].search_panel.search;
+            search.next();
+            if let Some(goto) = search.goto() {
+                let _ = self.sender.send(GotoLine(goto));
             }
         }
     }
 
     fn status_bar(&mut self, ui: &mut Ui) {
         ui.horizontal(|ui| {
-            let size = format_size_i(self.textarea_properties.buffer.len(), DECIMAL);
+            let tab = &self.tabs[self.active_tab];
+            let textarea_properties = &tab.textarea_properties;
+            let size = format_size_i(textarea_properties.buffer.len(), DECIMAL);
             ui.label(format!("Length: {size}"));
-            let mem = format_size_i(self.textarea_properties.buffer.mem(), DECIMAL);
+            let mem = format_size_i(textarea_properties.buffer.mem(), DECIMAL);
             ui.label(format!("Mem: {mem}"));
-            ui.label(format!(
-                "{} lines",
-                self.textarea_properties.buffer.line_count()
-            ));
+            let used = format_size_i(self.memory_used, DECIMAL);
+            let budget = format_size_i(self.options.memory_budget_bytes(), DECIMAL);
+            ui.label(format!("Budget: {used} / {budget}"))
+                .on_hover_text("Memory used by the decompressed lines of all the tabs");
+            if textarea_properties.is_filtered() {
+                ui.label(format!(
+                    "{} / {} lines",
+                    textarea_properties.line_count(),
+                    textarea_properties.buffer.line_count()
+                ));
+                if ui
+                    .add(egui::Button::new("Unfilter").frame(false))
+                    .on_hover_text("Remove the last filter")
+                    .clicked()
+                {
+                    let _ = self.sender.send(Event::PopView);
+                }
+            } else {
+                ui.label(format!(
+                    "{} lines",
+                    textarea_properties.buffer.line_count()
+                ));
+            }
+            let search = &tab.search_panel.search;
+            if search.has_results() {
+                ui.label(format!(
+                    "{} matches in {} lines",
+                    search.result_count(),
+                    search.line_count()
+                ));
+            }
+
+            let mut encoding = textarea_properties.buffer.encoding;
+            ui.menu_button(encoding.to_string(), |ui| {
+                for choice in Encoding::ALL {
+                    ui.radio_value(&mut encoding, choice, choice.to_string());
+                }
+            })
+            .response
+            .on_hover_text("The file encoding, choose another one to reload the file");
+            if let Some(read) = tab.streamed {
+                ui.spinner();
+                ui.label(format!("Reading stdin: {}", format_size_i(read, DECIMAL)));
+            }
+            if textarea_properties.buffer.path.is_some()
+                && ui
+                    .selectable_label(tab.follower.is_some(), "Follow")
+                    .on_hover_text("Append the lines written to the file")
+                    .clicked()
+            {
+                let _ = self.sender.send(Event::ToggleFollow);
+            }
+            if encoding != textarea_properties.buffer.encoding {
+                self.set_encoding(encoding);
+            }
 
             ui.separator();
 
-            let mode_text = match self.textarea_properties.interaction_mode {
+            let tab = self.tab_mut();
+            let mode_text = match tab.textarea_properties.interaction_mode {
                 InteractionMode::Selection => "Selection",
                 InteractionMode::Column => "Column",
             };
 
             if ui.add(egui::Button::new(mode_text).frame(false)).clicked() {
-                match self.textarea_properties.interaction_mode {
+                match tab.textarea_properties.interaction_mode {
                     InteractionMode::Selection => {
-                        self.textarea_properties
+                        tab.textarea_properties
                             .set_interaction_mode(InteractionMode::Column);
                     }
                     InteractionMode::Column => {
-                        self.clear_command();
-                        self.textarea_properties
+                        tab.clear_command();
+                        tab.textarea_properties
                             .set_interaction_mode(InteractionMode::Selection)
                     }
                 };
@@ -492,102 +1179,255 @@ impl Ceos {
 
     pub(crate) fn browse_open_file(&self) {
         info!("Browse open file");
-        if let Some(path) = rfd::FileDialog::new().set_directory("./").pick_file() {
-            let path = path.into_os_string();
-            let path = path.to_str().unwrap();
-            self.open_file(path.into());
+        if let Some(paths) = rfd::FileDialog::new().set_directory("./").pick_files() {
+            self.open_files(paths, None);
         }
     }
 
-    pub(crate) fn open_file(&self, path: PathBuf) {
-        info!("Open file {path:?}");
+    /// Choose the files to merge into one timeline
+    fn browse_merge_files(&self, format: Option<TimestampFormat>) {
+        info!("Browse files to merge");
+        if let Some(paths) = rfd::FileDialog::new().set_directory("./").pick_files() {
+            let format = format.unwrap_or_else(|| {
+                TimestampFormat::try_from(self.options.timestamp_format.as_str()).unwrap_or_default()
+            });
+            self.merge_files(paths, format);
+        }
+    }
+
+    /// Merge the files by timestamp into a new tab
+    fn merge_files(&self, paths: Vec<PathBuf>, format: TimestampFormat) {
         let sender = self.sender.clone();
         thread::spawn(move || {
-            sender.send(BufferClosed).unwrap();
-            match Buffer::new_from_file(path, sender.clone()) {
+            let cancellation = CancellationToken::default();
+            match merge::merge_files(&paths, &format, sender.clone(), &cancellation) {
                 Ok(buffer) => sender.send(BufferLoaded(buffer)).unwrap(),
-                Err(e) => warn!("{:?}", e),
+                Err(e) => warn!("Unable to merge {paths:?}: {e}"),
             }
         });
     }
 
-    fn save_file(&mut self) {
-        info!("save_file");
-        if !self.textarea_properties.buffer.dirty {
-            return;
-        }
-
-        match &self.textarea_properties.buffer.path {
-            None => self.save_as(),
-            Some(path) => self.save_to_path(path),
-        }
-        self.textarea_properties.buffer.dirty = false;
+    /// Open files in new tabs, if no encoding is given it is detected.
+    /// If a file is an archive its entries are listed to choose the ones to open.
+    pub(crate) fn open_files(&self, paths: Vec<PathBuf>, encoding: Option<Encoding>) {
+        info!("Open files {paths:?} with encoding {encoding:?}");
+        load_files(paths, encoding, self.sender.clone());
     }
 
-    fn save_to_path(&self, path: &PathBuf) {
-        let mut total_size: usize = 0;
-        let mut lines: Vec<Vec<u8>> = Vec::new();
-        for group in self.textarea_properties.buffer.line_groups() {
-            let cow = group.lines();
-            for line in cow.as_ref().iter() {
-                let bytes: Vec<u8> = line.content().as_bytes().to_vec();
-                total_size += bytes.len() + 1; // +1 pour le saut de ligne
-                lines.push(bytes);
-            }
-        }
-
+    /// Load the entries of an archive, each one in a new tab
+    fn open_archive_entries(&self, path: PathBuf, archive: Archive, entries: Vec<ArchiveEntry>) {
+        info!("Open {} files from {path:?}", entries.len());
         let sender = self.sender.clone();
-
-        let path = path.clone();
         thread::spawn(move || {
-            // Démarrer la progression
-            let _ = sender.send(Event::BufferSavingStarted(path.clone(), total_size));
-            match File::create(&path) {
-                Ok(file) => {
-                    let mut file = LineWriter::new(file);
-                    let mut current: usize = 0;
-                    for bytes in lines.iter() {
-                        if let Err(err) = file.write_all(bytes) {
-                            error!("{err}");
-                            let _ = sender.send(Event::BufferSaveFailed(path.clone()));
-                            return;
-                        }
-                        if let Err(err) = file.write_all(b"\n") {
-                            error!("{err}");
-                            let _ = sender.send(Event::BufferSaveFailed(path.clone()));
-                            return;
-                        }
-                        current += bytes.len() + 1;
-                        let _ =
-                            sender.send(Event::BufferSaving(path.clone(), current, total_size));
-                    }
-                    // Fin de progression
-                    let _ = sender.send(Event::BufferSaved(path.clone()));
-                }
-                Err(err) => {
-                    error!("Unable to save file {path:?} becaues {err}");
-                    let _ = sender.send(Event::BufferSaveFailed(path.clone()));
-                }
+            // cancelling a loading also skips the remaining entries
+            let cancellation = CancellationToken::default();
+            if let Err(e) = Buffer::load_archive_entries(
+                path.clone(),
+                archive,
+                &entries,
+                None,
+                sender.clone(),
+                &cancellation,
+                |buffer| sender.send(BufferLoaded(buffer)).unwrap(),
+            ) {
+                warn!("Unable to read the files of {path:?}: {e}");
             }
         });
     }
+}
 
-    pub(crate) fn save_as(&mut self) {
-        info!("save as");
-        let mut dialog = rfd::FileDialog::new().set_directory("./");
-        if let Some(path) = &self.textarea_properties.buffer.path {
-            if let Some(parent) = path.parent() {
-                dialog = dialog.set_directory(parent);
+/// Load the files one after the other in a background thread, each one in a new tab.
+/// Cancelling a loading also skips the remaining files.
+fn load_files(paths: Vec<PathBuf>, encoding: Option<Encoding>, sender: Sender<Event>) {
+    thread::spawn(move || {
+        let cancellation = CancellationToken::default();
+        for path in paths {
+            if cancellation.is_cancelled() {
+                break;
             }
-            if let Some(file_name) = path.file_name() {
-                dialog = dialog.set_file_name(file_name.to_string_lossy());
+            match Archive::detect(&path) {
+                Ok(Some(archive)) => {
+                    match archive.entries(&path) {
+                        Ok(entries) => {
+                            let _ = sender.send(Event::ArchiveOpened(path, archive, entries));
+                        }
+                        Err(e) => warn!("Unable to list the files of {path:?}: {e}"),
+                    }
+                    continue;
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    warn!("{:?}", e);
+                    continue;
+                }
             }
+            load_file(path, new_loading(), encoding, &sender, &cancellation);
         }
+    });
+}
 
-        if let Some(path) = dialog.save_file() {
-            self.save_to_path(&path);
-            self.textarea_properties.buffer.set_path(path);
-            self.textarea_properties.buffer.dirty = false;
+/// Load a file, the buffer replaces the lines of the tab of the loading if there is one
+fn load_file(
+    path: PathBuf,
+    loading: usize,
+    encoding: Option<Encoding>,
+    sender: &Sender<Event>,
+    cancellation: &CancellationToken,
+) {
+    match Buffer::new_from_file(path.clone(), loading, encoding, sender.clone(), cancellation) {
+        Ok(buffer) => sender.send(BufferLoaded(buffer)).unwrap(),
+        Err(e) => {
+            warn!("Unable to load {path:?}: {e}");
+            let _ = sender.send(Event::BufferLoadingFailed(loading));
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ceos::progress_manager::operation_id;
+
+    fn loaded(ceos: &mut Ceos, text: &str) {
+        let ctx = Context::default();
+        let buffer = Buffer::new_from_string(ceos.sender.clone(), text, 10);
+        ceos.process_event(&ctx, BufferLoaded(buffer));
+    }
+
+    #[test]
+    fn test_tabs() {
+        let mut ceos = Ceos::with_options(Options::default());
+        // the blank tab is reused by the first buffer
+        loaded(&mut ceos, "first");
+        loaded(&mut ceos, "second");
+        loaded(&mut ceos, "third");
+        assert_eq!(3, ceos.tabs.len());
+        assert_eq!(2, ceos.active_tab);
+
+        ceos.switch_tab(true);
+        assert_eq!(0, ceos.active_tab);
+        ceos.switch_tab(false);
+        assert_eq!(2, ceos.active_tab);
+
+        ceos.active_tab = 1;
+        ceos.process_event(&Context::default(), BufferClosed);
+        assert_eq!(2, ceos.tabs.len());
+        assert_eq!("third", ceos.tab().textarea_properties.buffer.line_text(0));
+
+        ceos.close_tab(0);
+        ceos.close_tab(0);
+        assert_eq!(1, ceos.tabs.len());
+        assert!(ceos.tab().is_blank());
+    }
+
+    #[test]
+    fn test_groups_shown_while_loading() {
+        let mut ceos = Ceos::with_options(Options::default());
+        let ctx = Context::default();
+        let path = PathBuf::from("app.log");
+        let partial = Buffer::new_from_string(ceos.sender.clone(), "1\n2\n3", 2);
+        let groups = partial.line_groups()[..1].iter().map(|g| g.snapshot()).collect();
+        ceos.process_event(&ctx, Event::BufferGroupsLoaded(7, path.clone(), groups));
+        assert_eq!(1, ceos.tabs.len());
+        assert_eq!(2, ceos.tab().textarea_properties.buffer.line_count());
+        assert_eq!(None, ceos.tab().textarea_properties.buffer.path);
+
+        // the loaded buffer replaces the partial one in the same tab
+        let mut buffer = Buffer::new_from_string(ceos.sender.clone(), "1\n2\n3", 2);
+        buffer.path = Some(path);
+        buffer.loading = 7;
+        ceos.process_event(&ctx, BufferLoaded(buffer));
+        assert_eq!(1, ceos.tabs.len());
+        assert!(ceos.tab().loading.is_none());
+        assert_eq!(3, ceos.tab().textarea_properties.buffer.line_count());
+    }
+
+    #[test]
+    fn test_same_file_loaded_twice() {
+        let mut ceos = Ceos::with_options(Options::default());
+        let ctx = Context::default();
+        let path = PathBuf::from("app.log");
+        let first = new_loading();
+        let second = new_loading();
+        let first_cancellation = CancellationToken::default();
+        let second_cancellation = CancellationToken::default();
+        ceos.process_event(
+            &ctx,
+            Event::BufferLoadingStarted(first, path.clone(), 10, first_cancellation.clone()),
+        );
+        ceos.process_event(
+            &ctx,
+            Event::BufferLoadingStarted(second, path.clone(), 10, second_cancellation.clone()),
+        );
+        let groups = |ceos: &Ceos| {
+            let partial = Buffer::new_from_string(ceos.sender.clone(), "1\n2", 2);
+            partial.line_groups()[..1].iter().map(|g| g.snapshot()).collect()
+        };
+        ceos.process_event(&ctx, Event::BufferGroupsLoaded(first, path.clone(), groups(&ceos)));
+        ceos.process_event(&ctx, Event::BufferGroupsLoaded(second, path.clone(), groups(&ceos)));
+        assert_eq!(2, ceos.tabs.len());
+        assert_eq!(2, ceos.tabs[1].textarea_properties.buffer.line_count());
+
+        // the first loading ends without removing the progress of the second one
+        ceos.process_event(&ctx, Event::OperationFinished(loading_id(first)));
+        assert!(ceos.progress_manager.progress.contains_key(&loading_id(second)));
+
+        ceos.close_tab(1);
+        assert!(second_cancellation.is_cancelled());
+        assert!(!first_cancellation.is_cancelled());
+    }
+
+    #[test]
+    fn test_operations_have_their_own_progress() {
+        let mut ceos = Ceos::with_options(Options::default());
+        let ctx = Context::default();
+        let first = operation_id("Filtering...");
+        let second = operation_id("Filtering...");
+        let second_cancellation = CancellationToken::default();
+        ceos.process_event(&ctx, Event::OperationStarted(first.clone(), 10, None));
+        ceos.process_event(
+            &ctx,
+            Event::OperationStarted(second.clone(), 10, Some(second_cancellation.clone())),
+        );
+        ceos.process_event(&ctx, Event::OperationFinished(first));
+        let progress = &ceos.progress_manager.progress[&second];
+        assert_eq!("Filtering...", progress.label);
+        progress.cancel();
+        assert!(second_cancellation.is_cancelled());
+    }
+
+    #[test]
+    fn test_startup_line_applied_once_streamed() {
+        let mut ceos = Ceos::with_options(Options::default());
+        let ctx = Context::default();
+        ceos.startup_line = Some(3);
+        let buffer = Buffer::new_from_string(ceos.sender.clone(), "1\n2\n3\n4", 2);
+        ceos.tab_mut().set_buffer(buffer);
+        ceos.tab_mut().streamed = Some(0);
+        ceos.process_event(&ctx, Event::BufferStreamed);
+        assert!(ceos.startup_line.is_none());
+        assert!(ceos.tab().streamed.is_none());
+    }
+
+    #[test]
+    fn test_split() {
+        let mut ceos = Ceos::with_options(Options::default());
+        loaded(&mut ceos, "first");
+        loaded(&mut ceos, "second");
+        let ctx = Context::default();
+        ceos.process_event(&ctx, Event::try_from("vsplit").unwrap());
+        ceos.process_event(&ctx, Event::ToggleScrollSync);
+        let split = ceos.split.as_ref().unwrap();
+        assert_eq!(SplitOrientation::Vertical, split.orientation);
+        assert_eq!(ceos.tabs[1].id, split.tab);
+        assert!(split.synchronized);
+
+        // the second pane shows the active tab once its tab is closed
+        ceos.active_tab = 0;
+        ceos.close_tab(1);
+        assert_eq!(ceos.tabs[0].id, ceos.split.as_ref().unwrap().tab);
+
+        ceos.process_event(&ctx, Event::try_from("unsplit").unwrap());
+        assert!(ceos.split.is_none());
+    }
+}
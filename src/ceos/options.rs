@@ -1,3 +1,4 @@
+use crate::ceos::gui::highlight::Highlight;
 use log::{info, warn};
 use serde::{Deserialize, Serialize};
 use std::fs;
@@ -5,13 +6,62 @@ use std::io::Error;
 use std::path::PathBuf;
 
 const CONFIG_FILE: &str = "ceos.toml";
+/// The default memory budget of the decompressed lines in MB
+const DEFAULT_MEMORY_BUDGET: usize = 1024;
+/// The default size in MB of the compressed lines a buffer keeps in memory
+const DEFAULT_SPILL_THRESHOLD: usize = 1024;
 
-#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub(crate) struct Options {
     pub(crate) compression: bool,
+    /// The format of the timestamps used to merge files: auto, iso, syslog, epoch, log4j or a
+    /// strftime format
+    #[serde(default)]
+    pub(crate) timestamp_format: String,
+    /// The highlighted texts with their colour
+    #[serde(default)]
+    pub(crate) highlights: Vec<Highlight>,
+    /// The memory in MB the decompressed lines of all the buffers may use, the least recently
+    /// used ones are compressed again above it
+    #[serde(default = "default_memory_budget")]
+    pub(crate) memory_budget: usize,
+    /// The size in MB of the compressed lines a buffer keeps in memory, the least recently used
+    /// ones are moved to a scratch file above it
+    #[serde(default = "default_spill_threshold")]
+    pub(crate) spill_threshold: usize,
+}
+
+const fn default_memory_budget() -> usize {
+    DEFAULT_MEMORY_BUDGET
+}
+
+const fn default_spill_threshold() -> usize {
+    DEFAULT_SPILL_THRESHOLD
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            compression: false,
+            timestamp_format: String::new(),
+            highlights: Vec::new(),
+            memory_budget: DEFAULT_MEMORY_BUDGET,
+            spill_threshold: DEFAULT_SPILL_THRESHOLD,
+        }
+    }
 }
 
 impl Options {
+    /// The memory budget in bytes
+    pub(crate) const fn memory_budget_bytes(&self) -> usize {
+        self.memory_budget.saturating_mul(1_000_000)
+    }
+
+    /// The spill threshold in bytes
+    pub(crate) const fn spill_threshold_bytes(&self) -> usize {
+        self.spill_threshold.saturating_mul(1_000_000)
+    }
+
     fn config_path() -> PathBuf {
         // Par défaut, on lit/écrit dans le répertoire courant
         std::env::current_dir()
@@ -80,4 +130,28 @@ mod tests {
         assert_eq!(o.compression, back.compression);
         Ok(())
     }
+
+    #[test]
+    fn missing_timestamp_format() -> Result<(), Box<dyn std::error::Error>> {
+        let o: Options = toml::from_str("compression = true")?;
+        assert_eq!("", o.timestamp_format);
+        assert!(o.highlights.is_empty());
+        assert_eq!(DEFAULT_MEMORY_BUDGET, o.memory_budget);
+        assert_eq!(DEFAULT_SPILL_THRESHOLD, o.spill_threshold);
+        Ok(())
+    }
+
+    #[test]
+    fn highlights_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+        let o = Options {
+            highlights: vec![Highlight {
+                text: "WARN".into(),
+                color: [255, 136, 0],
+            }],
+            ..Options::default()
+        };
+        let back: Options = toml::from_str(&toml::to_string_pretty(&o)?)?;
+        assert_eq!(o.highlights, back.highlights);
+        Ok(())
+    }
 }
@@ -1,8 +1,37 @@
+use crate::ceos::tools::cancellation::CancellationToken;
 use std::collections::HashMap;
 use std::collections::hash_map::Iter;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
-pub(crate) const BUFFER_LOADING: &str = "_BufferLoading_";
+const BUFFER_LOADING: &str = "_BufferLoading_";
 pub(crate) const BUFFER_SAVING: &str = "_BufferSaving_";
+/// Separates the label of an operation from its number in its progress id
+const OPERATION_NUMBER: char = '#';
+
+/// Returns a new loading id. Several files can be loaded at once, even the same file twice, so
+/// each loading has its own id carried by its events. 0 is the id of the buffers not loaded.
+pub(crate) fn new_loading() -> usize {
+    static NEXT_LOADING: AtomicUsize = AtomicUsize::new(1);
+    NEXT_LOADING.fetch_add(1, Ordering::Relaxed)
+}
+
+/// The id of the progress of a loading
+pub(crate) fn loading_id(loading: usize) -> String {
+    format!("{BUFFER_LOADING}{loading}")
+}
+
+/// The id of the progress of an operation that can run in several tabs at once, like a filter.
+/// Each call returns a new id made of the label and a number.
+pub(crate) fn operation_id(label: &str) -> String {
+    static NEXT_OPERATION: AtomicUsize = AtomicUsize::new(0);
+    let number = NEXT_OPERATION.fetch_add(1, Ordering::Relaxed);
+    format!("{label}{OPERATION_NUMBER}{number}")
+}
+
+/// The label displayed for the progress of an operation
+pub(crate) fn operation_label(id: &str) -> &str {
+    id.rsplit_once(OPERATION_NUMBER).map_or(id, |(label, _)| label)
+}
 
 #[derive(Default, Debug)]
 pub(crate) struct ProgressManager {
@@ -10,13 +39,21 @@ pub(crate) struct ProgressManager {
 }
 
 impl ProgressManager {
-    pub(crate) fn add(&mut self, id: String, label: String, max: usize) {
+    /// Add a progress, it can be cancelled by the user if a token is given
+    pub(crate) fn add(
+        &mut self,
+        id: String,
+        label: String,
+        max: usize,
+        cancellation: Option<CancellationToken>,
+    ) {
         self.progress.insert(
             id,
             Progress {
                 label,
                 current: 0,
                 max,
+                cancellation,
             },
         );
     }
@@ -52,6 +89,7 @@ pub(crate) struct Progress {
     pub(crate) label: String,
     pub(crate) current: usize,
     pub(crate) max: usize,
+    pub(crate) cancellation: Option<CancellationToken>,
 }
 
 impl Progress {
@@ -62,4 +100,19 @@ impl Progress {
             self.current as f32 / self.max as f32
         }
     }
+
+    /// Ask the operation to stop, returns false if it cannot be cancelled
+    pub(crate) fn cancel(&self) -> bool {
+        match &self.cancellation {
+            Some(cancellation) => {
+                cancellation.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub(crate) const fn is_cancellable(&self) -> bool {
+        self.cancellation.is_some()
+    }
 }
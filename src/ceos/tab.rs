@@ -0,0 +1,394 @@
+use crate::ceos::buffer::buffer::Buffer;
+use crate::ceos::buffer::follower::Follower;
+use crate::ceos::buffer::line_view::LineView;
+use crate::ceos::buffer::undo::BufferSnapshot;
+use crate::ceos::command;
+use crate::ceos::command::Command;
+use crate::ceos::command::search::Search;
+use crate::ceos::gui::overview::Overview;
+use crate::ceos::gui::searchpanel::SearchPanel;
+use crate::ceos::gui::textpane::textareaproperties::TextAreaProperties;
+use crate::ceos::tools::cancellation::CancellationToken;
+use crate::event::Event;
+use log::{debug, error, info, warn};
+use std::fs::File;
+use std::io;
+use std::io::{LineWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
+use std::thread;
+
+/// A buffer opened in a tab, with its own caret, selection, scroll, search and pending command
+#[derive(Debug)]
+pub(crate) struct Tab {
+    /// The unique id of the tab, used by the events coming from background threads
+    pub(crate) id: usize,
+    pub(crate) textarea_properties: TextAreaProperties,
+    pub(crate) command_buffer: String,
+    pub(crate) current_command: Option<Box<dyn Command + Send + Sync + 'static>>,
+    pub(crate) search_panel: SearchPanel,
+    /// The search results, highlights and errors shown next to the text
+    pub(crate) overview: Overview,
+    /// Cancels the search running in the background, if any
+    search_cancellation: Option<CancellationToken>,
+    /// The follower of the buffer file if the follow mode is active
+    pub(crate) follower: Option<Follower>,
+    /// The lines appended to the followed file or the stream while a command is running on the buffer
+    appended_lines: Vec<String>,
+    /// A command is running on the buffer in a background thread
+    command_running: bool,
+    /// The number of bytes read from the standard input while it is streamed
+    pub(crate) streamed: Option<usize>,
+    /// The id of the loading of the file, its lines are shown as they are loaded
+    pub(crate) loading: Option<usize>,
+    sender: Sender<Event>,
+}
+
+impl Tab {
+    pub(crate) fn new(id: usize, sender: Sender<Event>) -> Self {
+        Self {
+            id,
+            textarea_properties: TextAreaProperties::new(sender.clone()),
+            command_buffer: String::new(),
+            current_command: None,
+            search_panel: SearchPanel::new(sender.clone()),
+            overview: Overview::default(),
+            search_cancellation: None,
+            follower: None,
+            appended_lines: Vec::new(),
+            command_running: false,
+            streamed: None,
+            loading: None,
+            sender,
+        }
+    }
+
+    /// Returns true if the tab holds the empty buffer of a new tab, it can be reused to open a file
+    pub(crate) fn is_blank(&self) -> bool {
+        let buffer = &self.textarea_properties.buffer;
+        buffer.path.is_none()
+            && buffer.name.is_none()
+            && !buffer.dirty
+            && buffer.len() <= 1
+            && self.streamed.is_none()
+            && !self.command_running
+    }
+
+    /// The name displayed in the tab bar, a modified buffer is marked with a star
+    pub(crate) fn title(&self) -> String {
+        let buffer = &self.textarea_properties.buffer;
+        if buffer.dirty {
+            format!("{}*", buffer.name())
+        } else {
+            buffer.name()
+        }
+    }
+
+    pub(crate) fn set_buffer(&mut self, buffer: Buffer) {
+        self.reset_search();
+        self.stop_follow();
+        self.streamed = None;
+        self.loading = None;
+        self.textarea_properties.set_buffer(buffer);
+    }
+
+    /// Show the lines of a file while it is loaded, the buffer has no path until it is loaded
+    /// so the partial content cannot replace the file
+    pub(crate) fn start_loading(&mut self, loading: usize, path: &Path) {
+        let mut buffer = Buffer::new_stream_buffer(self.sender.clone());
+        buffer.name = Some(
+            path.file_name()
+                .unwrap_or(path.as_os_str())
+                .to_string_lossy()
+                .into_owned(),
+        );
+        self.set_buffer(buffer);
+        self.loading = Some(loading);
+    }
+
+    pub(crate) fn clear_command(&mut self) {
+        self.command_buffer = String::new();
+        self.current_command = None;
+    }
+
+    /// Prepare the search or the command typed in the command buffer
+    pub(crate) fn command_changed(&mut self) {
+        if !self.try_search() {
+            self.try_filter_command();
+        }
+    }
+
+    /// Start searching the text typed in the command buffer in a background thread, the
+    /// previous search is cancelled.
+    /// The search is given back by a `SearchCompleted` event.
+    pub(crate) fn try_search(&mut self) -> bool {
+        let Ok(mut search) = Search::try_from(self.command_buffer.as_str()) else {
+            return false;
+        };
+        let cancellation = CancellationToken::default();
+        if let Some(previous) = self.search_cancellation.replace(cancellation.clone()) {
+            previous.cancel();
+        }
+        // the compressed groups are shared with the snapshot, the buffer stays usable
+        let snapshot = self.textarea_properties.buffer.snapshot();
+        let view = self.textarea_properties.view().cloned();
+        let sender = self.sender.clone();
+        let id = self.id;
+        thread::spawn(move || {
+            if search.init(snapshot.line_groups(), view.as_ref(), &sender, &cancellation) {
+                let _ = sender.send(Event::SearchCompleted(id, search));
+            }
+        });
+        true
+    }
+
+    /// Show the results of the search once it is completed, and go to the first occurrence
+    pub(crate) fn search_completed(&mut self, search: Search) {
+        self.search_cancellation = None;
+        if let Some(goto) = search.goto() {
+            goto.execute(&mut self.textarea_properties);
+        }
+        self.search_panel.search = search;
+    }
+
+    /// Clear the search results, the running search is cancelled
+    pub(crate) fn reset_search(&mut self) {
+        if let Some(cancellation) = self.search_cancellation.take() {
+            cancellation.cancel();
+        }
+        self.search_panel.search.reset();
+    }
+
+    pub(crate) fn try_filter_command(&mut self) {
+        self.current_command = command::parse_command(self.command_buffer.as_str());
+
+        if let Some(command) = &self.current_command {
+            debug!("Found command {}", command);
+        }
+    }
+
+    pub(crate) fn execute_command(&mut self) {
+        if self.loading.is_some() {
+            warn!("Wait for the end of the loading to execute a command");
+            return;
+        }
+        if let Some(command) = self.current_command.take() {
+            info!("Execute command {}", command);
+            let mut tmp_buffer = Buffer::new_empty_buffer(self.sender.clone());
+            std::mem::swap(&mut tmp_buffer, &mut self.textarea_properties.buffer);
+            let views = self.textarea_properties.take_views();
+            let sender = self.sender.clone();
+            let id = self.id;
+            self.command_running = true;
+            std::thread::spawn(move || {
+                // cancelled from the progress bar of the command
+                let cancellation = CancellationToken::default();
+                let dirty = tmp_buffer.dirty;
+                let mut new_view = command.filter_view(&tmp_buffer, views.last(), &cancellation);
+                let mut snapshot = if new_view.is_none() {
+                    // keep the compressed content to undo the command without reloading the file
+                    let snapshot = tmp_buffer.snapshot();
+                    command.execute(&mut tmp_buffer, &cancellation);
+                    Some(snapshot)
+                } else {
+                    None
+                };
+                if cancellation.is_cancelled() {
+                    info!("Command {command} cancelled");
+                    // the partial result is dropped, the buffer is left as it was
+                    new_view = None;
+                    if let Some(before) = snapshot.take() {
+                        tmp_buffer.restore(before);
+                        tmp_buffer.dirty = dirty;
+                    }
+                }
+                sender
+                    .send(Event::CommandExecuted(id, tmp_buffer, views, new_view, snapshot))
+                    .unwrap();
+            });
+        } else if let Ok(command) = Event::try_from(self.command_buffer.as_str()) {
+            self.sender.send(command).unwrap();
+        }
+    }
+
+    /// Give back the buffer once the command is executed
+    pub(crate) fn command_executed(
+        &mut self,
+        buffer: Buffer,
+        views: Vec<LineView>,
+        new_view: Option<LineView>,
+        snapshot: Option<BufferSnapshot>,
+    ) {
+        self.reset_search();
+        self.textarea_properties
+            .set_filtered_buffer(buffer, views, new_view, snapshot);
+        self.command_running = false;
+        if !self.appended_lines.is_empty() {
+            let lines = std::mem::take(&mut self.appended_lines);
+            self.textarea_properties.append_lines(lines);
+        }
+    }
+
+    pub(crate) fn start_follow(&mut self) {
+        let Some(path) = self.textarea_properties.buffer.path.clone() else {
+            warn!("Cannot follow a buffer without file");
+            return;
+        };
+        let loaded_size = self.textarea_properties.buffer.loaded_size;
+        let encoding = self.textarea_properties.buffer.encoding;
+        // the followed file changes, its mapping could not be read anymore
+        self.textarea_properties.buffer.unmap();
+        match Follower::start(path.clone(), loaded_size, encoding, self.sender.clone()) {
+            Ok(follower) => self.follower = Some(follower),
+            Err(e) => warn!("Unable to follow {path:?}: {e}"),
+        }
+    }
+
+    pub(crate) fn stop_follow(&mut self) {
+        self.follower = None;
+        self.appended_lines.clear();
+    }
+
+    /// Append the lines read from the followed file or the stream to the buffer
+    pub(crate) fn append_lines(&mut self, lines: Vec<String>) {
+        if self.command_running {
+            // the buffer is used by the command, the lines are appended once it finishes
+            self.appended_lines.extend(lines);
+        } else {
+            self.textarea_properties.append_lines(lines);
+        }
+    }
+
+    pub(crate) fn save_file(&mut self) {
+        info!("save_file");
+        match &self.textarea_properties.buffer.path {
+            // a buffer without path is read from a stream and must be saved even if not modified
+            None => self.save_as(),
+            Some(_) if !self.textarea_properties.buffer.dirty => return,
+            Some(path) => self.save_to_path(path),
+        }
+        self.textarea_properties.buffer.dirty = false;
+        // the saved file has the content of the buffer, it is followed from its end
+        self.textarea_properties.buffer.loaded_size = None;
+    }
+
+    fn save_to_path(&self, path: &PathBuf) {
+        let mut total_size: usize = 0;
+        let mut lines: Vec<Vec<u8>> = Vec::new();
+        // only the visible lines are saved if the buffer is filtered
+        let view = self.textarea_properties.view();
+        let encoding = self.textarea_properties.buffer.encoding;
+        let eol = encoding.encode("\n").into_owned();
+        for group in self.textarea_properties.buffer.line_groups() {
+            let first_line = group.first_line();
+            let cow = group.lines();
+            for (i, line) in cow.as_ref().iter().enumerate() {
+                if view.is_some_and(|view| !view.contains(first_line + i)) {
+                    continue;
+                }
+                let bytes: Vec<u8> = encoding.encode(line.content()).into_owned();
+                total_size += bytes.len() + eol.len();
+                lines.push(bytes);
+            }
+        }
+
+        let sender = self.sender.clone();
+
+        let path = path.clone();
+        // the lines of a mapped buffer are read from the saved file, it is written aside then
+        // renamed so the mapping stays readable. As renaming replaces a symbolic link by a file
+        // and separates the hard links, the other buffers are written in place.
+        let replace = self.textarea_properties.buffer.is_mapped();
+        thread::spawn(move || {
+            // Démarrer la progression
+            let _ = sender.send(Event::BufferSavingStarted(path.clone(), total_size));
+            let written_path = if replace {
+                saving_path(&path)
+            } else {
+                path.clone()
+            };
+            let discard = || {
+                if replace {
+                    let _ = std::fs::remove_file(&written_path);
+                }
+            };
+            match File::create(&written_path) {
+                Ok(file) => {
+                    let mut file = LineWriter::new(file);
+                    let mut current: usize = 0;
+                    for bytes in lines.iter() {
+                        if let Err(err) = file.write_all(bytes) {
+                            error!("{err}");
+                            discard();
+                            let _ = sender.send(Event::BufferSaveFailed(path.clone()));
+                            return;
+                        }
+                        if let Err(err) = file.write_all(&eol) {
+                            error!("{err}");
+                            discard();
+                            let _ = sender.send(Event::BufferSaveFailed(path.clone()));
+                            return;
+                        }
+                        current += bytes.len() + eol.len();
+                        let _ =
+                            sender.send(Event::BufferSaving(path.clone(), current, total_size));
+                    }
+                    if replace && let Err(err) = replace_file(file, &written_path, &path) {
+                        error!("Unable to save file {path:?} because {err}");
+                        discard();
+                        let _ = sender.send(Event::BufferSaveFailed(path.clone()));
+                        return;
+                    }
+                    // Fin de progression
+                    let _ = sender.send(Event::BufferSaved(path.clone()));
+                }
+                Err(err) => {
+                    error!("Unable to save file {path:?} becaues {err}");
+                    let _ = sender.send(Event::BufferSaveFailed(path.clone()));
+                }
+            }
+        });
+    }
+
+    pub(crate) fn save_as(&mut self) {
+        info!("save as");
+        if self.loading.is_some() {
+            // the buffer of a file being loaded has no path, it is saved once loaded
+            warn!("Wait for the end of the loading to save the file");
+            return;
+        }
+        let mut dialog = rfd::FileDialog::new().set_directory("./");
+        if let Some(path) = &self.textarea_properties.buffer.path {
+            if let Some(parent) = path.parent() {
+                dialog = dialog.set_directory(parent);
+            }
+            if let Some(file_name) = path.file_name() {
+                dialog = dialog.set_file_name(file_name.to_string_lossy());
+            }
+        }
+
+        if let Some(path) = dialog.save_file() {
+            self.save_to_path(&path);
+            // the followed file is not the one of the buffer anymore
+            self.stop_follow();
+            self.textarea_properties.buffer.set_path(path);
+            self.textarea_properties.buffer.dirty = false;
+        }
+    }
+}
+
+/// The hidden file written next to the saved file before replacing it
+fn saving_path(path: &Path) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!(".{name}.ceos-save"))
+}
+
+/// Replace the file by the written one, keeping the permissions of the replaced file
+fn replace_file(mut file: LineWriter<File>, temp_path: &Path, path: &Path) -> io::Result<()> {
+    file.flush()?;
+    drop(file);
+    if let Ok(metadata) = std::fs::metadata(path) {
+        std::fs::set_permissions(temp_path, metadata.permissions())?;
+    }
+    std::fs::rename(temp_path, path)
+}
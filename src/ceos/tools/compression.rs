@@ -0,0 +1,275 @@
+use bzip2::bufread::MultiBzDecoder;
+use flate2::bufread::GzDecoder;
+use log::{info, warn};
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, Read, Seek, SeekFrom};
+use std::path::Path;
+use xz2::bufread::XzDecoder;
+
+const GZIP_MAGIC: &[u8] = &[0x1F, 0x8B];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xB5, 0x2F, 0xFD];
+const XZ_MAGIC: &[u8] = &[0xFD, b'7', b'z', b'X', b'Z', 0x00];
+const BZIP2_MAGIC: &[u8] = b"BZh";
+const LZ4_MAGIC: &[u8] = &[0x04, 0x22, 0x4D, 0x18];
+/// The longest magic number
+const MAGIC_LENGTH: usize = 6;
+/// The compression ratio assumed when the uncompressed size is not stored in the file
+const ESTIMATED_RATIO: usize = 10;
+/// The maximum length of a zstd frame header
+const ZSTD_HEADER_LENGTH: usize = 18;
+/// The length of the xz stream footer
+const XZ_FOOTER_LENGTH: usize = 12;
+
+/// The compression formats recognized by their magic number
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Compression {
+    None,
+    Gzip,
+    Zstd,
+    Xz,
+    Bzip2,
+    Lz4,
+}
+
+impl Compression {
+    /// Detect the compression from the first bytes of a reader, the position is restored after.
+    pub(crate) fn detect<R>(reader: &mut R) -> Self
+    where
+        R: Read + Seek,
+    {
+        let mut magic = Vec::with_capacity(MAGIC_LENGTH);
+        let start_pos = reader.stream_position().ok().unwrap_or(0);
+        let _ = reader
+            .by_ref()
+            .take(MAGIC_LENGTH as u64)
+            .read_to_end(&mut magic);
+        let _ = reader.seek(SeekFrom::Start(start_pos));
+        Self::from_magic(&magic)
+    }
+
+    pub(crate) fn from_magic(bytes: &[u8]) -> Self {
+        [
+            (GZIP_MAGIC, Compression::Gzip),
+            (ZSTD_MAGIC, Compression::Zstd),
+            (XZ_MAGIC, Compression::Xz),
+            (BZIP2_MAGIC, Compression::Bzip2),
+            (LZ4_MAGIC, Compression::Lz4),
+        ]
+        .into_iter()
+        .find(|(magic, _)| bytes.starts_with(magic))
+        .map_or(Compression::None, |(_, compression)| compression)
+    }
+
+    /// Wrap the reader with a streaming decoder
+    pub(crate) fn decoder<'a>(
+        self,
+        reader: impl BufRead + Send + 'a,
+    ) -> io::Result<Box<dyn Read + Send + 'a>> {
+        Ok(match self {
+            Compression::None => Box::new(reader),
+            Compression::Gzip => Box::new(GzDecoder::new(reader)),
+            Compression::Zstd => Box::new(zstd::Decoder::with_buffer(reader)?),
+            Compression::Xz => Box::new(XzDecoder::new_multi_decoder(reader)),
+            Compression::Bzip2 => Box::new(MultiBzDecoder::new(reader)),
+            Compression::Lz4 => Box::new(lz4::Decoder::new(reader)?),
+        })
+    }
+
+    /// A best-effort estimate of the uncompressed size of a file for the progress bar.
+    /// The size stored in the file is used when the format has one, otherwise a typical ratio
+    /// for logs is assumed.
+    pub(crate) fn uncompressed_size(self, path: &Path, file_size: usize) -> usize {
+        let stored_size = match self {
+            Compression::None => return file_size,
+            Compression::Gzip => gzip_uncompressed_size_fast(path).map(|size| Some(size as usize)),
+            Compression::Zstd => zstd_uncompressed_size(path),
+            Compression::Xz => xz_uncompressed_size(path),
+            Compression::Lz4 => lz4_uncompressed_size(path),
+            Compression::Bzip2 => Ok(None),
+        };
+        match stored_size {
+            Ok(Some(size)) => size,
+            Ok(None) => file_size.saturating_mul(ESTIMATED_RATIO),
+            Err(e) => {
+                warn!("Unable to read the uncompressed size of {path:?}: {e}");
+                file_size.saturating_mul(ESTIMATED_RATIO)
+            }
+        }
+    }
+}
+
+pub(crate) fn gzip_uncompressed_size_fast(path: &Path) -> io::Result<u32> {
+    let mut f = File::open(path)?;
+    f.seek(SeekFrom::End(-4))?;
+    let mut buf = [0u8; 4];
+    f.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf)) // ISIZE
+}
+
+/// The content size of the first frame, it is optional in the frame header
+fn zstd_uncompressed_size(path: &Path) -> io::Result<Option<usize>> {
+    let mut header = Vec::with_capacity(ZSTD_HEADER_LENGTH);
+    File::open(path)?
+        .take(ZSTD_HEADER_LENGTH as u64)
+        .read_to_end(&mut header)?;
+    Ok(zstd::zstd_safe::get_frame_content_size(&header)
+        .ok()
+        .flatten()
+        .map(|size| size as usize))
+}
+
+/// The content size of the lz4 frame, it is optional in the frame descriptor
+fn lz4_uncompressed_size(path: &Path) -> io::Result<Option<usize>> {
+    const CONTENT_SIZE_FLAG: u8 = 0x08;
+    // magic, FLG, BD, content size
+    let mut header = [0; 14];
+    File::open(path)?.read_exact(&mut header)?;
+    if header[4] & CONTENT_SIZE_FLAG == 0 {
+        return Ok(None);
+    }
+    let size = u64::from_le_bytes(header[6..14].try_into().expect("8 bytes"));
+    Ok(Some(size as usize))
+}
+
+/// Sum the uncompressed sizes of the blocks listed in the index of the last xz stream
+fn xz_uncompressed_size(path: &Path) -> io::Result<Option<usize>> {
+    let mut file = File::open(path)?;
+    let file_size = file.metadata()?.len();
+    let mut footer = [0; XZ_FOOTER_LENGTH];
+    file.seek(SeekFrom::End(-(XZ_FOOTER_LENGTH as i64)))?;
+    file.read_exact(&mut footer)?;
+    if &footer[10..] != b"YZ" {
+        info!("{path:?} has no xz footer");
+        return Ok(None);
+    }
+    let backward_size = u32::from_le_bytes(footer[4..8].try_into().expect("4 bytes"));
+    let index_size = (u64::from(backward_size) + 1) * 4;
+    if index_size > file_size.saturating_sub(XZ_FOOTER_LENGTH as u64) {
+        // a damaged footer, the index cannot be larger than the file
+        warn!("{path:?} has an invalid xz index size {index_size}");
+        return Ok(None);
+    }
+    let mut index = vec![0; index_size as usize];
+    file.seek(SeekFrom::End(
+        -((XZ_FOOTER_LENGTH as u64 + index_size) as i64),
+    ))?;
+    file.read_exact(&mut index)?;
+    Ok(parse_xz_index(&index))
+}
+
+/// The index starts with a zero indicator and the number of records, each record holds the
+/// unpadded and the uncompressed size of a block as variable length integers.
+fn parse_xz_index(index: &[u8]) -> Option<usize> {
+    let (&indicator, mut rest) = index.split_first()?;
+    if indicator != 0 {
+        return None;
+    }
+    let records = read_xz_varint(&mut rest)?;
+    let mut uncompressed = 0;
+    for _ in 0..records {
+        read_xz_varint(&mut rest)?;
+        uncompressed += read_xz_varint(&mut rest)?;
+    }
+    Some(uncompressed as usize)
+}
+
+fn read_xz_varint(bytes: &mut &[u8]) -> Option<u64> {
+    let mut value = 0;
+    for (i, byte) in bytes.iter().enumerate().take(9) {
+        value |= u64::from(byte & 0x7F) << (i * 7);
+        if byte & 0x80 == 0 {
+            *bytes = &bytes[i + 1..];
+            return Some(value);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use std::io::{BufReader, Cursor, Write};
+
+    const TEXT: &str = "first line\nsecond line\n";
+
+    fn compress(compression: Compression) -> Vec<u8> {
+        let text = TEXT.as_bytes();
+        match compression {
+            Compression::None => text.to_vec(),
+            Compression::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(text).unwrap();
+                encoder.finish().unwrap()
+            }
+            Compression::Zstd => zstd::bulk::compress(text, 0).unwrap(),
+            Compression::Xz => {
+                let mut encoder = xz2::write::XzEncoder::new(Vec::new(), 6);
+                encoder.write_all(text).unwrap();
+                encoder.finish().unwrap()
+            }
+            Compression::Bzip2 => {
+                let mut encoder =
+                    bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+                encoder.write_all(text).unwrap();
+                encoder.finish().unwrap()
+            }
+            Compression::Lz4 => {
+                let mut encoder = lz4::EncoderBuilder::new().build(Vec::new()).unwrap();
+                encoder.write_all(text).unwrap();
+                let (bytes, result) = encoder.finish();
+                result.unwrap();
+                bytes
+            }
+        }
+    }
+
+    #[rstest]
+    #[case(Compression::None)]
+    #[case(Compression::Gzip)]
+    #[case(Compression::Zstd)]
+    #[case(Compression::Xz)]
+    #[case(Compression::Bzip2)]
+    #[case(Compression::Lz4)]
+    fn test_detect_and_decode(#[case] compression: Compression) -> io::Result<()> {
+        let mut reader = Cursor::new(compress(compression));
+        assert_eq!(compression, Compression::detect(&mut reader));
+        assert_eq!(0, reader.position());
+        let mut text = String::new();
+        compression
+            .decoder(BufReader::new(reader))?
+            .read_to_string(&mut text)?;
+        assert_eq!(TEXT, text);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(Compression::Gzip)]
+    #[case(Compression::Zstd)]
+    #[case(Compression::Xz)]
+    fn test_uncompressed_size(#[case] compression: Compression) -> io::Result<()> {
+        let path = std::env::temp_dir().join(format!("ceos_size_{compression:?}"));
+        let bytes = compress(compression);
+        std::fs::write(&path, &bytes)?;
+        let size = compression.uncompressed_size(&path, bytes.len());
+        std::fs::remove_file(&path)?;
+        assert_eq!(TEXT.len(), size);
+        Ok(())
+    }
+
+    #[test]
+    fn test_xz_index_larger_than_file() -> io::Result<()> {
+        let path = std::env::temp_dir().join("ceos_size_xz_damaged");
+        let mut bytes = compress(Compression::Xz);
+        // the backward size of the footer
+        let footer = bytes.len() - XZ_FOOTER_LENGTH;
+        bytes[footer + 4..footer + 8].copy_from_slice(&u32::MAX.to_le_bytes());
+        std::fs::write(&path, &bytes)?;
+        let size = xz_uncompressed_size(&path);
+        std::fs::remove_file(&path)?;
+        assert_eq!(None, size?);
+        Ok(())
+    }
+}
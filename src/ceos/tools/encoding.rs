@@ -0,0 +1,334 @@
+use std::borrow::Cow;
+use std::fmt::Display;
+use std::io;
+use std::io::BufRead;
+
+/// The characters of the 0x80..0xA0 range in Windows-1252, the other bytes are the same as Latin-1.
+/// The undefined bytes are mapped to the control character of the same value like browsers do.
+const WINDOWS_1252: [char; 32] = [
+    '\u{20AC}', '\u{81}', '\u{201A}', '\u{192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{2C6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8D}', '\u{17D}', '\u{8F}',
+    '\u{90}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{2DC}', '\u{2122}', '\u{161}', '\u{203A}', '\u{153}', '\u{9D}', '\u{17E}', '\u{178}',
+];
+
+/// The number of bytes looked at to guess an encoding without byte order mark
+const GUESS_LENGTH: usize = 1024;
+
+/// The encodings of the text files.
+///
+/// The text is always decoded lossily: the invalid sequences are replaced by U+FFFD instead of
+/// failing the whole loading.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Encoding {
+    #[default]
+    Utf8,
+    Latin1,
+    Windows1252,
+    Utf16Le,
+    Utf16Be,
+}
+
+impl Encoding {
+    pub(crate) const ALL: [Encoding; 5] = [
+        Encoding::Utf8,
+        Encoding::Latin1,
+        Encoding::Windows1252,
+        Encoding::Utf16Le,
+        Encoding::Utf16Be,
+    ];
+
+    /// Returns the encoding given by the byte order mark and the length of the mark
+    pub(crate) fn from_bom(bytes: &[u8]) -> Option<(Encoding, usize)> {
+        if bytes.starts_with(&[0xEF, 0xBB, 0xBF]) {
+            Some((Encoding::Utf8, 3))
+        } else if bytes.starts_with(&[0xFF, 0xFE]) {
+            Some((Encoding::Utf16Le, 2))
+        } else if bytes.starts_with(&[0xFE, 0xFF]) {
+            Some((Encoding::Utf16Be, 2))
+        } else {
+            None
+        }
+    }
+
+    /// Guess the encoding of the beginning of a text without byte order mark.
+    /// A text with a lot of zeros on one side of the byte pairs is considered as UTF-16.
+    pub(crate) fn guess(bytes: &[u8]) -> Encoding {
+        let pairs = &bytes[..bytes.len().min(GUESS_LENGTH)];
+        let pair_count = pairs.len() / 2;
+        if pair_count == 0 {
+            return Encoding::Utf8;
+        }
+        let (even_zeros, odd_zeros) = pairs
+            .chunks_exact(2)
+            .fold((0, 0), |(even, odd), pair| {
+                (even + usize::from(pair[0] == 0), odd + usize::from(pair[1] == 0))
+            });
+        if odd_zeros * 2 > pair_count && even_zeros == 0 {
+            Encoding::Utf16Le
+        } else if even_zeros * 2 > pair_count && odd_zeros == 0 {
+            Encoding::Utf16Be
+        } else {
+            Encoding::Utf8
+        }
+    }
+
+    /// Returns true if the newline is a single byte that cannot be part of another character,
+    /// the lines can then be split without decoding the text
+    pub(crate) const fn has_byte_newline(&self) -> bool {
+        !matches!(self, Encoding::Utf16Le | Encoding::Utf16Be)
+    }
+
+    /// Returns the length of the complete lines at the beginning of the bytes,
+    /// None if they don't contain a newline
+    pub(crate) fn complete_lines_len(&self, bytes: &[u8]) -> Option<usize> {
+        match self {
+            Encoding::Utf16Le => bytes
+                .chunks_exact(2)
+                .rposition(|unit| unit == b"\n\0")
+                .map(|i| 2 * i + 2),
+            Encoding::Utf16Be => bytes
+                .chunks_exact(2)
+                .rposition(|unit| unit == b"\0\n")
+                .map(|i| 2 * i + 2),
+            _ => bytes.iter().rposition(|b| *b == b'\n').map(|i| i + 1),
+        }
+    }
+
+    /// Decode a text, the invalid sequences are replaced by U+FFFD
+    pub(crate) fn decode<'a>(&self, bytes: &'a [u8]) -> Cow<'a, str> {
+        match self {
+            Encoding::Utf8 => String::from_utf8_lossy(bytes),
+            Encoding::Latin1 => Cow::Owned(bytes.iter().map(|b| char::from(*b)).collect()),
+            Encoding::Windows1252 => Cow::Owned(
+                bytes
+                    .iter()
+                    .map(|b| match b {
+                        0x80..0xA0 => WINDOWS_1252[usize::from(b - 0x80)],
+                        _ => char::from(*b),
+                    })
+                    .collect(),
+            ),
+            Encoding::Utf16Le => Cow::Owned(decode_utf16(bytes, u16::from_le_bytes)),
+            Encoding::Utf16Be => Cow::Owned(decode_utf16(bytes, u16::from_be_bytes)),
+        }
+    }
+
+    /// Encode a text, the characters that cannot be encoded are replaced by '?'
+    pub(crate) fn encode<'a>(&self, text: &'a str) -> Cow<'a, [u8]> {
+        match self {
+            Encoding::Utf8 => Cow::Borrowed(text.as_bytes()),
+            Encoding::Latin1 => Cow::Owned(
+                text.chars()
+                    .map(|c| u8::try_from(c).unwrap_or(b'?'))
+                    .collect(),
+            ),
+            Encoding::Windows1252 => Cow::Owned(
+                text.chars()
+                    .map(|c| match u8::try_from(c) {
+                        Ok(b) if !(0x80..0xA0).contains(&b) => b,
+                        _ => WINDOWS_1252
+                            .iter()
+                            .position(|special| *special == c)
+                            .map_or(b'?', |i| 0x80 + i as u8),
+                    })
+                    .collect(),
+            ),
+            Encoding::Utf16Le => Cow::Owned(text.encode_utf16().flat_map(u16::to_le_bytes).collect()),
+            Encoding::Utf16Be => Cow::Owned(text.encode_utf16().flat_map(u16::to_be_bytes).collect()),
+        }
+    }
+}
+
+fn decode_utf16(bytes: &[u8], to_u16: fn([u8; 2]) -> u16) -> String {
+    let units = bytes.chunks_exact(2).map(|pair| to_u16([pair[0], pair[1]]));
+    let mut text: String = char::decode_utf16(units)
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect();
+    if !bytes.len().is_multiple_of(2) {
+        text.push(char::REPLACEMENT_CHARACTER);
+    }
+    text
+}
+
+impl TryFrom<&str> for Encoding {
+    type Error = ();
+
+    fn try_from(name: &str) -> Result<Self, Self::Error> {
+        match name.to_ascii_lowercase().as_str() {
+            "utf-8" | "utf8" => Ok(Encoding::Utf8),
+            "latin-1" | "latin1" | "iso-8859-1" => Ok(Encoding::Latin1),
+            "windows-1252" | "cp1252" => Ok(Encoding::Windows1252),
+            "utf-16le" | "utf16le" => Ok(Encoding::Utf16Le),
+            "utf-16be" | "utf16be" => Ok(Encoding::Utf16Be),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Display for Encoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Latin1 => "ISO-8859-1",
+            Encoding::Windows1252 => "Windows-1252",
+            Encoding::Utf16Le => "UTF-16LE",
+            Encoding::Utf16Be => "UTF-16BE",
+        })
+    }
+}
+
+/// Iterate over the lines of a text in the given encoding.
+/// The lines are terminated by "\n" or "\r\n" that are not included in the lines.
+pub(crate) struct LineReader<R> {
+    reader: R,
+    encoding: Encoding,
+    bytes: Vec<u8>,
+    /// The number of bytes read
+    read: usize,
+}
+
+impl<R: BufRead> LineReader<R> {
+    pub(crate) const fn new(reader: R, encoding: Encoding) -> Self {
+        Self {
+            reader,
+            encoding,
+            bytes: Vec::new(),
+            read: 0,
+        }
+    }
+
+    pub(crate) const fn read(&self) -> usize {
+        self.read
+    }
+
+    pub(crate) const fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Read the bytes of the next line including its terminator.
+    /// Returns false at the end of the text.
+    fn read_line_bytes(&mut self) -> io::Result<bool> {
+        self.bytes.clear();
+        loop {
+            let count = self.reader.read_until(b'\n', &mut self.bytes)?;
+            self.read += count;
+            if count == 0 || self.bytes.last() != Some(&b'\n') {
+                // end of the text
+                return Ok(!self.bytes.is_empty());
+            }
+            let eol = self.bytes.len() - 1;
+            match self.encoding {
+                Encoding::Utf16Le if eol.is_multiple_of(2) => {
+                    // the newline is the low byte of a code unit, the high byte must be zero
+                    let mut high = [0];
+                    let count = self.reader.read(&mut high)?;
+                    self.read += count;
+                    self.bytes.extend_from_slice(&high[..count]);
+                    if count == 0 || high[0] == 0 {
+                        return Ok(true);
+                    }
+                }
+                Encoding::Utf16Be if eol % 2 == 1 && self.bytes[eol - 1] == 0 => return Ok(true),
+                Encoding::Utf16Le | Encoding::Utf16Be => {}
+                _ => return Ok(true),
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for LineReader<R> {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.read_line_bytes() {
+            Ok(true) => {
+                let text = self.encoding.decode(&self.bytes);
+                let line = text.strip_suffix('\n').unwrap_or(&text);
+                let line = line.strip_suffix('\r').unwrap_or(line);
+                Some(Ok(line.to_owned()))
+            }
+            Ok(false) => None,
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn read_lines(bytes: &[u8], encoding: Encoding) -> Vec<String> {
+        LineReader::new(bytes, encoding)
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_invalid_utf8_is_replaced() {
+        assert_eq!(
+            vec!["a\u{FFFD}b", "c"],
+            read_lines(b"a\xFFb\r\nc", Encoding::Utf8)
+        );
+    }
+
+    #[rstest]
+    #[case(Encoding::Latin1, b"caf\xE9 \x80", "café \u{80}")]
+    #[case(Encoding::Windows1252, b"caf\xE9 \x80", "café €")]
+    fn test_decode_8_bits(#[case] encoding: Encoding, #[case] bytes: &[u8], #[case] text: &str) {
+        assert_eq!(text, encoding.decode(bytes));
+        assert_eq!(bytes, encoding.encode(text).as_ref());
+    }
+
+    #[rstest]
+    #[case(Encoding::Utf16Le)]
+    #[case(Encoding::Utf16Be)]
+    fn test_utf16_lines(#[case] encoding: Encoding) {
+        // U+010A contains a 0x0A byte that is not a newline
+        let text = "a\u{10A}b\r\n€\n\nlast";
+        let bytes = encoding.encode(text);
+        assert_eq!(
+            vec!["a\u{10A}b", "€", "", "last"],
+            read_lines(&bytes, encoding)
+        );
+    }
+
+    #[rstest]
+    #[case(Encoding::Utf8, b"a\nb\nc", Some(4))]
+    #[case(Encoding::Utf8, b"abc", None)]
+    #[case(Encoding::Utf16Le, b"a\x00\n\x00\n\x01b", Some(4))]
+    #[case(Encoding::Utf16Be, b"\x00a\x00\n\x01\n\x00b", Some(4))]
+    fn test_complete_lines_len(
+        #[case] encoding: Encoding,
+        #[case] bytes: &[u8],
+        #[case] expected: Option<usize>,
+    ) {
+        assert_eq!(expected, encoding.complete_lines_len(bytes));
+    }
+
+    #[rstest]
+    #[case(b"\xEF\xBB\xBFabc", Some((Encoding::Utf8, 3)))]
+    #[case(b"\xFF\xFEa\x00", Some((Encoding::Utf16Le, 2)))]
+    #[case(b"\xFE\xFF\x00a", Some((Encoding::Utf16Be, 2)))]
+    #[case(b"abc", None)]
+    fn test_from_bom(#[case] bytes: &[u8], #[case] expected: Option<(Encoding, usize)>) {
+        assert_eq!(expected, Encoding::from_bom(bytes));
+    }
+
+    #[rstest]
+    #[case(b"a\x00b\x00\n\x00", Encoding::Utf16Le)]
+    #[case(b"\x00a\x00b\x00\n", Encoding::Utf16Be)]
+    #[case(b"ab\n", Encoding::Utf8)]
+    fn test_guess(#[case] bytes: &[u8], #[case] expected: Encoding) {
+        assert_eq!(expected, Encoding::guess(bytes));
+    }
+
+    #[rstest]
+    #[case("latin1", Encoding::Latin1)]
+    #[case("CP1252", Encoding::Windows1252)]
+    #[case("utf-16le", Encoding::Utf16Le)]
+    fn test_try_from(#[case] name: &str, #[case] expected: Encoding) {
+        assert_eq!(Ok(expected), Encoding::try_from(name));
+    }
+}
@@ -1,3 +1,6 @@
+pub(crate) mod cancellation;
+pub(crate) mod compression;
+pub(crate) mod encoding;
 pub(crate) mod range;
 pub(crate) mod text_tool;
-pub(crate) mod misc_tool;
+pub(crate) mod timestamp;
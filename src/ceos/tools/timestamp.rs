@@ -0,0 +1,282 @@
+use chrono::{DateTime, Datelike, NaiveDateTime, NaiveTime, Timelike, Utc};
+use std::fmt::Display;
+use std::time::SystemTime;
+
+/// The ISO-8601 date times, with a comma or a dot before the fraction of seconds, or without
+/// seconds
+const ISO_8601: [&str; 6] = [
+    "%Y-%m-%dT%H:%M:%S,%3f",
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S,%3f",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M",
+];
+
+/// The date of the log4j `%d{DATE}` pattern
+const LOG4J: &str = "%d %b %Y %H:%M:%S,%3f";
+
+/// The syslog date doesn't have a year, the current one is put before it
+const SYSLOG: &str = "%Y %b %e %H:%M:%S";
+
+/// The number of digits of the epoch in milliseconds until 2286
+const EPOCH_MILLIS_DIGITS: usize = 13;
+
+const MILLIS_PER_DAY: i64 = 86_400_000;
+
+/// The format of the timestamps at the beginning of the log lines.
+///
+/// The timestamps are parsed as milliseconds since the epoch, the date times without time zone
+/// are considered as UTC so the lines of the files written in the same time zone can be compared.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) enum TimestampFormat {
+    /// Any of the known formats
+    #[default]
+    Auto,
+    /// `2024-01-31T12:00:00.123Z` or `2024-01-31 12:00:00,123`
+    Iso8601,
+    /// `Jan 31 12:00:00`
+    Syslog,
+    /// `1706702400123`
+    EpochMillis,
+    /// `31 Jan 2024 12:00:00,123`
+    Log4j,
+    /// A strftime format like `%d/%m/%Y %H:%M:%S`
+    Custom(String),
+}
+
+impl TimestampFormat {
+    /// Returns the timestamp in milliseconds at the beginning of the line.
+    /// The leading spaces and opening brackets are skipped.
+    pub(crate) fn parse(&self, line: &str) -> Option<i64> {
+        let text = line.trim_start_matches(|c: char| c == '[' || c.is_whitespace());
+        match self {
+            TimestampFormat::Auto => [
+                TimestampFormat::Iso8601,
+                TimestampFormat::Log4j,
+                TimestampFormat::Syslog,
+                TimestampFormat::EpochMillis,
+            ]
+            .iter()
+            .find_map(|format| format.parse(text)),
+            TimestampFormat::Iso8601 => ISO_8601.iter().find_map(|format| {
+                let (date_time, remainder) =
+                    NaiveDateTime::parse_and_remainder(text, format).ok()?;
+                let offset = parse_offset(remainder).unwrap_or(0);
+                Some(date_time.and_utc().timestamp_millis() - offset)
+            }),
+            TimestampFormat::Syslog => {
+                let year = DateTime::<Utc>::from(SystemTime::now()).year();
+                let text = format!("{year} {}", text.get(..15).unwrap_or(text));
+                parse_naive(&text, SYSLOG)
+            }
+            TimestampFormat::EpochMillis => {
+                let digits =
+                    text.len() - text.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+                if digits != EPOCH_MILLIS_DIGITS {
+                    return None;
+                }
+                text[..digits].parse().ok()
+            }
+            TimestampFormat::Log4j => parse_naive(text, LOG4J),
+            TimestampFormat::Custom(format) => DateTime::parse_and_remainder(text, format)
+                .map(|(date_time, _)| date_time.timestamp_millis())
+                .ok()
+                .or_else(|| parse_naive(text, format)),
+        }
+    }
+}
+
+fn parse_naive(text: &str, format: &str) -> Option<i64> {
+    NaiveDateTime::parse_and_remainder(text, format)
+        .map(|(date_time, _)| date_time.and_utc().timestamp_millis())
+        .ok()
+}
+
+/// Returns the time zone offset in milliseconds of `Z`, `+01:00` or `-0130`
+fn parse_offset(text: &str) -> Option<i64> {
+    if text.starts_with('Z') {
+        return Some(0);
+    }
+    let sign = match text.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+    let digits: String = text[1..]
+        .chars()
+        .take(5)
+        .filter(|c| *c != ':')
+        .take(4)
+        .collect();
+    if digits.len() != 4 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let hours: i64 = digits[..2].parse().ok()?;
+    let minutes: i64 = digits[2..].parse().ok()?;
+    Some(sign * (hours * 60 + minutes) * 60_000)
+}
+
+/// A bound of the time window
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum TimeBound {
+    /// A date time in milliseconds since the epoch
+    Timestamp(i64),
+    /// A time of any day in milliseconds since midnight
+    TimeOfDay(i64),
+}
+
+impl TimeBound {
+    /// Parse a time of day like `14:05` or `14:05:30.250`, or a timestamp in one of the known
+    /// formats. The end of the window includes the whole minute or second it is given with.
+    pub(crate) fn parse(text: &str, end: bool) -> Result<Self, ()> {
+        let text = text.trim();
+        for (format, precision) in [("%H:%M:%S%.f", 1_000), ("%H:%M", 60_000)] {
+            if let Ok(time) = NaiveTime::parse_from_str(text, format) {
+                let millis = i64::from(time.num_seconds_from_midnight()) * 1_000
+                    + i64::from(time.nanosecond() / 1_000_000);
+                let fraction = text.contains(['.', ',']);
+                return Ok(TimeBound::TimeOfDay(if end && !fraction {
+                    millis + precision - 1
+                } else {
+                    millis
+                }));
+            }
+        }
+        TimestampFormat::Auto
+            .parse(text)
+            .map(TimeBound::Timestamp)
+            .ok_or(())
+    }
+
+    /// Returns the timestamp of the bound, a time of day is the next one at or after the
+    /// reference timestamp
+    pub(crate) const fn resolve(self, reference: i64) -> i64 {
+        match self {
+            TimeBound::Timestamp(bound) => bound,
+            TimeBound::TimeOfDay(bound) => {
+                let day = reference - reference.rem_euclid(MILLIS_PER_DAY);
+                if day + bound < reference {
+                    day + MILLIS_PER_DAY + bound
+                } else {
+                    day + bound
+                }
+            }
+        }
+    }
+
+    /// Returns the bound and the timestamp in the same unit
+    pub(crate) const fn compare(self, timestamp: i64) -> (i64, i64) {
+        match self {
+            TimeBound::Timestamp(bound) => (bound, timestamp),
+            TimeBound::TimeOfDay(bound) => (bound, timestamp.rem_euclid(MILLIS_PER_DAY)),
+        }
+    }
+}
+
+impl Display for TimestampFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimestampFormat::Auto => write!(f, "auto"),
+            TimestampFormat::Iso8601 => write!(f, "iso"),
+            TimestampFormat::Syslog => write!(f, "syslog"),
+            TimestampFormat::EpochMillis => write!(f, "epoch"),
+            TimestampFormat::Log4j => write!(f, "log4j"),
+            TimestampFormat::Custom(format) => write!(f, "{format}"),
+        }
+    }
+}
+
+impl TryFrom<&str> for TimestampFormat {
+    type Error = ();
+
+    fn try_from(format: &str) -> Result<Self, Self::Error> {
+        match format.trim() {
+            "" | "auto" => Ok(TimestampFormat::Auto),
+            "iso" | "iso8601" => Ok(TimestampFormat::Iso8601),
+            "syslog" => Ok(TimestampFormat::Syslog),
+            "epoch" => Ok(TimestampFormat::EpochMillis),
+            "log4j" => Ok(TimestampFormat::Log4j),
+            format if format.contains('%') => Ok(TimestampFormat::Custom(format.to_string())),
+            _ => Err(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    /// 2024-01-31T12:00:00.123Z
+    const MILLIS: i64 = 1_706_702_400_123;
+
+    #[rstest]
+    #[case(
+        TimestampFormat::Iso8601,
+        "2024-01-31T12:00:00.123Z INFO start",
+        Some(MILLIS)
+    )]
+    #[case(
+        TimestampFormat::Iso8601,
+        "2024-01-31 12:00:00,123 INFO start",
+        Some(MILLIS)
+    )]
+    #[case(
+        TimestampFormat::Iso8601,
+        "[2024-01-31T13:30:00.123+01:30] start",
+        Some(MILLIS)
+    )]
+    #[case(TimestampFormat::Iso8601, "2024-01-31T12:00:00 start", Some(MILLIS - 123))]
+    #[case(TimestampFormat::Iso8601, "\tat com.example.Main", None)]
+    #[case(TimestampFormat::Log4j, "31 Jan 2024 12:00:00,123 INFO", Some(MILLIS))]
+    #[case(TimestampFormat::EpochMillis, "1706702400123 start", Some(MILLIS))]
+    #[case(TimestampFormat::EpochMillis, "1706702400 start", None)]
+    #[case(TimestampFormat::Custom("%d/%m/%Y %H:%M:%S%.f".into()), "31/01/2024 12:00:00.123 x", Some(MILLIS))]
+    #[case(TimestampFormat::Auto, "31 Jan 2024 12:00:00,123 INFO", Some(MILLIS))]
+    #[case(TimestampFormat::Auto, "2024-01-31T12:00:00.123Z", Some(MILLIS))]
+    #[case(TimestampFormat::Auto, "Caused by: java.io.IOException", None)]
+    fn test_parse(
+        #[case] format: TimestampFormat,
+        #[case] line: &str,
+        #[case] expected: Option<i64>,
+    ) {
+        assert_eq!(format.parse(line), expected);
+    }
+
+    #[test]
+    fn test_parse_syslog() {
+        let first = TimestampFormat::Syslog.parse("Jan  5 08:00:00 host sshd[42]: accepted");
+        let second = TimestampFormat::Auto.parse("Jan 15 08:00:00 host sshd[42]: closed");
+        assert_eq!(
+            Some(10 * 24 * 3_600_000),
+            second.zip(first).map(|(s, f)| s - f)
+        );
+    }
+
+    #[rstest]
+    #[case("14:03", true, TimeBound::TimeOfDay(14 * 3_600_000 + 3 * 60_000 + 59_999))]
+    #[case("14:03:12", false, TimeBound::TimeOfDay(14 * 3_600_000 + 3 * 60_000 + 12_000))]
+    #[case("14:03:12.5", true, TimeBound::TimeOfDay(14 * 3_600_000 + 3 * 60_000 + 12_500))]
+    #[case("2024-01-31T12:00:00.123", false, TimeBound::Timestamp(MILLIS))]
+    fn test_time_bound(#[case] text: &str, #[case] end: bool, #[case] expected: TimeBound) {
+        assert_eq!(Ok(expected), TimeBound::parse(text, end));
+    }
+
+    #[rstest]
+    #[case("12:30", MILLIS + 30 * 60_000 - 123)]
+    #[case("11:00", MILLIS + 23 * 3_600_000 - 123)]
+    fn test_resolve(#[case] text: &str, #[case] expected: i64) {
+        let bound = TimeBound::parse(text, false).unwrap();
+        assert_eq!(expected, bound.resolve(MILLIS));
+    }
+
+    #[rstest]
+    #[case("", Ok(TimestampFormat::Auto))]
+    #[case("syslog", Ok(TimestampFormat::Syslog))]
+    #[case("%H:%M", Ok(TimestampFormat::Custom("%H:%M".into())))]
+    #[case("unknown", Err(()))]
+    fn test_try_from(#[case] format: &str, #[case] expected: Result<TimestampFormat, ()>) {
+        assert_eq!(TimestampFormat::try_from(format), expected);
+    }
+}
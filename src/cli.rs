@@ -0,0 +1,164 @@
+use crate::ceos::tools::encoding::Encoding;
+use std::path::PathBuf;
+
+/// The file argument reading the standard input
+pub(crate) const STDIN: &str = "-";
+
+pub(crate) const USAGE: &str = "Usage: ceos [options] [+line|:line] [file]...
+
+The standard input is read if the file is - or if it is piped.
+
+Options:
+  +<line>, :<line>        go to the line once the file is loaded
+  -b, --batch             apply the commands to the file without opening a window
+  -c, --command <command> a command to execute, can be repeated
+  -o, --output <file>     the file written in batch mode, the standard output by default
+  -e, --encoding <name>   the encoding of the file: utf-8, latin1, windows-1252, utf-16le or utf-16be,
+                          detected by default
+  -h, --help              show this help";
+
+/// The command line arguments
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct Arguments {
+    pub(crate) batch: bool,
+    pub(crate) help: bool,
+    pub(crate) commands: Vec<String>,
+    /// The line to go to once the file is loaded
+    pub(crate) line: Option<usize>,
+    pub(crate) encoding: Option<Encoding>,
+    pub(crate) output: Option<PathBuf>,
+    pub(crate) files: Vec<PathBuf>,
+}
+
+impl Arguments {
+    /// Parse the arguments, the program name must not be included.
+    /// Returns a message explaining the problem if they are invalid.
+    pub(crate) fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut arguments = Self::default();
+        let mut args = args.into_iter();
+        let mut only_files = false;
+        while let Some(arg) = args.next() {
+            if only_files {
+                arguments.files.push(arg.into());
+                continue;
+            }
+            match arg.as_str() {
+                "-b" | "--batch" => arguments.batch = true,
+                "-h" | "--help" => arguments.help = true,
+                "-c" | "--command" => arguments.commands.push(Self::value(&arg, args.next())?),
+                "-o" | "--output" => {
+                    arguments.output = Some(Self::value(&arg, args.next())?.into())
+                }
+                "-e" | "--encoding" => {
+                    let name = Self::value(&arg, args.next())?;
+                    let encoding = Encoding::try_from(name.as_str())
+                        .map_err(|_| format!("Unknown encoding {name}"))?;
+                    arguments.encoding = Some(encoding);
+                }
+                "--" => only_files = true,
+                _ if let Some(line) = Self::line(&arg) => arguments.line = Some(line),
+                _ if arg.len() > 1 && arg.starts_with('-') => {
+                    return Err(format!("Unknown option {arg}"));
+                }
+                _ => arguments.files.push(arg.into()),
+            }
+        }
+        Ok(arguments)
+    }
+
+    /// Parse a `+N` or `:N` argument
+    fn line(arg: &str) -> Option<usize> {
+        arg.strip_prefix('+')
+            .or_else(|| arg.strip_prefix(':'))
+            .and_then(|line| line.parse().ok())
+    }
+
+    fn value(option: &str, value: Option<String>) -> Result<String, String> {
+        value.ok_or_else(|| format!("Missing value for {option}"))
+    }
+}
+
+/// Returns true if the standard input is a pipe or a redirected file
+#[cfg(unix)]
+pub(crate) fn is_stdin_piped() -> bool {
+    use std::os::fd::AsFd;
+    use std::os::unix::fs::FileTypeExt;
+    // a closed or /dev/null standard input is not read
+    std::io::stdin()
+        .as_fd()
+        .try_clone_to_owned()
+        .and_then(|fd| std::fs::File::from(fd).metadata())
+        .is_ok_and(|metadata| metadata.file_type().is_fifo() || metadata.is_file())
+}
+
+#[cfg(not(unix))]
+pub(crate) fn is_stdin_piped() -> bool {
+    use std::io::IsTerminal;
+    !std::io::stdin().is_terminal()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn parse(args: &[&str]) -> Result<Arguments, String> {
+        Arguments::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_batch() {
+        let arguments = parse(&[
+            "--batch",
+            "-c",
+            "filter ERROR",
+            "--command",
+            "l 10..30",
+            "input.log.gz",
+            "-o",
+            "out.log",
+        ])
+        .unwrap();
+        assert_eq!(
+            Arguments {
+                batch: true,
+                help: false,
+                commands: vec!["filter ERROR".to_string(), "l 10..30".to_string()],
+                line: None,
+                encoding: None,
+                output: Some("out.log".into()),
+                files: vec!["input.log.gz".into()],
+            },
+            arguments
+        );
+    }
+
+    #[test]
+    fn test_parse_files_after_separator() {
+        let arguments = parse(&["--", "-b", "a.log"]).unwrap();
+        assert!(!arguments.batch);
+        assert_eq!(
+            vec![PathBuf::from("-b"), PathBuf::from("a.log")],
+            arguments.files
+        );
+    }
+
+    #[rstest]
+    #[case("+42")]
+    #[case(":42")]
+    fn test_parse_line(#[case] arg: &str) {
+        let arguments = parse(&[arg, "app.log", "--command", "s error"]).unwrap();
+        assert_eq!(Some(42), arguments.line);
+        assert_eq!(vec![PathBuf::from("app.log")], arguments.files);
+        assert_eq!(vec!["s error".to_string()], arguments.commands);
+    }
+
+    #[rstest]
+    #[case(&["-c"])]
+    #[case(&["a.log", "--output"])]
+    #[case(&["--unknown"])]
+    #[case(&["--encoding", "ebcdic"])]
+    fn test_parse_invalid(#[case] args: &[&str]) {
+        assert!(parse(args).is_err());
+    }
+}
@@ -1,7 +1,17 @@
+use crate::ceos::buffer::archive::{Archive, ArchiveEntry};
 use crate::ceos::buffer::buffer::Buffer;
+use crate::ceos::buffer::line_group::LineGroup;
+use crate::ceos::buffer::line_view::LineView;
+use crate::ceos::buffer::undo::BufferSnapshot;
 use crate::ceos::command::direct::goto::Goto;
 use crate::ceos::command::direct::zoom::Zoom;
-use crate::event::Event::{BufferClosed, GotoLine, NewFont};
+use crate::ceos::command::search::Search;
+use crate::ceos::gui::highlight::HighlightCommand;
+use crate::ceos::gui::overview::Mark;
+use crate::ceos::gui::split::SplitOrientation;
+use crate::ceos::tools::cancellation::CancellationToken;
+use crate::ceos::tools::timestamp::TimestampFormat;
+use crate::event::Event::{BufferClosed, GotoLine, NewFont, PopView, Redo, ToggleFollow, Undo};
 use egui::FontId;
 use std::path::PathBuf;
 
@@ -9,28 +19,71 @@ use std::path::PathBuf;
 pub(crate) enum Event {
     /// BufferLoading(path, current, size)
     OpenFile(PathBuf),
-    BufferLoadingStarted(PathBuf, usize),
-    BufferLoading(PathBuf, usize, usize),
+    /// The loading of a file started (loading, path, size, token to cancel it)
+    BufferLoadingStarted(usize, PathBuf, usize, CancellationToken),
+    /// A file is loading (loading, current, size)
+    BufferLoading(usize, usize, usize),
+    /// Groups of lines of a file were loaded, they are shown before the end of the loading
+    /// (loading, path, complete groups)
+    BufferGroupsLoaded(usize, PathBuf, Vec<LineGroup>),
+    /// The loading of a file failed or was cancelled (loading)
+    BufferLoadingFailed(usize),
     // Saving progression events
     BufferSavingStarted(PathBuf, usize),
     BufferSaving(PathBuf, usize, usize),
     BufferSaved(PathBuf),
     BufferSaveFailed(PathBuf),
+    /// A buffer was loaded, it replaces the lines shown while its file was loaded or it is
+    /// opened in a new tab. Its loading id is the one of the loading events.
     BufferLoaded(Buffer),
+    /// An archive was opened, its entries must be chosen (path, archive, entries)
+    ArchiveOpened(PathBuf, Archive, Vec<ArchiveEntry>),
+    /// Lines were appended to a followed file (path, lines)
+    BufferAppended(PathBuf, Vec<String>),
+    /// Lines were read from a stream (lines, bytes read so far)
+    BufferStreaming(Vec<String>, usize),
+    /// The stream is closed
+    BufferStreamed,
+    /// Start or stop following the file of the buffer
+    ToggleFollow,
+    /// Choose files to merge by timestamp, the format of the options is used if none is given
+    MergeOpen(Option<TimestampFormat>),
+    /// A command was executed on the buffer of a tab
+    /// (tab, buffer, views, new view built by the command, snapshot of the buffer before the command)
+    CommandExecuted(usize, Buffer, Vec<LineView>, Option<LineView>, Option<BufferSnapshot>),
+    /// Remove the last filtered view
+    PopView,
+    /// Revert the last edit, filter or command
+    Undo,
+    /// Apply again the last undone edit
+    Redo,
+    /// Close the active tab
     BufferClosed,
     GotoLine(Goto),
+    /// Show the active tab in a second pane
+    Split(SplitOrientation),
+    /// Close the second pane
+    Unsplit,
+    /// Start or stop synchronizing the scroll of the two panes
+    ToggleScrollSync,
+    /// Add, remove or list the highlighted texts
+    Highlight(HighlightCommand),
+    /// The overview ruler of a tab was computed (tab, generation, marks)
+    OverviewComputed(usize, usize, Vec<Mark>),
+    /// The search started by a tab found its occurrences (tab, search)
+    SearchCompleted(usize, Search),
     NewFont(FontId),
     ShowHelp,
     SetCommand(String),
     /// Clear the current command
     ClearCommand,
-    /// An operation started (label, total size)
-    OperationStarted(String, usize),
-    /// An operation is progressing (label, current status)
+    /// An operation started (progress id, total size, token to cancel it if it can be)
+    OperationStarted(String, usize, Option<CancellationToken>),
+    /// An operation is progressing (progress id, current status)
     OperationProgress(String, usize),
-    /// An operation progress increment (label, amount)
+    /// An operation progress increment (progress id, amount)
     OperationIncrement(String, usize),
-    /// An operation finished (label)
+    /// An operation finished (progress id)
     OperationFinished(String),
 }
 
@@ -41,12 +94,36 @@ impl TryFrom<&str> for Event {
         if command == "?" {
             return Ok(Event::ShowHelp);
         }
-        if command.starts_with(':') {
+        if command.starts_with(':') || command.starts_with('@') {
             if let Ok(goto) = Goto::try_from(command) {
                 return Ok(GotoLine(goto));
             }
         } else if command == "close" {
             return Ok(BufferClosed);
+        } else if command == "unfilter" {
+            return Ok(PopView);
+        } else if command == "follow" {
+            return Ok(ToggleFollow);
+        } else if command == "undo" {
+            return Ok(Undo);
+        } else if command == "redo" {
+            return Ok(Redo);
+        } else if let Ok(orientation) = SplitOrientation::try_from(command) {
+            return Ok(Event::Split(orientation));
+        } else if command == "unsplit" {
+            return Ok(Event::Unsplit);
+        } else if command == "sync" {
+            return Ok(Event::ToggleScrollSync);
+        } else if let Ok(highlight) = HighlightCommand::try_from(command) {
+            return Ok(Event::Highlight(highlight));
+        } else if let Some(format) = command.strip_prefix("merge open")
+            && (format.is_empty() || format.starts_with(' '))
+        {
+            let format = match format.trim() {
+                "" => None,
+                format => Some(TimestampFormat::try_from(format)?),
+            };
+            return Ok(Event::MergeOpen(format));
         } else if command.starts_with("zoom ")
             && let Ok(zoom) = Zoom::try_from(command)
         {
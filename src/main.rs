@@ -1,16 +1,41 @@
 #![windows_subsystem = "windows"]
 extern crate core;
 
-use crate::ceos::Ceos;
+use crate::ceos::{Ceos, batch};
+use crate::cli::{Arguments, USAGE};
 
 mod ceos;
+mod cli;
 mod event;
 
 const INITIAL_WIDTH: f32 = 1024.0;
 const INITIAL_HEIGHT: f32 = 768.0;
 
 fn main() {
+    let arguments = Arguments::parse(std::env::args().skip(1));
+    if !matches!(&arguments, Ok(arguments) if !arguments.batch && !arguments.help) {
+        // the messages and the output of the batch mode are written to the terminal
+        attach_console();
+    }
     env_logger::init();
+    let arguments = match arguments {
+        Ok(arguments) => arguments,
+        Err(message) => {
+            eprintln!("{message}\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+    if arguments.help {
+        println!("{USAGE}");
+        return;
+    }
+    if arguments.batch {
+        if let Err(message) = batch::run(&arguments) {
+            eprintln!("{message}");
+            std::process::exit(1);
+        }
+        return;
+    }
     let native_options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_drag_and_drop(true)
@@ -20,6 +45,28 @@ fn main() {
     let _ = eframe::run_native(
         "Ceos",
         native_options,
-        Box::new(|_cc| Ok(Box::<Ceos>::default())),
+        Box::new(|_cc| {
+            let mut ceos = Ceos::default();
+            ceos.open_arguments(arguments);
+            Ok(Box::new(ceos))
+        }),
     );
 }
+
+/// Attach to the console of the parent process, the windows subsystem has none so the
+/// standard output and error would be lost
+#[cfg(windows)]
+fn attach_console() {
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    // SAFETY: AttachConsole has no precondition, it fails if the parent has no console
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+const fn attach_console() {}